@@ -18,26 +18,479 @@
 compile_error!("no target languages selected (select at least one \"translations-XXX\" feature)");
 
 use jmdict_enums::*;
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryInto;
-use std::io::Write;
 
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_ENTRYPACK");
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_SOURCE");
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_MIRRORS");
+    if let Some(path) = std::env::var_os("RUST_JMDICT_MIRRORS") {
+        println!("cargo:rerun-if-changed={}", path.to_string_lossy());
+    }
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_PURGE_CACHE");
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_MAX_ENTRIES");
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_TOPICS");
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_PARTS_OF_SPEECH");
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_FILTER");
+    if let Some(path) = std::env::var_os("RUST_JMDICT_FILTER") {
+        println!("cargo:rerun-if-changed={}", path.to_string_lossy());
+    }
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_JLPT_LIST");
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_JLPT_LEVEL");
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_SIZE_REPORT");
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_MAX_PAYLOAD_BYTES");
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_BUILD_CACHE");
+    if let Some(path) = std::env::var_os("RUST_JMDICT_JLPT_LIST") {
+        println!("cargo:rerun-if-changed={}", path.to_string_lossy());
+    }
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_RADKFILE");
+    if let Some(path) = std::env::var_os("RUST_JMDICT_RADKFILE") {
+        println!("cargo:rerun-if-changed={}", path.to_string_lossy());
+    }
+    println!("cargo:rerun-if-env-changed=RUST_JMDICT_KANJIVG");
+    if let Some(path) = std::env::var_os("RUST_JMDICT_KANJIVG") {
+        println!("cargo:rerun-if-changed={}", path.to_string_lossy());
+    }
+
+    //the spill file is only left behind by a failed run, and must not end up in the cache or in
+    //the fingerprint if this run restores the other files from the cache
+    let _ = std::fs::remove_file(path_to(DATA_SPILL_FILENAME));
 
-    let opts = jmdict_traverse::Options {
-        is_db_minimal: cfg!(feature = "db-minimal"),
-        with_uncommon: cfg!(feature = "scope-uncommon"),
-        with_archaic: cfg!(feature = "scope-archaic"),
+    let cache = BuildCache::from_env();
+    let creation_date = match cache.as_ref().and_then(BuildCache::restore) {
+        Some(creation_date) => creation_date,
+        None => {
+            let creation_date = build_payload();
+            if let Some(cache) = &cache {
+                cache.store(&creation_date);
+            }
+            creation_date
+        }
     };
+    println!("cargo:rustc-env=JMDICT_CREATION_DATE={}", creation_date);
+    println!(
+        "cargo:rustc-env=JMDICT_BUILD_FINGERPRINT={}",
+        compute_fingerprint()
+    );
+
+    if std::env::var_os("RUST_JMDICT_SIZE_REPORT").is_some() {
+        let path = path_to(SIZE_REPORT_FILENAME);
+        println!("cargo:warning=size report written to {}", path.display());
+    }
+    enforce_payload_budget();
+}
+
+///Parses the data file and writes all files into OUT_DIR, including the size report. Returns the
+///creation date of the JMdict.
+fn build_payload() -> String {
+    let mut opts = jmdict_traverse::Options::default();
+    opts.is_db_minimal = cfg!(feature = "db-minimal");
+    opts.with_uncommon = cfg!(feature = "scope-uncommon");
+    opts.with_archaic = cfg!(feature = "scope-archaic");
+    opts.with_names = cfg!(not(feature = "exclude-names"));
+    opts.with_sensitive = cfg!(not(feature = "exclude-sensitive"));
+    opts.topics = jmdict_traverse::enum_list_from_env("RUST_JMDICT_TOPICS");
+    opts.parts_of_speech = jmdict_traverse::enum_list_from_env("RUST_JMDICT_PARTS_OF_SPEECH");
+    opts.filter = jmdict_traverse::BuildFilter::from_env();
+    opts.jlpt = jmdict_traverse::JlptList::from_env();
+    opts.is_tolerant = cfg!(feature = "tolerant-parsing");
+
+    //use as many threads as Cargo allows us to (this is usually the number of CPUs)
+    let threads = std::env::var("NUM_JOBS")
+        .ok()
+        .and_then(|val| val.parse().ok())
+        .unwrap_or(1);
 
     let mut omni: OmniBuffer = Default::default();
+    omni.spill_data_to(&path_to(DATA_SPILL_FILENAME));
     if cfg!(not(feature = "db-empty")) {
-        jmdict_traverse::process_dictionary(&mut omni, opts);
+        jmdict_traverse::process_dictionary_sharded(&mut omni, opts, threads);
+    }
+    if cfg!(feature = "order-by-frequency") {
+        omni.order_by_frequency();
+    }
+    omni.share_substrings();
+
+    let kanji_index = omni.encode_kanji_index();
+    let headword_postings = std::mem::take(&mut omni.headword_postings);
+    let headword_length_index = encode_headword_length_index(&headword_postings);
+    let headword_index = omni.encode_text_index(headword_postings);
+    let reading_postings = std::mem::take(&mut omni.reading_postings);
+    let reading_index = omni.encode_text_index(reading_postings);
+    let loanword_postings = std::mem::take(&mut omni.loanword_postings);
+    let loanword_index = omni.encode_text_index(loanword_postings);
+    let stem_postings = std::mem::take(&mut omni.stem_postings);
+    let stem_index = omni.encode_text_index(stem_postings);
+    let tag_postings = std::mem::take(&mut omni.tag_postings);
+    let tag_index = omni.encode_text_index(tag_postings);
+    let bigram_index = omni.encode_bigram_index();
+    let radicals = if cfg!(feature = "radicals") {
+        jmdict_traverse::RadkFile::from_env()
+    } else {
+        None
+    };
+    let radical_index = omni.encode_radical_index(radicals.as_ref());
+    let strokes = if cfg!(feature = "stroke-order") {
+        jmdict_traverse::KanjiVgFile::from_env()
+    } else {
+        None
+    };
+    let stroke_index = omni.encode_stroke_index(strokes.as_ref());
+
+    //the second argument is the record size of each table (see `write_u32s()`)
+    write_u32s(&path_to("entry_offsets.dat"), 1, &omni.entry_offsets);
+    write_u32s(&path_to("kanji_index.dat"), 3, &kanji_index);
+    write_u32s(&path_to("headword_index.dat"), 4, &headword_index);
+    write_u32s(
+        &path_to("headword_length_index.dat"),
+        3,
+        &headword_length_index,
+    );
+    write_u32s(&path_to("reading_index.dat"), 4, &reading_index);
+    write_u32s(&path_to("loanword_index.dat"), 4, &loanword_index);
+    write_u32s(&path_to("stem_index.dat"), 4, &stem_index);
+    write_u32s(&path_to("tag_index.dat"), 4, &tag_index);
+    write_u32s(&path_to("bigram_index.dat"), 4, &bigram_index);
+    write_u32s(&path_to("radical_index.dat"), 4, &radical_index);
+    write_u32s(&path_to("stroke_index.dat"), 3, &stroke_index);
+    write_u32s(&path_to("unknown_codes.dat"), 2, &omni.unknown_code_refs);
+    omni.write_data(&path_to("payload.dat"));
+    write_data_file(&path_to("strings.txt"), omni.text.as_bytes());
+    std::fs::write(path_to("stats.rs"), omni.encode_stats()).unwrap();
+    std::fs::write(path_to(SIZE_REPORT_FILENAME), omni.encode_size_report()).unwrap();
+    omni.creation_date
+}
+
+///The report that is shown when `RUST_JMDICT_SIZE_REPORT` is set. It is always written (so that it
+///can be restored from the `BuildCache` later), but it is the only file in OUT_DIR that is not
+///embedded into the library.
+const SIZE_REPORT_FILENAME: &str = "size_report.json";
+
+///Where `OmniBuffer::data` is spilled to while the build is running. This file is removed again by
+///`OmniBuffer::write_data()`.
+const DATA_SPILL_FILENAME: &str = "payload.tmp";
+
+///Returns the paths of all files in OUT_DIR that get embedded into the library, sorted by name.
+fn embedded_files() -> Vec<std::path::PathBuf> {
+    let out_dir = std::env::var_os("OUT_DIR").unwrap();
+    let mut paths: Vec<_> = std::fs::read_dir(out_dir)
+        .unwrap()
+        .map(|dir_entry| dir_entry.unwrap().path())
+        .filter(|path| !path.ends_with(SIZE_REPORT_FILENAME))
+        .collect();
+    paths.sort();
+    paths
+}
+
+///If `RUST_JMDICT_MAX_PAYLOAD_BYTES` is set, fails the build when the embedded data files are
+///larger than that in total. This is meant for targets with a hard limit on the binary size, where
+///it is preferable to fail here instead of when flashing the firmware image.
+fn enforce_payload_budget() {
+    let budget: u64 = match std::env::var("RUST_JMDICT_MAX_PAYLOAD_BYTES") {
+        Ok(val) => val.parse().unwrap_or_else(|_| {
+            panic!("invalid value for RUST_JMDICT_MAX_PAYLOAD_BYTES: {:?}", val)
+        }),
+        Err(_) => return,
+    };
+
+    //stats.rs is compiled into code, so it does not count towards the payload
+    let mut sizes: Vec<(String, u64)> = embedded_files()
+        .into_iter()
+        .filter(|path| !path.ends_with("stats.rs"))
+        .map(|path| {
+            let size = std::fs::metadata(&path).unwrap().len();
+            let name = path.file_name().unwrap().to_string_lossy().into_owned();
+            (name, size)
+        })
+        .collect();
+    let total: u64 = sizes.iter().map(|(_, size)| size).sum();
+    if total <= budget {
+        return;
+    }
+
+    sizes.sort_by(|(_, size1), (_, size2)| size2.cmp(size1));
+    let largest: Vec<_> = sizes
+        .iter()
+        .take(3)
+        .map(|(name, size)| format!("{} has {} bytes", name, size))
+        .collect();
+    panic!(
+        "the generated payload has {} bytes, which exceeds RUST_JMDICT_MAX_PAYLOAD_BYTES={} ({}). \
+        Consider selecting fewer entries (e.g. with RUST_JMDICT_MAX_ENTRIES) or enabling the \
+        compact-payload or compressed-payload features. Set RUST_JMDICT_SIZE_REPORT=1 for a \
+        detailed breakdown.",
+        total,
+        budget,
+        largest.join(", "),
+    );
+}
+
+///Stores the files generated by `build_payload()` in a directory outside of OUT_DIR, so that they
+///can be reused when the build script runs again with the same inputs. This happens e.g. when
+///features are toggled that do not affect the payload (which gives a new OUT_DIR), or when
+///build.rs is touched without changing it.
+///
+///Each entry is a subdirectory named after a hash of all inputs (see `BuildCache::compute_key()`),
+///containing copies of the generated files and a file with the creation date.
+struct BuildCache {
+    dir: std::path::PathBuf,
+    key: String,
+}
+
+///How many entries are kept in the build cache. When a new entry is stored, the oldest entries
+///beyond this limit are removed.
+const BUILD_CACHE_CAPACITY: usize = 8;
+
+///Where the creation date is stored in each build cache entry.
+const CREATION_DATE_FILENAME: &str = "creation_date";
+
+///Features that do not affect the generated files, and thus not the build cache key. (Unknown
+///features are included in the key, so forgetting to list one here only makes the cache less
+///effective.)
+const FEATURES_IRRELEVANT_FOR_PAYLOAD: &[&str] = &[
+    "BUILTIN_DOWNLOAD",
+    "DEFAULT",
+    "DECODE_XZ",
+    "DECODE_ZSTD",
+    "DIACRITIC_FOLDING",
+    "FULL",
+    "RAND",
+    "RAYON",
+    "SERDE",
+];
+
+impl BuildCache {
+    ///Returns None unless `RUST_JMDICT_BUILD_CACHE` is set to the path of the cache directory. The
+    ///cache is opt-in since build scripts are not supposed to write outside of `OUT_DIR`.
+    fn from_env() -> Option<Self> {
+        //there is nothing to parse in this case, so the cache would only waste space
+        if cfg!(feature = "db-empty") {
+            return None;
+        }
+        let dir = std::env::var_os("RUST_JMDICT_BUILD_CACHE")?;
+        if dir.is_empty() {
+            return None;
+        }
+        Some(Self {
+            dir: dir.into(),
+            key: Self::compute_key(),
+        })
+    }
+
+    ///Computes a hash over everything that affects the generated files: the data file, the relevant
+    ///features and environment variables, and the code that processes the data file.
+    fn compute_key() -> String {
+        //This does not use SHA-256 like `compute_fingerprint()` since build scripts are compiled
+        //without optimizations, which makes sha2 too slow for hashing the whole data file on each
+        //build. SipHash (as used by the hasher from std) does much less work per byte, and is good
+        //enough for this purpose since the cache only holds a few entries at a time.
+        use std::collections::hash_map::DefaultHasher;
+        use std::hash::Hasher;
+        use std::io::BufRead;
+        let mut hasher = DefaultHasher::new();
+        let hash_item_header = |hasher: &mut DefaultHasher, name: &str, len: u64| {
+            hasher.write(name.as_bytes());
+            hasher.write(&[0]);
+            hasher.write(&len.to_le_bytes());
+        };
+
+        let data_file = jmdict_traverse::locate_data_file();
+        //on a cache hit, `OmniBuffer::notify_data_file_path()` does not get called
+        println!("cargo:rerun-if-changed={}", data_file.display());
+        //the data file is fed into the hasher in chunks since it can be quite large
+        let file = std::fs::File::open(&data_file).unwrap();
+        hash_item_header(&mut hasher, "data file", file.metadata().unwrap().len());
+        let mut reader = std::io::BufReader::with_capacity(1 << 16, file);
+        loop {
+            let chunk = reader.fill_buf().unwrap();
+            if chunk.is_empty() {
+                break;
+            }
+            hasher.write(chunk);
+            let len = chunk.len();
+            reader.consume(len);
+        }
+
+        let mut hash_item = |name: &str, contents: &[u8]| {
+            hash_item_header(&mut hasher, name, contents.len() as u64);
+            hasher.write(contents);
+        };
+
+        let mut features: Vec<String> = std::env::vars()
+            .filter_map(|(key, _)| Some(key.strip_prefix("CARGO_FEATURE_")?.to_owned()))
+            .filter(|name| !FEATURES_IRRELEVANT_FOR_PAYLOAD.contains(&name.as_str()))
+            .collect();
+        features.sort();
+        hash_item("features", features.join(",").as_bytes());
+
+        for var in &[
+            "RUST_JMDICT_SOURCE",
+            "RUST_JMDICT_MAX_ENTRIES",
+            "RUST_JMDICT_TOPICS",
+            "RUST_JMDICT_PARTS_OF_SPEECH",
+            "RUST_JMDICT_JLPT_LEVEL",
+        ] {
+            let val = std::env::var_os(var).unwrap_or_default();
+            hash_item(var, val.to_string_lossy().as_bytes());
+        }
+        for var in &[
+            "RUST_JMDICT_FILTER",
+            "RUST_JMDICT_JLPT_LIST",
+            "RUST_JMDICT_RADKFILE",
+            "RUST_JMDICT_KANJIVG",
+        ] {
+            let contents = std::env::var_os(var)
+                .and_then(|path| std::fs::read(path).ok())
+                .unwrap_or_default();
+            hash_item(var, &contents);
+        }
+
+        //When building from the repository, jmdict-traverse and jmdict-enums may have been changed
+        //without a version bump, so their sources need to be considered as well. Otherwise, they
+        //are in lockstep with the version of this crate.
+        hash_item("version", env!("CARGO_PKG_VERSION").as_bytes());
+        hash_item("build.rs", include_bytes!("build.rs"));
+        let manifest_dir =
+            std::path::PathBuf::from(std::env::var_os("CARGO_MANIFEST_DIR").unwrap());
+        for dir in &["jmdict-traverse", "jmdict-enums"] {
+            for path in list_files_recursively(&manifest_dir.join(dir)) {
+                let name = path.strip_prefix(&manifest_dir).unwrap().to_string_lossy();
+                hash_item(&name, &std::fs::read(&path).unwrap());
+            }
+        }
+
+        format!("{:016x}", hasher.finish())
+    }
+
+    ///If there is an entry for the current inputs, copies its files into OUT_DIR and returns the
+    ///creation date. Returns None if there is no such entry.
+    fn restore(&self) -> Option<String> {
+        let entry_dir = self.dir.join(&self.key);
+        let creation_date = std::fs::read_to_string(entry_dir.join(CREATION_DATE_FILENAME)).ok()?;
+        for path in list_files_recursively(&entry_dir) {
+            let name = path.file_name().unwrap().to_str().unwrap();
+            if name != CREATION_DATE_FILENAME {
+                std::fs::copy(&path, path_to(name)).unwrap();
+            }
+        }
+        Some(creation_date)
     }
 
-    write_u32s(&path_to("entry_offsets.dat"), &omni.entry_offsets);
-    write_u32s(&path_to("payload.dat"), &omni.data);
-    std::fs::write(&path_to("strings.txt"), &omni.text).unwrap();
+    ///Copies the files in OUT_DIR into a new entry for the current inputs. Errors are reported as
+    ///warnings only, since the build can succeed without the cache.
+    fn store(&self, creation_date: &str) {
+        if let Err(err) = self.try_store(creation_date) {
+            println!(
+                "cargo:warning=could not store generated files in build cache at {}: {}",
+                self.dir.display(),
+                err
+            );
+        }
+    }
+
+    fn try_store(&self, creation_date: &str) -> std::io::Result<()> {
+        //the entry is assembled under a temporary name and then renamed, so that concurrent builds
+        //never see an incomplete entry
+        let tmp_dir = self
+            .dir
+            .join(format!("{}.tmp{}", self.key, std::process::id()));
+        std::fs::create_dir_all(&tmp_dir)?;
+        let mut paths = embedded_files();
+        paths.push(path_to(SIZE_REPORT_FILENAME));
+        for path in paths {
+            std::fs::copy(&path, tmp_dir.join(path.file_name().unwrap()))?;
+        }
+        std::fs::write(tmp_dir.join(CREATION_DATE_FILENAME), creation_date)?;
+        if std::fs::rename(&tmp_dir, self.dir.join(&self.key)).is_err() {
+            //a concurrent build has stored the same entry already
+            std::fs::remove_dir_all(&tmp_dir)?;
+        }
+
+        //remove the oldest entries beyond the capacity (entries that are still being assembled
+        //are left alone since they belong to other builds)
+        let mut entries = Vec::new();
+        for dir_entry in std::fs::read_dir(&self.dir)? {
+            let dir_entry = dir_entry?;
+            if !dir_entry.file_name().to_string_lossy().contains(".tmp") {
+                entries.push((dir_entry.metadata()?.modified()?, dir_entry.path()));
+            }
+        }
+        entries.sort();
+        let excess = entries.len().saturating_sub(BUILD_CACHE_CAPACITY);
+        for (_, path) in entries.into_iter().take(excess) {
+            std::fs::remove_dir_all(path)?;
+        }
+        Ok(())
+    }
+}
+
+///Returns the paths of all files below the given directory, sorted by path. Returns an empty list
+///if the directory does not exist.
+fn list_files_recursively(dir: &std::path::Path) -> Vec<std::path::PathBuf> {
+    let mut result = Vec::new();
+    let mut dirs = vec![dir.to_owned()];
+    while let Some(dir) = dirs.pop() {
+        let read_dir = match std::fs::read_dir(&dir) {
+            Ok(read_dir) => read_dir,
+            Err(_) => continue,
+        };
+        for dir_entry in read_dir {
+            let path = dir_entry.unwrap().path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else {
+                result.push(path);
+            }
+        }
+    }
+    result.sort();
+    result
+}
+
+///Computes a SHA-256 hash over all files in OUT_DIR (i.e. everything that gets embedded into the
+///library), and returns it in hex encoding. Files are hashed in order of their names, each one
+///preceded by its name and length. The same algorithm is used in `src/test_fingerprint.rs`.
+fn compute_fingerprint() -> String {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    for path in embedded_files() {
+        let contents = std::fs::read(&path).unwrap();
+        hasher.update(path.file_name().unwrap().to_str().unwrap().as_bytes());
+        hasher.update([0]);
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+///Groups the headwords by their first character, and returns a table of records
+///`[first_char, length_mask, max_length]` (sorted by first_char). Bit `n - 1` of the length mask is
+///set if there is a headword with `n` characters, except that bit 31 stands for all lengths of 32
+///characters or more. Lengths are counted in chars, not in bytes. This allows segment() to only
+///probe lengths that can actually match.
+fn encode_headword_length_index(postings: &BTreeMap<String, Vec<u32>>) -> Vec<u32> {
+    let mut lengths = BTreeMap::<char, (u32, u32)>::new();
+    for text in postings.keys() {
+        let first_char = match text.chars().next() {
+            Some(c) => c,
+            None => continue,
+        };
+        let len = text.chars().count() as u32;
+        let (mask, max_length) = lengths.entry(first_char).or_default();
+        *mask |= 1 << (len.min(32) - 1);
+        *max_length = (*max_length).max(len);
+    }
+    let mut table = Vec::with_capacity(lengths.len() * 3);
+    for (first_char, (mask, max_length)) in lengths {
+        table.extend(&[first_char as u32, mask, max_length]);
+    }
+    table
 }
 
 fn path_to(filename: &str) -> std::path::PathBuf {
@@ -45,14 +498,148 @@ fn path_to(filename: &str) -> std::path::PathBuf {
     std::path::Path::new(&out_dir).join(filename)
 }
 
-fn write_u32s(path: &std::path::Path, vals: &[u32]) {
-    let f = std::fs::File::create(&path).unwrap();
-    let mut f = std::io::BufWriter::new(f);
-    for val in vals {
-        f.write_all(&val.to_ne_bytes()).unwrap();
+///Writes the given values in little-endian byte order. The payload format does not depend on the
+///byte order of the build host, since that may differ from the target when cross-compiling.
+///
+///With the `compact-payload` feature, values are instead written as LEB128 varints (preceded by
+///the `stride` itself). If `stride > 0`, each value is replaced by its difference to the value in
+///the same position of the previous record (in zigzag encoding). This works well for the indexes,
+///where consecutive records refer to neighboring strings and entries.
+fn write_u32s(path: &std::path::Path, stride: usize, vals: &[u32]) {
+    let mut w = U32Writer::create(path, stride);
+    w.write(vals);
+    w.finish();
+}
+
+///Like `write_u32s()`, but the values can be supplied in multiple parts, so that they do not all
+///need to be in memory at the same time.
+struct U32Writer {
+    file: DataFileWriter,
+    stride: usize,
+    ///The number of values written so far.
+    count: usize,
+    ///The last `stride` values written, at position `idx % stride` for each `idx`.
+    previous: Vec<u32>,
+    buf: Vec<u8>,
+}
+
+impl U32Writer {
+    fn create(path: &std::path::Path, stride: usize) -> Self {
+        let mut w = Self {
+            file: DataFileWriter::create(path),
+            stride,
+            count: 0,
+            previous: vec![0; stride],
+            buf: Vec::new(),
+        };
+        if cfg!(feature = "compact-payload") {
+            write_varint(&mut w.buf, stride as u32);
+        }
+        w
+    }
+
+    fn write(&mut self, vals: &[u32]) {
+        if cfg!(feature = "compact-payload") {
+            for &val in vals {
+                let val = if self.stride > 0 {
+                    let prev = &mut self.previous[self.count % self.stride];
+                    let delta = val.wrapping_sub(*prev) as i32;
+                    *prev = val;
+                    ((delta << 1) ^ (delta >> 31)) as u32
+                } else {
+                    val
+                };
+                write_varint(&mut self.buf, val);
+                self.count += 1;
+            }
+        } else {
+            for val in vals {
+                self.buf.extend(&val.to_le_bytes());
+            }
+        }
+        self.file.write(&self.buf);
+        self.buf.clear();
+    }
+
+    fn finish(self) {
+        let mut file = self.file;
+        file.write(&self.buf);
+        file.finish();
+    }
+}
+
+///Writes a file that gets embedded into the binary by src/payload.rs. With the
+///`compressed-payload` feature, the file contents are compressed with Zstandard.
+fn write_data_file(path: &std::path::Path, contents: &[u8]) {
+    let mut file = DataFileWriter::create(path);
+    file.write(contents);
+    file.finish();
+}
+
+///Like `write_data_file()`, but the contents can be supplied in multiple parts. Only the variant
+///for the selected features exists, so that zstd is only built when `compressed-payload` is enabled.
+enum DataFileWriter {
+    #[cfg(not(feature = "compressed-payload"))]
+    Plain(std::io::BufWriter<std::fs::File>),
+    #[cfg(feature = "compressed-payload")]
+    Compressed(zstd::Encoder<'static, std::io::BufWriter<std::fs::File>>),
+}
+
+impl DataFileWriter {
+    fn create(path: &std::path::Path) -> Self {
+        let file = std::io::BufWriter::new(std::fs::File::create(path).unwrap());
+        #[cfg(feature = "compressed-payload")]
+        return Self::Compressed(zstd::Encoder::new(file, 19).unwrap());
+        #[cfg(not(feature = "compressed-payload"))]
+        Self::Plain(file)
+    }
+
+    fn write(&mut self, contents: &[u8]) {
+        use std::io::Write;
+        match self {
+            #[cfg(not(feature = "compressed-payload"))]
+            Self::Plain(file) => file.write_all(contents).unwrap(),
+            #[cfg(feature = "compressed-payload")]
+            Self::Compressed(encoder) => encoder.write_all(contents).unwrap(),
+        }
+    }
+
+    fn finish(self) {
+        use std::io::Write;
+        let mut file = match self {
+            #[cfg(not(feature = "compressed-payload"))]
+            Self::Plain(file) => file,
+            #[cfg(feature = "compressed-payload")]
+            Self::Compressed(encoder) => encoder.finish().unwrap(),
+        };
+        file.flush().unwrap();
     }
 }
 
+fn write_varint(buf: &mut Vec<u8>, mut val: u32) {
+    while val >= 0x80 {
+        buf.push((val & 0x7F) as u8 | 0x80);
+        val >>= 7;
+    }
+    buf.push(val as u8);
+}
+
+///Reads the given number of little-endian u32 from the given file.
+fn read_u32s(file: &mut impl std::io::Read, count: usize) -> Vec<u32> {
+    let mut bytes = vec![0u8; count * 4];
+    file.read_exact(&mut bytes).unwrap();
+    bytes
+        .chunks_exact(4)
+        .map(|b| u32::from_le_bytes(b.try_into().unwrap()))
+        .collect()
+}
+
+///The file that OmniBuffer::data is spilled into, see `OmniBuffer::spill_data_to()`.
+struct DataSpill {
+    path: std::path::PathBuf,
+    file: std::io::BufWriter<std::fs::File>,
+}
+
 ///Helper type for references into OmniBuffer::data or OmniBuffer::text.
 ///Gets constructed as `(start, end).into()` in the respective OmniBuffer methods.
 struct StoredRef {
@@ -74,8 +661,59 @@ impl From<(usize, usize)> for StoredRef {
 #[derive(Default)]
 struct OmniBuffer {
     entry_offsets: Vec<u32>,
+    ///With the `order-by-frequency` feature: For each entry in `entry_offsets`, its priority score
+    ///(see `order_by_frequency()`).
+    entry_scores: Vec<PriorityScore>,
+    ///The part of the payload that has not been spilled to disk yet (see `spill_data_to()`). This
+    ///starts at offset `data_spilled` within the payload.
     data: Vec<u32>,
+    data_spill: Option<DataSpill>,
+    data_spilled: usize,
+    ///Rewrites the references into `text` after `share_substrings()`. Since the payload may have
+    ///been spilled to disk already, this is only applied when writing the payload.
+    text_relocation: Option<Relocation>,
     text: String,
+    ///For each string stored in `text`, its start offset. This is used by `push_str()` to store
+    ///each distinct string only once.
+    text_starts: HashMap<String, u32>,
+    ///For each kanji, the indexes (into `entry_offsets`) of all entries using that kanji in at
+    ///least one of their kanji elements.
+    kanji_postings: BTreeMap<char, Vec<u32>>,
+    ///For each text of a kanji element or reading element, the indexes (into `entry_offsets`) of
+    ///all entries having a kanji element or reading element with that text.
+    headword_postings: BTreeMap<String, Vec<u32>>,
+    ///For each text of a reading element, the indexes (into `entry_offsets`) of all entries having
+    ///a reading element with that text.
+    reading_postings: BTreeMap<String, Vec<u32>>,
+    ///For each language code of a loanword source, the indexes (into `entry_offsets`) of all
+    ///entries having a loanword source in that language.
+    loanword_postings: BTreeMap<String, Vec<u32>>,
+    ///For each stem (see `inflection_stem()`) of a kanji element or reading element, the indexes
+    ///(into `entry_offsets`) of all entries having an inflecting element with that stem.
+    stem_postings: BTreeMap<String, Vec<u32>>,
+    ///For each tag returned by `indexed_tags()`, the indexes (into `entry_offsets`) of all entries
+    ///having at least one sense with that tag.
+    tag_postings: BTreeMap<String, Vec<u32>>,
+    ///With the `bigram-index` feature: For each pair of consecutive characters in a kanji element
+    ///or reading element, the indexes (into `entry_offsets`) of all entries having a kanji element
+    ///or reading element containing that pair.
+    bigram_postings: BTreeMap<(char, char), Vec<u32>>,
+    ///The creation date of the JMdict snapshot, as reported by `notify_creation_date()`.
+    creation_date: String,
+    ///The total number of senses across all entries.
+    sense_count: usize,
+    ///For each gloss language code, the total number of glosses in that language.
+    gloss_counts: BTreeMap<&'static str, usize>,
+    ///For each gloss language code, the number of bytes that glosses in that language contribute
+    ///to the payload (see `encode_size_report()`).
+    gloss_sizes: BTreeMap<&'static str, usize>,
+    ///Like `gloss_sizes`, but for other parts of the payload that can be reduced through features
+    ///or filters (e.g. "priorities").
+    field_sizes: BTreeMap<&'static str, usize>,
+    ///The codes stored in `Unknown` variants of enums, see `intern_unknown_code()`.
+    unknown_codes: Vec<&'static str>,
+    ///For each entry in `unknown_codes`, the `[start, end]` of its text.
+    unknown_code_refs: Vec<u32>,
 }
 
 impl OmniBuffer {
@@ -85,10 +723,21 @@ impl OmniBuffer {
             return (0, 0).into();
         }
 
+        //many strings (e.g. common glosses or headwords repeated in cross-references) occur
+        //multiple times, but only need to be stored once
+        if let Some(&start) = self.text_starts.get(text) {
+            return StoredRef {
+                start,
+                end: start + text.len() as u32,
+            };
+        }
+
         let start = self.text.len();
         self.text.push_str(text);
         let end = self.text.len();
-        (start, end).into()
+        let r: StoredRef = (start, end).into();
+        self.text_starts.insert(text.into(), r.start);
+        r
     }
 
     pub fn push_data(&mut self, data: &[u32]) -> StoredRef {
@@ -97,11 +746,87 @@ impl OmniBuffer {
             return (0, 0).into();
         }
 
-        let start = self.data.len();
+        let start = self.data_spilled + self.data.len();
         self.data.extend(data);
         (start, start + data.len()).into()
     }
 
+    ///Makes this buffer move its payload into the given file whenever it grows large enough, to
+    ///keep memory usage in check. This is only used for the main OmniBuffer, not for shards.
+    pub fn spill_data_to(&mut self, path: &std::path::Path) {
+        let file = std::fs::File::create(path).unwrap();
+        self.data_spill = Some(DataSpill {
+            path: path.to_owned(),
+            file: std::io::BufWriter::new(file),
+        });
+    }
+
+    ///Moves the payload to disk if it has grown large enough (or, if `force` is set, in any case).
+    fn spill_data(&mut self, force: bool) {
+        //spilling in large chunks keeps the number of syscalls down
+        const THRESHOLD: usize = 1 << 20;
+        let spill = match &mut self.data_spill {
+            Some(spill) if force || self.data.len() >= THRESHOLD => spill,
+            _ => return,
+        };
+        use std::io::Write;
+        for val in &self.data {
+            spill.file.write_all(&val.to_le_bytes()).unwrap();
+        }
+        self.data_spilled += self.data.len();
+        self.data.clear();
+    }
+
+    ///Writes the payload into the given file (in the same way as `write_u32s()`). If the payload
+    ///was spilled to disk, it is streamed from there, and the spill file is removed afterwards.
+    ///This is also where the references rewritten by `share_substrings()` are fixed up.
+    pub fn write_data(&mut self, path: &std::path::Path) {
+        let r = self.text_relocation.take();
+        let relocate = |chunk: &mut [u32], base: usize, entry_offsets: &[u32]| {
+            if let Some(r) = &r {
+                let mut window = DataWindow { base, buf: chunk };
+                for &offset in entry_offsets {
+                    jmdict_traverse::RawEntry::relocate_one(&mut window, offset as usize, r);
+                }
+            }
+        };
+
+        //records in the payload have different sizes, so we cannot use delta encoding there
+        let mut w = U32Writer::create(path, 0);
+        self.spill_data(true);
+        match self.data_spill.take() {
+            None => {
+                let mut data = std::mem::take(&mut self.data);
+                relocate(&mut data, 0, &self.entry_offsets);
+                w.write(&data);
+            }
+            Some(DataSpill { path, file }) => {
+                drop(file.into_inner().unwrap());
+                let mut file = std::io::BufReader::new(std::fs::File::open(&path).unwrap());
+
+                //Each entry is encoded after all the arrays that it refers to, so the payload
+                //belonging to an entry starts right after the previous entry's record, and ends
+                //with its own record. After the last entry, there are only the posting lists of the
+                //indexes, which do not contain any references into `text`.
+                //(With `order-by-frequency`, `entry_offsets` is not ascending anymore.)
+                let mut offsets = self.entry_offsets.clone();
+                offsets.sort_unstable();
+                let mut pos = 0;
+                for offset in offsets {
+                    let end = offset as usize + jmdict_traverse::RawEntry::size();
+                    let mut chunk = read_u32s(&mut file, end - pos);
+                    relocate(&mut chunk, pos, &[offset]);
+                    w.write(&chunk);
+                    pos = end;
+                }
+                w.write(&read_u32s(&mut file, self.data_spilled - pos));
+                drop(file);
+                std::fs::remove_file(&path).unwrap();
+            }
+        }
+        w.finish();
+    }
+
     pub fn push_array<T: ToPayload>(&mut self, data: &[T]) -> StoredRef {
         //optimization: empty arrays don't require any work
         if data.is_empty() {
@@ -117,6 +842,315 @@ impl OmniBuffer {
 
         self.push_data(&repr)
     }
+
+    ///Rebuilds `self.text` such that strings which are a prefix or suffix of another string are
+    ///stored as part of that string instead of separately (e.g. "mother" is stored within
+    ///"mothers"), and rewrites all references into `self.text` accordingly (for references in the
+    ///payload, this is deferred until `write_data()`). This must be called after all entries have
+    ///been visited, but before the indexes are encoded.
+    pub fn share_substrings(&mut self) {
+        let mut strings: Vec<(String, u32)> =
+            std::mem::take(&mut self.text_starts).into_iter().collect();
+        let count = strings.len();
+
+        //If a string is a prefix of any other string, it is also a prefix of the string directly
+        //following it in lexicographic order, so we only need to compare neighbors. Going
+        //backwards, we can follow chains of prefixes to the longest string containing all of them.
+        strings.sort_unstable();
+        let mut prefix_host: Vec<usize> = (0..count).collect();
+        for idx in (1..count).rev() {
+            if strings[idx].0.starts_with(&strings[idx - 1].0) {
+                prefix_host[idx - 1] = prefix_host[idx];
+            }
+        }
+
+        //same for suffixes among the remaining strings, using the lexicographic order of the
+        //reversed strings (this can be done bytewise since each string starts on a char boundary)
+        let mut candidates: Vec<usize> = (0..count).filter(|&i| prefix_host[i] == i).collect();
+        candidates.sort_unstable_by(|&a, &b| {
+            let (a, b) = (strings[a].0.bytes().rev(), strings[b].0.bytes().rev());
+            a.cmp(b)
+        });
+        let mut suffix_host: Vec<usize> = (0..count).collect();
+        for pos in (1..candidates.len()).rev() {
+            let (idx, prev) = (candidates[pos], candidates[pos - 1]);
+            if strings[idx].0.ends_with(&strings[prev].0) {
+                suffix_host[prev] = suffix_host[idx];
+            }
+        }
+
+        //only the strings that are not contained in any other string are actually stored (in
+        //their original order, to keep related strings close together)
+        let mut roots: Vec<usize> = candidates
+            .into_iter()
+            .filter(|&i| suffix_host[i] == i)
+            .collect();
+        roots.sort_unstable_by_key(|&i| strings[i].1);
+        let mut new_starts = vec![0u32; count];
+        //the old text is not needed anymore since all strings are in `strings`
+        let mut text = String::with_capacity(std::mem::take(&mut self.text).len());
+        for idx in roots {
+            new_starts[idx] = text.len() as u32;
+            text.push_str(&strings[idx].0);
+        }
+        for idx in 0..count {
+            let host = suffix_host[idx];
+            if host != idx {
+                let offset = strings[host].0.len() - strings[idx].0.len();
+                new_starts[idx] = new_starts[host] + offset as u32;
+            }
+        }
+        for idx in 0..count {
+            let host = prefix_host[idx];
+            if host != idx {
+                new_starts[idx] = new_starts[host];
+            }
+        }
+
+        //rewrite all references using the same mechanism as `ShardVisitor::merge()` (for the
+        //payload, this happens in `write_data()`)
+        let r = Relocation {
+            data: 0,
+            text: (strings.iter().map(|(_, start)| *start))
+                .zip(new_starts.iter().copied())
+                .collect(),
+            unknown_codes: (0..self.unknown_codes.len() as u32).collect(),
+        };
+        for refs in self.unknown_code_refs.chunks_mut(2) {
+            let len = refs[1] - refs[0];
+            r.shift_text(&mut refs[0], len);
+            r.shift_text_end(&mut refs[1], len);
+        }
+        self.text_relocation = Some(r);
+        self.text = text;
+        self.text_starts = strings
+            .into_iter()
+            .map(|(string, _)| string)
+            .zip(new_starts)
+            .collect();
+    }
+
+    ///Pushes all posting lists of the kanji index into `self.data`, and returns a table of records
+    ///`[kanji, start, end]` (sorted by kanji) that refers to them.
+    pub fn encode_kanji_index(&mut self) -> Vec<u32> {
+        let postings = std::mem::take(&mut self.kanji_postings);
+        let mut table = Vec::with_capacity(postings.len() * 3);
+        for (kanji, entry_indexes) in postings {
+            let r = self.push_data(&entry_indexes);
+            table.extend(&[kanji as u32, r.start, r.end]);
+        }
+        table
+    }
+
+    ///Pushes all posting lists of the bigram index into `self.data`, and returns a table of records
+    ///`[first_char, second_char, start, end]` (sorted by both chars) that refers to them.
+    pub fn encode_bigram_index(&mut self) -> Vec<u32> {
+        let postings = std::mem::take(&mut self.bigram_postings);
+        let mut table = Vec::with_capacity(postings.len() * 4);
+        for ((c1, c2), entry_indexes) in postings {
+            let r = self.push_data(&entry_indexes);
+            table.extend(&[c1 as u32, c2 as u32, r.start, r.end]);
+        }
+        table
+    }
+
+    ///Pushes the kanji lists of all radicals from the given RADKFILE into `self.data`, and returns
+    ///a table of records `[radical, stroke_count, start, end]` (in the order of the RADKFILE) that
+    ///refers to them. Each kanji list contains the codepoints of the kanji in ascending order.
+    pub fn encode_radical_index(
+        &mut self,
+        radicals: Option<&jmdict_traverse::RadkFile>,
+    ) -> Vec<u32> {
+        let radicals = match radicals {
+            Some(radicals) => &radicals.radicals[..],
+            None => &[],
+        };
+        let mut table = Vec::with_capacity(radicals.len() * 4);
+        for radical in radicals {
+            let kanji: Vec<u32> = radical.kanji.iter().map(|&c| c as u32).collect();
+            let r = self.push_data(&kanji);
+            table.extend(&[
+                radical.text as u32,
+                radical.stroke_count.into(),
+                r.start,
+                r.end,
+            ]);
+        }
+        table
+    }
+
+    ///Pushes the strokes of all characters from the given KanjiVG file into `self.data` (as
+    ///`[text_start, text_end]` for the path data of each stroke), and returns a table of records
+    ///`[char, start, end]` (sorted by char) that refers to them.
+    pub fn encode_stroke_index(
+        &mut self,
+        strokes: Option<&jmdict_traverse::KanjiVgFile>,
+    ) -> Vec<u32> {
+        let characters = match strokes {
+            Some(strokes) => &strokes.characters[..],
+            None => &[],
+        };
+        let mut table = Vec::with_capacity(characters.len() * 3);
+        for character in characters {
+            let mut refs = Vec::with_capacity(character.strokes.len() * 2);
+            for path in &character.strokes {
+                let t = self.push_str(path);
+                refs.extend(&[t.start, t.end]);
+            }
+            let r = self.push_data(&refs);
+            table.extend(&[character.text as u32, r.start, r.end]);
+        }
+        table
+    }
+
+    ///Pushes all posting lists of an index keyed by strings (e.g. the headword index) into
+    ///`self.data`, and returns a table of records `[text_start, text_end, start, end]` (sorted by
+    ///text) that refers to them.
+    pub fn encode_text_index(&mut self, postings: BTreeMap<String, Vec<u32>>) -> Vec<u32> {
+        let mut table = Vec::with_capacity(postings.len() * 4);
+        for (text, entry_indexes) in postings {
+            let t = self.push_str(&text);
+            let r = self.push_data(&entry_indexes);
+            table.extend(&[t.start, t.end, r.start, r.end]);
+        }
+        table
+    }
+
+    ///Returns the index of the given code in the table of unknown enum codes, adding it to the table
+    ///if necessary. Enum values of the `Unknown` variant are encoded as `0x80000000 | index`.
+    pub fn intern_unknown_code(&mut self, code: &'static str) -> u32 {
+        if let Some(idx) = self.unknown_codes.iter().position(|&c| c == code) {
+            return idx as u32;
+        }
+        let r = self.push_str(code);
+        self.unknown_codes.push(code);
+        self.unknown_code_refs.extend(&[r.start, r.end]);
+        (self.unknown_codes.len() - 1) as u32
+    }
+
+    ///Adds the sizes of the given entry's parts to `gloss_sizes` and `field_sizes`. Each part is
+    ///counted as the size of its records plus the length of its strings, i.e. before strings are
+    ///deduplicated (see `push_str()`) and before any compression.
+    fn count_field_sizes(&mut self, entry: &jmdict_traverse::RawEntry) {
+        let str_size = |text: &&str| 4 * <&str>::size() + text.len();
+        let mut add = |field, size| *self.field_sizes.entry(field).or_default() += size;
+
+        if cfg!(not(feature = "exclude-priority")) {
+            add("priorities", 4 * (entry.k_ele.len() + entry.r_ele.len()));
+        }
+        for sense in &entry.sense {
+            add(
+                "cross_references",
+                sense.xref.iter().chain(&sense.ant).map(str_size).sum(),
+            );
+            for lsource in &sense.lsource {
+                let size = 4 * jmdict_traverse::RawLSource::size() + lsource.text.len();
+                add("loanword_sources", size);
+            }
+            for example in &sense.example {
+                let mut size = 4 * jmdict_traverse::RawExample::size();
+                size += example.text.len() + example.sentence.len();
+                for translation in &example.translations {
+                    size += 4 * jmdict_traverse::RawExampleTranslation::size();
+                    size += translation.text.len();
+                }
+                add("examples", size);
+            }
+        }
+        for gloss in entry.sense.iter().flat_map(|s| s.gloss.iter()) {
+            let size = 4 * jmdict_traverse::RawGloss::size() + gloss.text.len();
+            *self.gloss_sizes.entry(gloss.lang.code()).or_default() += size;
+        }
+    }
+
+    ///Renders the report for `RUST_JMDICT_SIZE_REPORT` as JSON. All sizes are in bytes.
+    pub fn encode_size_report(&self) -> String {
+        let gloss_count: usize = self.gloss_counts.values().sum();
+        let counts = vec![
+            ("entries", self.entry_offsets.len()),
+            ("senses", self.sense_count),
+            ("glosses", gloss_count),
+        ];
+
+        let files: Vec<(String, usize)> = embedded_files()
+            .into_iter()
+            .map(|path| {
+                let size = std::fs::metadata(&path).unwrap().len() as usize;
+                let name = path.file_name().unwrap().to_string_lossy().into_owned();
+                (name, size)
+            })
+            .collect();
+
+        let pairs =
+            |map: &BTreeMap<&'static str, usize>| map.iter().map(|(&k, &v)| (k, v)).collect();
+        let sections: Vec<(&str, Vec<(&str, usize)>)> = vec![
+            ("counts", counts),
+            ("gloss_counts", pairs(&self.gloss_counts)),
+            (
+                "file_sizes",
+                files.iter().map(|(k, v)| (k.as_str(), *v)).collect(),
+            ),
+            ("gloss_sizes", pairs(&self.gloss_sizes)),
+            ("field_sizes", pairs(&self.field_sizes)),
+        ];
+
+        //all keys are plain ASCII, so their Debug representation is also valid JSON
+        let mut lines = vec!["{".to_string()];
+        for (idx, (name, fields)) in sections.iter().enumerate() {
+            lines.push(format!("  {:?}: {{", name));
+            for (idx, (key, val)) in fields.iter().enumerate() {
+                let comma = if idx + 1 < fields.len() { "," } else { "" };
+                lines.push(format!("    {:?}: {}{}", key, val, comma));
+            }
+            let comma = if idx + 1 < sections.len() { "," } else { "" };
+            lines.push(format!("  }}{}", comma));
+        }
+        lines.push("}".into());
+        lines.join("\n") + "\n"
+    }
+
+    ///Renders the statistics collected while visiting entries as Rust code for `src/stats.rs`.
+    pub fn encode_stats(&self) -> String {
+        let gloss_count: usize = self.gloss_counts.values().sum();
+        let mut lines = vec![format!(
+            "const COUNTS: [usize; 3] = [{}, {}, {}];",
+            self.entry_offsets.len(),
+            self.sense_count,
+            gloss_count
+        )];
+        lines.push("static GLOSS_COUNTS_BY_LANGUAGE: &[(&str, usize)] = &[".into());
+        for (code, count) in &self.gloss_counts {
+            lines.push(format!("    ({:?}, {}),", code, count));
+        }
+        lines.push("];".into());
+        lines.join("\n") + "\n"
+    }
+
+    ///Reorders `entry_offsets` such that the most common entries come first (by descending
+    ///priority score, and by sequence number among entries with the same score). All posting
+    ///lists are rewritten to match, since they refer to entries by their index.
+    fn order_by_frequency(&mut self) {
+        let mut order: Vec<u32> = (0..self.entry_offsets.len() as u32).collect();
+        let scores = std::mem::take(&mut self.entry_scores);
+        order.sort_by_key(|&idx| (std::cmp::Reverse(scores[idx as usize]), idx));
+
+        let mut new_index = vec![0u32; order.len()];
+        for (new_idx, &old_idx) in order.iter().enumerate() {
+            new_index[old_idx as usize] = new_idx as u32;
+        }
+        self.entry_offsets = order
+            .iter()
+            .map(|&old_idx| self.entry_offsets[old_idx as usize])
+            .collect();
+
+        remap_postings(&mut self.kanji_postings, &new_index);
+        remap_postings(&mut self.headword_postings, &new_index);
+        remap_postings(&mut self.reading_postings, &new_index);
+        remap_postings(&mut self.loanword_postings, &new_index);
+        remap_postings(&mut self.stem_postings, &new_index);
+        remap_postings(&mut self.tag_postings, &new_index);
+        remap_postings(&mut self.bigram_postings, &new_index);
+    }
 }
 
 impl jmdict_traverse::Visitor for OmniBuffer {
@@ -124,15 +1158,250 @@ impl jmdict_traverse::Visitor for OmniBuffer {
         println!("cargo:rerun-if-changed={}", &path);
     }
 
+    fn notify_creation_date(&mut self, date: &str) {
+        self.creation_date = date.into();
+    }
+
     fn process_entry(&mut self, entry: &jmdict_traverse::RawEntry) {
+        let entry_index = self.entry_offsets.len() as u32;
+        for k_ele in &entry.k_ele {
+            for c in k_ele.keb.chars().filter(|&c| is_kanji(c)) {
+                add_posting(&mut self.kanji_postings, c, entry_index);
+            }
+        }
+        let kebs = entry.k_ele.iter().map(|k| k.keb);
+        let rebs = entry.r_ele.iter().map(|r| r.reb);
+        for text in kebs.clone().chain(rebs.clone()) {
+            add_posting(&mut self.headword_postings, text.into(), entry_index);
+        }
+        for text in rebs.clone() {
+            add_posting(&mut self.reading_postings, text.into(), entry_index);
+        }
+        if cfg!(feature = "bigram-index") {
+            for text in kebs.clone().chain(rebs.clone()) {
+                let chars: Vec<char> = text.chars().collect();
+                for pair in chars.windows(2) {
+                    add_posting(&mut self.bigram_postings, (pair[0], pair[1]), entry_index);
+                }
+            }
+        }
+        for lsource in entry.sense.iter().flat_map(|s| s.lsource.iter()) {
+            add_posting(
+                &mut self.loanword_postings,
+                lsource.lang.code().into(),
+                entry_index,
+            );
+        }
+        for tag in entry.sense.iter().flat_map(indexed_tags) {
+            add_posting(&mut self.tag_postings, tag.into(), entry_index);
+        }
+        for pos in entry.sense.iter().flat_map(|s| s.pos.iter()) {
+            for text in kebs.clone().chain(rebs.clone()) {
+                if let Some(stem) = inflection_stem(text, pos.code()) {
+                    add_posting(&mut self.stem_postings, stem.into(), entry_index);
+                }
+            }
+        }
+
+        self.sense_count += entry.sense.len();
+        for gloss in entry.sense.iter().flat_map(|s| s.gloss.iter()) {
+            *self.gloss_counts.entry(gloss.lang.code()).or_default() += 1;
+        }
+        self.count_field_sizes(entry);
+
         let size = jmdict_traverse::RawEntry::size();
         let mut repr = vec![0u32; size];
         entry.encode_one(self, &mut repr);
         let r = self.push_data(&repr);
         self.entry_offsets.push(r.start);
+        if cfg!(feature = "order-by-frequency") {
+            self.entry_scores.push(entry.priority_score());
+        }
+        self.spill_data(false);
+    }
+}
+
+impl jmdict_traverse::ShardVisitor for OmniBuffer {
+    fn merge(&mut self, mut shard: Self) {
+        //All references in the shard's payload are relative to the shard's own buffers, so they
+        //need to be rewritten to point into the respective part of our buffers. Strings are stored
+        //again through `push_str()`, in the same order as in the shard, so that strings already
+        //known to us are not stored twice, and the resulting text does not depend on how the
+        //entries were divided into shards.
+        let mut shard_strings: Vec<(u32, String)> = shard
+            .text_starts
+            .drain()
+            .map(|(text, start)| (start, text))
+            .collect();
+        shard_strings.sort_unstable();
+        let text = shard_strings
+            .into_iter()
+            .map(|(start, text)| (start, self.push_str(&text).start))
+            .collect();
+        let mut r = Relocation {
+            data: (self.data_spilled + self.data.len()).try_into().unwrap(),
+            text,
+            unknown_codes: Vec::new(),
+        };
+        r.unknown_codes = shard
+            .unknown_codes
+            .iter()
+            .zip(shard.unknown_code_refs.chunks(2))
+            .map(|(&code, refs)| {
+                if let Some(idx) = self.unknown_codes.iter().position(|&c| c == code) {
+                    return idx as u32;
+                }
+                //the shard has already stored the code in its text, so we can just refer to that
+                self.unknown_codes.push(code);
+                let (mut start, mut end) = (refs[0], refs[1]);
+                let len = end - start;
+                r.shift_text(&mut start, len);
+                r.shift_text_end(&mut end, len);
+                self.unknown_code_refs.extend(&[start, end]);
+                (self.unknown_codes.len() - 1) as u32
+            })
+            .collect();
+        let mut window = DataWindow {
+            base: 0,
+            buf: &mut shard.data,
+        };
+        for &offset in &shard.entry_offsets {
+            jmdict_traverse::RawEntry::relocate_one(&mut window, offset as usize, &r);
+        }
+        self.entry_offsets
+            .extend(shard.entry_offsets.iter().map(|offset| offset + r.data));
+        self.entry_scores.extend(shard.entry_scores);
+        self.data.extend(shard.data);
+        self.spill_data(false);
+
+        let entry_shift = (self.entry_offsets.len() - shard.entry_offsets.len()) as u32;
+        merge_postings(&mut self.kanji_postings, shard.kanji_postings, entry_shift);
+        merge_postings(
+            &mut self.headword_postings,
+            shard.headword_postings,
+            entry_shift,
+        );
+        merge_postings(
+            &mut self.reading_postings,
+            shard.reading_postings,
+            entry_shift,
+        );
+        merge_postings(
+            &mut self.loanword_postings,
+            shard.loanword_postings,
+            entry_shift,
+        );
+        merge_postings(&mut self.stem_postings, shard.stem_postings, entry_shift);
+        merge_postings(&mut self.tag_postings, shard.tag_postings, entry_shift);
+        merge_postings(
+            &mut self.bigram_postings,
+            shard.bigram_postings,
+            entry_shift,
+        );
+
+        self.sense_count += shard.sense_count;
+        for (code, count) in shard.gloss_counts {
+            *self.gloss_counts.entry(code).or_default() += count;
+        }
+        for (code, size) in shard.gloss_sizes {
+            *self.gloss_sizes.entry(code).or_default() += size;
+        }
+        for (field, size) in shard.field_sizes {
+            *self.field_sizes.entry(field).or_default() += size;
+        }
+    }
+}
+
+///Appends the posting lists of a shard (see `ShardVisitor::merge()`) to those of the main buffer.
+fn merge_postings<K: Ord>(
+    postings: &mut BTreeMap<K, Vec<u32>>,
+    shard_postings: BTreeMap<K, Vec<u32>>,
+    entry_shift: u32,
+) {
+    for (key, entry_indexes) in shard_postings {
+        let shifted = entry_indexes.into_iter().map(|idx| idx + entry_shift);
+        postings.entry(key).or_default().extend(shifted);
     }
 }
 
+///Rewrites all entry indexes in the given posting lists through the given mapping (see
+///`OmniBuffer::order_by_frequency()`). Lookups require the posting lists to stay sorted.
+fn remap_postings<K: Ord>(postings: &mut BTreeMap<K, Vec<u32>>, new_index: &[u32]) {
+    for entry_indexes in postings.values_mut() {
+        for idx in entry_indexes.iter_mut() {
+            *idx = new_index[*idx as usize];
+        }
+        entry_indexes.sort_unstable();
+    }
+}
+
+///Returns the tags of this sense that are indexed in `OmniBuffer.tag_postings`. The same strings
+///are used for lookups in `src/lookup.rs`.
+fn indexed_tags<'a>(
+    sense: &'a jmdict_traverse::RawSense,
+) -> impl Iterator<Item = &'static str> + 'a {
+    let pos_tags = sense.pos.iter().filter_map(|pos| match pos {
+        PartOfSpeech::Expression => Some("pos:exp"),
+        _ => None,
+    });
+    let misc_tags = sense.misc.iter().filter_map(|info| match info {
+        SenseInfo::Proverb => Some("misc:proverb"),
+        SenseInfo::Yojijukugo => Some("misc:yoji"),
+        _ => None,
+    });
+    pos_tags.chain(misc_tags)
+}
+
+///Adds an entry to the posting list for the given key.
+fn add_posting<K: Ord>(postings: &mut BTreeMap<K, Vec<u32>>, key: K, entry_index: u32) {
+    let postings = postings.entry(key).or_default();
+    //entries are visited in order, so we only need to look at the last posting to avoid duplicates
+    if postings.last() != Some(&entry_index) {
+        postings.push(entry_index);
+    }
+}
+
+///For words that inflect, returns the part of the given kanji element or reading element that
+///stays the same in all regular conjugated forms, based on the given part of speech.
+fn inflection_stem<'a>(text: &'a str, pos_code: &str) -> Option<&'a str> {
+    let ending = match pos_code {
+        "v1" | "v1-s" | "vk" => "る",
+        "v5aru" | "v5r" | "v5r-i" => "る",
+        "v5b" => "ぶ",
+        "v5g" => "ぐ",
+        "v5k" | "v5k-s" => "く",
+        "v5m" => "む",
+        "v5n" => "ぬ",
+        "v5s" => "す",
+        "v5t" => "つ",
+        "v5u" | "v5u-s" => "う",
+        "vs-i" | "vs-s" => "する",
+        "vz" => "ずる",
+        //nouns taking suru (e.g. 勉強) are conjugated by appending forms of する
+        "vs" => "",
+        //NOTE: For adj-ix, only the spellings with "よい" or "良い" are considered, since the
+        //spelling with "いい" only occurs in the dictionary form.
+        "adj-i" | "adj-ix" => "い",
+        _ => return None,
+    };
+    let stem = text.strip_suffix(ending)?;
+    if stem.is_empty() || (pos_code == "adj-ix" && text.ends_with("いい")) {
+        None
+    } else {
+        Some(stem)
+    }
+}
+
+///Whether the given character is a kanji, i.e. a CJK ideograph.
+fn is_kanji(c: char) -> bool {
+    matches!(c as u32,
+        0x3400..=0x4DBF     //CJK Unified Ideographs Extension A
+        | 0x4E00..=0x9FFF   //CJK Unified Ideographs
+        | 0xF900..=0xFAFF   //CJK Compatibility Ideographs
+        | 0x20000..=0x2FA1F //CJK Unified Ideographs Extension B and later, plus supplements
+    )
+}
+
 //Like omni.push_array(), but does not push the resulting array just yet.
 fn push_array<T: ToPayload>(buf: &mut Vec<u32>, omni: &mut OmniBuffer, array: &[T]) -> u32 {
     if !array.is_empty() {
@@ -155,6 +1424,81 @@ trait ToPayload {
 
     ///Encode one item of this type into the given preallocated buffer of length `Self::size()`.
     fn encode_one(&self, omni: &mut OmniBuffer, buf: &mut [u32]);
+
+    ///Given the encoding of one item of this type at `data[pos..(pos + Self::size())]`, rewrites all
+    ///references contained within it (recursively) as described by `r`. This is the inverse of
+    ///`encode_one()` in the sense that it needs to understand the exact same layout.
+    fn relocate_one(_data: &mut DataWindow, _pos: usize, _r: &Relocation) {}
+}
+
+///Describes how the payload of a shard needs to be rewritten when merging it into the main
+///OmniBuffer. See `ShardVisitor::merge()`.
+struct Relocation {
+    ///This is added to all references into `OmniBuffer::data`.
+    data: u32,
+    ///Maps the start of each string in the shard's text to the start of the same string in
+    ///`OmniBuffer::text`.
+    text: HashMap<u32, u32>,
+    ///Maps indexes into the shard's `unknown_codes` to indexes into the main `unknown_codes`.
+    unknown_codes: Vec<u32>,
+}
+
+impl Relocation {
+    ///Rewrites a reference into `OmniBuffer::data`, unless it refers to an empty array (those are
+    ///always encoded as `(0, 0)`).
+    fn shift_data(&self, start: &mut u32, len: u32) {
+        if len > 0 {
+            *start += self.data;
+        }
+    }
+
+    ///Rewrites a reference into `OmniBuffer::text`, unless it refers to an empty string (those are
+    ///always encoded as `(0, 0)`). Flags in the upper bits of `start` are preserved since the text
+    ///offsets themselves always fit into the lower 28 bits.
+    fn shift_text(&self, start: &mut u32, len: u32) {
+        if len > 0 {
+            let flags = *start & 0xF0000000;
+            *start = flags | self.text[&(*start & 0x0FFFFFFF)];
+        }
+    }
+
+    ///Like `shift_text()`, but for the end of a reference into `OmniBuffer::text`.
+    fn shift_text_end(&self, end: &mut u32, len: u32) {
+        if len > 0 {
+            *end = self.text[&(*end - len)] + len;
+        }
+    }
+}
+
+///A part of `OmniBuffer::data` that is being relocated. It is indexed like the whole payload, but
+///only `data[base..(base + buf.len())]` needs to be in memory, which is enough to relocate entries
+///one at a time (see `OmniBuffer::write_data()`).
+struct DataWindow<'a> {
+    base: usize,
+    buf: &'a mut [u32],
+}
+
+impl std::ops::Index<usize> for DataWindow<'_> {
+    type Output = u32;
+
+    fn index(&self, pos: usize) -> &u32 {
+        &self.buf[pos - self.base]
+    }
+}
+
+impl std::ops::IndexMut<usize> for DataWindow<'_> {
+    fn index_mut(&mut self, pos: usize) -> &mut u32 {
+        &mut self.buf[pos - self.base]
+    }
+}
+
+///Relocates all items of an array that was encoded with `push_array()` and occupies
+///`data[start..end]`.
+fn relocate_array<T: ToPayload>(data: &mut DataWindow, start: u32, end: u32, r: &Relocation) {
+    let size = T::size();
+    for pos in (start as usize..end as usize).step_by(size) {
+        T::relocate_one(data, pos, r);
+    }
 }
 
 //NOTE: It would be really nice to just do `impl ToPayload for T where T: EnumPayload`, but this
@@ -166,8 +1510,18 @@ macro_rules! enum_to_payload {
                 1
             }
 
-            fn encode_one(&self, _omni: &mut OmniBuffer, buf: &mut [u32]) {
-                buf[0] = self.to_u32();
+            fn encode_one(&self, omni: &mut OmniBuffer, buf: &mut [u32]) {
+                buf[0] = match self.unknown_code() {
+                    Some(code) => 0x80000000 | omni.intern_unknown_code(code),
+                    None => self.to_u32(),
+                };
+            }
+
+            fn relocate_one(data: &mut DataWindow, pos: usize, r: &Relocation) {
+                if data[pos] & 0x80000000 != 0 {
+                    let idx = data[pos] & 0x7FFFFFFF;
+                    data[pos] = 0x80000000 | r.unknown_codes[idx as usize];
+                }
             }
         }
     };
@@ -180,6 +1534,27 @@ enum_to_payload!(SenseTopic);
 enum_to_payload!(SenseInfo);
 enum_to_payload!(Dialect);
 
+///Encodes a short array of enum values into the lower 14 bits of a u32, so that it can be stored
+///inline in the record of the parent object instead of in a separate array. Each value takes 7 bits
+///and is stored as `code + 1`, so that 0 means "no value". Bit 15 is always set to distinguish the
+///result from an array offset. Returns None if the array is empty or too long, or if it contains
+///codes that do not fit (this includes unknown codes, which are not worth the extra complexity).
+///
+///The inverse of this function is `EnumRange::inline()` in src/payload.rs.
+fn encode_inline_enums<T: EnumPayload>(array: &[T]) -> Option<u32> {
+    if array.is_empty() || array.len() > 2 {
+        return None;
+    }
+    let mut packed = 0x8000;
+    for (idx, value) in array.iter().enumerate() {
+        if value.unknown_code().is_some() || value.to_u32() >= 0x7F {
+            return None;
+        }
+        packed |= (value.to_u32() + 1) << (7 * idx);
+    }
+    Some(packed)
+}
+
 impl ToPayload for jmdict_traverse::RawEntry<'_> {
     fn size() -> usize {
         4
@@ -191,6 +1566,7 @@ impl ToPayload for jmdict_traverse::RawEntry<'_> {
         //`push_data()` them all at once. We then encode that StoredRef, plus offsets to split the
         //encoded array back into its constituents. Since each encoded array is rather short, the
         //offsets fit into a single byte, so we can encode both (plus self.ent_seq) in a single u32.
+        //Sequence numbers fit into 24 bits, so the top byte of the last u32 holds the JLPT level.
         //
         //Compared to the naive layout as 3 StoredRef + 1 u32 (28 bytes), we save 12 bytes per Sense.
 
@@ -203,39 +1579,119 @@ impl ToPayload for jmdict_traverse::RawEntry<'_> {
         buf[0] = r.start;
         buf[1] = r.end;
         buf[2] = offset1 + (offset2 << 16);
-        buf[3] = self.ent_seq;
+        assert!(self.ent_seq < 0x01000000);
+        buf[3] = self.ent_seq | (u32::from(self.jlpt_level.unwrap_or(0)) << 24);
+    }
+
+    fn relocate_one(data: &mut DataWindow, pos: usize, r: &Relocation) {
+        let (start, end) = (data[pos], data[pos + 1]);
+        let offset1 = start + (data[pos + 2] & 0xFFFF);
+        let offset2 = start + (data[pos + 2] >> 16);
+        relocate_array::<jmdict_traverse::RawKanjiElement>(data, start, offset1, r);
+        relocate_array::<jmdict_traverse::RawReadingElement>(data, offset1, offset2, r);
+        relocate_array::<jmdict_traverse::RawSense>(data, offset2, end, r);
+        r.shift_data(&mut data[pos], end - start);
+        r.shift_data(&mut data[pos + 1], end - start);
     }
 }
 
 impl ToPayload for jmdict_traverse::RawKanjiElement<'_> {
     fn size() -> usize {
-        5
+        //the priority goes last, so that it can be left out without changing the other offsets
+        if cfg!(feature = "exclude-priority") {
+            4
+        } else {
+            5
+        }
     }
 
     fn encode_one(&self, omni: &mut OmniBuffer, buf: &mut [u32]) {
-        buf[0] = self.ke_pri.to_u32();
         let r = omni.push_str(self.keb);
-        buf[1] = r.start;
-        buf[2] = r.end;
-        let r = omni.push_array(&self.ke_inf);
-        buf[3] = r.start;
-        buf[4] = r.end;
+        buf[0] = r.start;
+        buf[1] = r.end;
+        //Most kanji elements have at most one info, so those are usually inlined to save the
+        //indirection. `omni.data` is significantly shorter than 2^31 values, so the highest bit
+        //of `buf[2]` tells the two layouts apart.
+        if let Some(packed) = encode_inline_enums(&self.ke_inf) {
+            buf[2] = 0x80000000 | packed;
+            buf[3] = 0;
+        } else {
+            let r = omni.push_array(&self.ke_inf);
+            buf[2] = r.start;
+            buf[3] = r.end;
+        }
+        if cfg!(not(feature = "exclude-priority")) {
+            buf[4] = self.ke_pri.to_u32();
+        }
+    }
+
+    fn relocate_one(data: &mut DataWindow, pos: usize, r: &Relocation) {
+        let text_len = data[pos + 1] - data[pos];
+        r.shift_text(&mut data[pos], text_len);
+        r.shift_text_end(&mut data[pos + 1], text_len);
+        if data[pos + 2] & 0x80000000 != 0 {
+            return;
+        }
+        let (start, end) = (data[pos + 2], data[pos + 3]);
+        relocate_array::<KanjiInfo>(data, start, end, r);
+        r.shift_data(&mut data[pos + 2], end - start);
+        r.shift_data(&mut data[pos + 3], end - start);
     }
 }
 
 impl ToPayload for jmdict_traverse::RawReadingElement<'_> {
     fn size() -> usize {
-        5
+        //same as for RawKanjiElement above
+        if cfg!(feature = "exclude-priority") {
+            4
+        } else {
+            5
+        }
     }
 
     fn encode_one(&self, omni: &mut OmniBuffer, buf: &mut [u32]) {
-        buf[0] = self.re_pri.to_u32();
         let r = omni.push_str(self.reb);
-        buf[1] = r.start;
-        buf[2] = r.end;
-        let r = omni.push_array(&self.re_inf);
-        buf[3] = r.start;
-        buf[4] = r.end;
+        //`omni.text` is significantly shorter than 2^28 bytes, so we can shove the boolean into the
+        //highest bit of the offset value (same as for RawLSource below)
+        buf[0] = r.start | if self.re_nokanji { 0x80000000 } else { 0 };
+        buf[1] = r.end;
+
+        //Same idea as for RawSense below: We concatenate both member arrays and encode the start
+        //of the result, plus the offset of the second array and the total length (both of which
+        //easily fit into 16 bits) in a single u32. If the infos can be inlined (same as for
+        //RawKanjiElement above), they take the place of the offset, which is recognizable by its
+        //bit 15 being set.
+        let mut dbuf = Vec::new();
+        let offset = match encode_inline_enums(&self.re_inf) {
+            Some(packed) => packed,
+            None => {
+                let offset = push_array(&mut dbuf, omni, &self.re_inf);
+                assert!(offset < 0x8000, "too many infos in reading element");
+                offset
+            }
+        };
+        push_array(&mut dbuf, omni, &self.re_restr);
+        let r = omni.push_data(&dbuf);
+        buf[2] = r.start;
+        buf[3] = offset + ((r.end - r.start) << 16);
+        if cfg!(not(feature = "exclude-priority")) {
+            buf[4] = self.re_pri.to_u32();
+        }
+    }
+
+    fn relocate_one(data: &mut DataWindow, pos: usize, r: &Relocation) {
+        let text_len = data[pos + 1] - (data[pos] & 0x0FFFFFFF);
+        r.shift_text(&mut data[pos], text_len);
+        r.shift_text_end(&mut data[pos + 1], text_len);
+        let start = data[pos + 2];
+        let offset = match data[pos + 3] & 0xFFFF {
+            packed if packed & 0x8000 != 0 => start,
+            len => start + len,
+        };
+        let end = start + (data[pos + 3] >> 16);
+        relocate_array::<ReadingInfo>(data, start, offset, r);
+        relocate_array::<&str>(data, offset, end, r);
+        r.shift_data(&mut data[pos + 2], end - start);
     }
 }
 
@@ -251,7 +1707,7 @@ impl ToPayload for jmdict_traverse::RawSense<'_> {
         //encoded array back into its constituents. Since each encoded array is rather short, the
         //offsets fit into a single byte, so we can encode four at a time in a single u32.
         //
-        //Compared to the naive layout as 11 StoredRef (88 bytes), we save 68 bytes per Sense.
+        //Compared to the naive layout as 12 StoredRef (96 bytes), we save 76 bytes per Sense.
 
         let mut dbuf = Vec::new();
         let offset1 = push_array(&mut dbuf, omni, &self.stagk);
@@ -264,29 +1720,68 @@ impl ToPayload for jmdict_traverse::RawSense<'_> {
         let offset8 = push_array(&mut dbuf, omni, &self.s_inf);
         let offset9 = push_array(&mut dbuf, omni, &self.lsource);
         let offset10 = push_array(&mut dbuf, omni, &self.dial);
+        let offset11 = push_array(&mut dbuf, omni, &self.example);
+        //glosses go last since there can be well over 100 of them with all translations enabled,
+        //which would not fit into the 8-bit offsets
         push_array(&mut dbuf, omni, &self.gloss);
+        assert!(offset11 < 256, "too much data in sense");
+
+        //the sense number is encoded in the last free byte
+        assert!(self.number < 256, "too many senses in entry");
 
         let r = omni.push_data(&dbuf);
         buf[0] = r.start;
         buf[1] = r.end;
         buf[2] = offset1 + (offset2 << 8) + (offset3 << 16) + (offset4 << 24);
         buf[3] = offset5 + (offset6 << 8) + (offset7 << 16) + (offset8 << 24);
-        buf[4] = offset9 + (offset10 << 8);
+        buf[4] = offset9 + (offset10 << 8) + (offset11 << 16) + (self.number << 24);
+    }
+
+    fn relocate_one(data: &mut DataWindow, pos: usize, r: &Relocation) {
+        let (start, end) = (data[pos], data[pos + 1]);
+        let offset = |idx: usize| start + ((data[pos + 2 + idx / 4] >> (8 * (idx % 4))) & 0xFF);
+        let offsets = [
+            start,
+            offset(0),
+            offset(1),
+            offset(2),
+            offset(3),
+            offset(4),
+            offset(5),
+            offset(6),
+            offset(7),
+            offset(8),
+            offset(9),
+            offset(10),
+            end,
+        ];
+        relocate_array::<&str>(data, offsets[0], offsets[1], r);
+        relocate_array::<&str>(data, offsets[1], offsets[2], r);
+        relocate_array::<PartOfSpeech>(data, offsets[2], offsets[3], r);
+        relocate_array::<&str>(data, offsets[3], offsets[4], r);
+        relocate_array::<&str>(data, offsets[4], offsets[5], r);
+        relocate_array::<SenseTopic>(data, offsets[5], offsets[6], r);
+        relocate_array::<SenseInfo>(data, offsets[6], offsets[7], r);
+        relocate_array::<&str>(data, offsets[7], offsets[8], r);
+        relocate_array::<jmdict_traverse::RawLSource>(data, offsets[8], offsets[9], r);
+        relocate_array::<Dialect>(data, offsets[9], offsets[10], r);
+        relocate_array::<jmdict_traverse::RawExample>(data, offsets[10], offsets[11], r);
+        relocate_array::<jmdict_traverse::RawGloss>(data, offsets[11], offsets[12], r);
+        r.shift_data(&mut data[pos], end - start);
+        r.shift_data(&mut data[pos + 1], end - start);
     }
 }
 
 impl ToPayload for jmdict_traverse::RawLSource<'_> {
     fn size() -> usize {
-        4
+        3
     }
 
     fn encode_one(&self, omni: &mut OmniBuffer, buf: &mut [u32]) {
         let r = omni.push_str(self.text);
         buf[0] = r.start;
         buf[1] = r.end;
-        let r = omni.push_str(self.lang);
-        buf[2] = r.start;
-        buf[3] = r.end;
+        buf[2] = self.lang.to_u32();
         //`omni.text` is significantly shorter than 2^28 bytes, so we can shove those two booleans
         //into the highest bits of one of the offset values
         if self.is_partial {
@@ -296,6 +1791,12 @@ impl ToPayload for jmdict_traverse::RawLSource<'_> {
             buf[0] |= 0x20000000;
         }
     }
+
+    fn relocate_one(data: &mut DataWindow, pos: usize, r: &Relocation) {
+        let text_len = data[pos + 1] - (data[pos] & 0x0FFFFFFF);
+        r.shift_text(&mut data[pos], text_len);
+        r.shift_text_end(&mut data[pos + 1], text_len);
+    }
 }
 
 impl ToPayload for jmdict_traverse::RawGloss<'_> {
@@ -305,15 +1806,73 @@ impl ToPayload for jmdict_traverse::RawGloss<'_> {
 
     fn encode_one(&self, omni: &mut OmniBuffer, buf: &mut [u32]) {
         //`omni.text` is never larger than 30-40 MiB. That's slightly more than 2^24 bytes, but
-        //comfortably below 2^28 bytes. We can therefore use the upper 4 bits of `buf[0]` and
-        //`buf[1]`, respectively, to encode `self.lang` and `self.g_type`.
+        //comfortably below 2^28 bytes. We can therefore use the upper 4 bits of `buf[0]` to encode
+        //`self.lang`. In `buf[1]`, we store the length of the text instead of its end, which
+        //leaves 8 bits to encode `self.g_gend` and `self.g_type`.
+        let r = omni.push_str(self.text);
+        let len = r.end - r.start;
+        assert!(len < (1 << 24), "gloss too long: {}", self.text);
+        buf[0] = r.start | (self.lang.to_u32() << 28);
+        buf[1] = len | (self.g_gend.to_u32() << 24) | (self.g_type.to_u32() << 28);
+    }
+
+    fn relocate_one(data: &mut DataWindow, pos: usize, r: &Relocation) {
+        let text_len = data[pos + 1] & 0xFFFFFF;
+        r.shift_text(&mut data[pos], text_len);
+    }
+}
+
+impl ToPayload for jmdict_traverse::RawExample<'_> {
+    fn size() -> usize {
+        7
+    }
+
+    fn encode_one(&self, omni: &mut OmniBuffer, buf: &mut [u32]) {
+        buf[0] = self.source_id;
+        let r = omni.push_str(self.text);
+        buf[1] = r.start;
+        buf[2] = r.end;
+        let r = omni.push_str(self.sentence);
+        buf[3] = r.start;
+        buf[4] = r.end;
+        let r = omni.push_array(&self.translations);
+        buf[5] = r.start;
+        buf[6] = r.end;
+    }
+
+    fn relocate_one(data: &mut DataWindow, pos: usize, r: &Relocation) {
+        for idx in [1, 3] {
+            let text_len = data[pos + idx + 1] - data[pos + idx];
+            r.shift_text(&mut data[pos + idx], text_len);
+            r.shift_text_end(&mut data[pos + idx + 1], text_len);
+        }
+        let (start, end) = (data[pos + 5], data[pos + 6]);
+        relocate_array::<jmdict_traverse::RawExampleTranslation>(data, start, end, r);
+        r.shift_data(&mut data[pos + 5], end - start);
+        r.shift_data(&mut data[pos + 6], end - start);
+    }
+}
+
+impl ToPayload for jmdict_traverse::RawExampleTranslation<'_> {
+    fn size() -> usize {
+        2
+    }
+
+    fn encode_one(&self, omni: &mut OmniBuffer, buf: &mut [u32]) {
+        //same encoding as for RawGloss, but without the gloss type
         let r = omni.push_str(self.text);
         buf[0] = r.start | (self.lang.to_u32() << 28);
-        buf[1] = r.end | (self.g_type.to_u32() << 28);
+        buf[1] = r.end;
+    }
+
+    fn relocate_one(data: &mut DataWindow, pos: usize, r: &Relocation) {
+        let text_len = data[pos + 1] - (data[pos] & 0x0FFFFFFF);
+        r.shift_text(&mut data[pos], text_len);
+        r.shift_text_end(&mut data[pos + 1], text_len);
     }
 }
 
-impl<'a> ToPayload for &'a str {
+impl ToPayload for &str {
     fn size() -> usize {
         2
     }
@@ -323,6 +1882,12 @@ impl<'a> ToPayload for &'a str {
         buf[0] = r.start;
         buf[1] = r.end;
     }
+
+    fn relocate_one(data: &mut DataWindow, pos: usize, r: &Relocation) {
+        let text_len = data[pos + 1] - data[pos];
+        r.shift_text(&mut data[pos], text_len);
+        r.shift_text_end(&mut data[pos + 1], text_len);
+    }
 }
 
 impl ToPayload for u32 {
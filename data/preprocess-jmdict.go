@@ -169,6 +169,7 @@ type dictSense struct {
 	Lsource []dictLsource `xml:"lsource" json:"L,omitempty"`
 	Dial    []string      `xml:"dial" json:"dial,omitempty"`
 	Gloss   []dictGloss   `xml:"gloss" json:"G,omitempty"`
+	Example []dictExample `xml:"example" json:"ex,omitempty"`
 }
 
 type dictLsource struct {
@@ -184,7 +185,24 @@ type dictGloss struct {
 	GGend string   `xml:"g_gend,attr" json:"g_gend,omitempty"`
 	GType string   `xml:"g_type,attr" json:"g_type,omitempty"`
 	Pri   []string `xml:"pri" json:"pri,omitempty"`
-	//NOTE: g_gend and <pri> are defined in the DTD, but do not actually occur in any entry.
+	//NOTE: g_gend and <pri> are defined in the DTD, but do not actually occur in any entry (as of
+	//2021-07). g_gend is mapped anyway, since it is relevant for non-English glosses.
+}
+
+type dictExample struct {
+	Source    dictExSource     `xml:"ex_srce" json:"src"`
+	Text      string           `xml:"ex_text" json:"t"`
+	Sentences []dictExSentence `xml:"ex_sent" json:"S"`
+}
+
+type dictExSource struct {
+	ID   string `xml:",chardata" json:"id"`
+	Type string `xml:"exsrc_type,attr" json:"type,omitempty"`
+}
+
+type dictExSentence struct {
+	Text string `xml:",chardata" json:"t"`
+	Lang string `xml:"lang,attr" json:"l,omitempty"`
 }
 
 var decoderEntities = make(map[string]string)
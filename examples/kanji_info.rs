@@ -5,26 +5,35 @@
 
 fn main() {
     let input = "一日";
-    let count = jmdict::entries()
-        .filter(|e| e.kanji_elements().any(|k| k.text == input))
-        .inspect(|e| {
-            // note two entries have identical Kanji
-            // yet differ in reading
-            println!("--- entry #{} ---", e.number);
-            for kanji in e.kanji_elements() {
-                println!("kanji element: {}", kanji.text);
-                println!("   priority: {:?}\n", kanji.priority);
-            }
+    let group = match jmdict::entries_sharing_form(input) {
+        Some(group) => group,
+        None => {
+            println!("0 entries for {}", input);
+            return;
+        }
+    };
+
+    // note two entries have identical Kanji
+    // yet differ in reading
+    for member in &group.members {
+        let e = member.entry;
+        println!("--- entry #{} ---", e.number);
+        for kanji in e.kanji_elements() {
+            println!("kanji element: {}", kanji.text);
+            println!("   priority: {:?}\n", kanji.priority);
+        }
 
-            for reading in e.reading_elements() {
-                println!("reading_form: {}", reading.text);
-                println!("   priority: {:?}\n", reading.priority);
+        for reading in e.reading_elements() {
+            println!("reading_form: {}", reading.text);
+            println!("   priority: {:?}\n", reading.priority);
 
-                for info in reading.infos() {
-                    println!("info: {:?}", info);
-                }
+            for info in reading.infos() {
+                println!("info: {:?}", info);
             }
-        })
-        .count();
-    println!("{} entries for {}", count, input);
+        }
+
+        let readings: Vec<_> = member.reading_elements.iter().map(|r| r.text).collect();
+        println!("readings for {}: {}\n", group.form, readings.join(", "));
+    }
+    println!("{} entries for {}", group.members.len(), input);
 }
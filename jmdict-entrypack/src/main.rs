@@ -0,0 +1,132 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! Converts the official JMdict XML file into the entrypack format used by the `jmdict` crate.
+//!
+//! Usage: `jmdict-entrypack <input-file> <output-file>`. The input may be compressed with GZip,
+//! Zstandard or XZ. The output is GZip-compressed if its filename ends in `.gz`.
+//!
+//! With `jmdict-entrypack --check <file>`, an existing entrypack is checked against the entry count
+//! and checksum in its header instead.
+//!
+//! With `jmdict-entrypack --diff <old-file> <new-file> <delta-file>`, a delta between two
+//! entrypacks is written. With `jmdict-entrypack --apply <old-file> <delta-file> <new-file>`, such
+//! a delta is applied to reconstruct the new entrypack. The output of both commands is
+//! GZip-compressed if its filename ends in `.gz`.
+
+use std::io::Write;
+use std::path::Path;
+
+fn main() {
+    let args: Vec<_> = std::env::args_os().skip(1).collect();
+    if args.len() == 2 && args[0] == "--check" {
+        check(args[1].as_ref());
+        return;
+    }
+    if args.len() == 4 && args[0] == "--diff" {
+        diff(args[1].as_ref(), args[2].as_ref(), args[3].as_ref());
+        return;
+    }
+    if args.len() == 4 && args[0] == "--apply" {
+        apply(args[1].as_ref(), args[2].as_ref(), args[3].as_ref());
+        return;
+    }
+    if args.len() != 2 {
+        eprintln!("usage: jmdict-entrypack <input-file> <output-file>");
+        eprintln!("   or: jmdict-entrypack --check <file>");
+        eprintln!("   or: jmdict-entrypack --diff <old-file> <new-file> <delta-file>");
+        eprintln!("   or: jmdict-entrypack --apply <old-file> <delta-file> <new-file>");
+        std::process::exit(1);
+    }
+    let (input_path, output_path) = (&args[0], &args[1]);
+
+    let xml = jmdict_traverse::open_data_file(input_path.as_ref());
+    write_output(output_path.as_ref(), |mut out| {
+        jmdict_traverse::write_entrypack(xml, &mut out).map_err(|err| err.to_string())
+    })
+    .unwrap();
+}
+
+///Creates the file at `path` and calls `write` to fill it. The file is GZip-compressed if its name
+///ends in `.gz`.
+fn write_output<T>(
+    path: &Path,
+    write: impl FnOnce(&mut dyn Write) -> Result<T, String>,
+) -> Result<T, String> {
+    let file = std::fs::File::create(path).unwrap();
+    if path.to_string_lossy().ends_with(".gz") {
+        let mut encoder = libflate::gzip::Encoder::new(std::io::BufWriter::new(file)).unwrap();
+        let result = write(&mut encoder)?;
+        encoder.finish().into_result().unwrap().flush().unwrap();
+        Ok(result)
+    } else {
+        let mut file = std::io::BufWriter::new(file);
+        let result = write(&mut file)?;
+        file.flush().unwrap();
+        Ok(result)
+    }
+}
+
+fn diff(old_path: &Path, new_path: &Path, delta_path: &Path) {
+    let result = write_output(delta_path, |mut out| {
+        jmdict_traverse::write_entrypack_delta(old_path, new_path, &mut out)
+    });
+    match result {
+        Ok(stats) => println!(
+            "{}: {} entries added, {} changed, {} removed",
+            delta_path.display(),
+            stats.added,
+            stats.changed,
+            stats.removed
+        ),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn apply(old_path: &Path, delta_path: &Path, new_path: &Path) {
+    let result = write_output(new_path, |mut out| {
+        jmdict_traverse::apply_entrypack_delta(old_path, delta_path, &mut out)
+    });
+    match result {
+        Ok(header) => println!(
+            "{}: entrypack format version {}, {} entries, checksum OK",
+            new_path.display(),
+            header.entrypack,
+            header.entry_count
+        ),
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn check(path: &Path) {
+    match jmdict_traverse::check_entrypack(path) {
+        Ok(header) => {
+            println!(
+                "{}: entrypack format version {}, {} entries, checksum OK",
+                path.display(),
+                header.entrypack,
+                header.entry_count
+            );
+            let unknown = String::from("unknown");
+            println!(
+                "JMdict created on {}, DTD revision {}, {} entities",
+                header.creation_date.as_ref().unwrap_or(&unknown),
+                header.revision.as_ref().unwrap_or(&unknown),
+                header.entities.len()
+            );
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+    }
+}
@@ -34,6 +34,93 @@ struct Enum<'a> {
     variants: Vec<EnumVariant>,
 }
 
+///The languages that loanwords are borrowed from, as (ISO 639-2/B code, variant name, English name).
+///Unlike for `GlossLanguage`, all of these are always enabled since the enum is small and its
+///values do not influence the size of the database.
+const SOURCE_LANGUAGES: &[(&str, &str, &str)] = &[
+    ("afr", "Afrikaans", "Afrikaans"),
+    ("ain", "Ainu", "Ainu"),
+    ("alg", "Algonquian", "Algonquian languages"),
+    ("amh", "Amharic", "Amharic"),
+    ("ara", "Arabic", "Arabic"),
+    ("arn", "Mapudungun", "Mapudungun"),
+    ("bnt", "Bantu", "Bantu languages"),
+    ("bre", "Breton", "Breton"),
+    ("bul", "Bulgarian", "Bulgarian"),
+    ("bur", "Burmese", "Burmese"),
+    ("chi", "Chinese", "Chinese"),
+    ("chn", "ChinookJargon", "Chinook jargon"),
+    ("cze", "Czech", "Czech"),
+    ("dan", "Danish", "Danish"),
+    ("dut", "Dutch", "Dutch"),
+    ("eng", "English", "English"),
+    ("epo", "Esperanto", "Esperanto"),
+    ("est", "Estonian", "Estonian"),
+    ("fil", "Filipino", "Filipino"),
+    ("fin", "Finnish", "Finnish"),
+    ("fre", "French", "French"),
+    ("geo", "Georgian", "Georgian"),
+    ("ger", "German", "German"),
+    ("glg", "Galician", "Galician"),
+    ("grc", "AncientGreek", "Ancient Greek"),
+    ("gre", "Greek", "Greek"),
+    ("haw", "Hawaiian", "Hawaiian"),
+    ("heb", "Hebrew", "Hebrew"),
+    ("hin", "Hindi", "Hindi"),
+    ("hun", "Hungarian", "Hungarian"),
+    ("ice", "Icelandic", "Icelandic"),
+    ("ind", "Indonesian", "Indonesian"),
+    ("ita", "Italian", "Italian"),
+    ("khm", "Khmer", "Khmer"),
+    ("kor", "Korean", "Korean"),
+    ("kur", "Kurdish", "Kurdish"),
+    ("lat", "Latin", "Latin"),
+    ("mal", "Malayalam", "Malayalam"),
+    ("mao", "Maori", "Maori"),
+    ("may", "Malay", "Malay"),
+    ("mnc", "Manchu", "Manchu"),
+    ("mol", "Moldavian", "Moldavian"),
+    ("mon", "Mongolian", "Mongolian"),
+    ("nor", "Norwegian", "Norwegian"),
+    ("per", "Persian", "Persian"),
+    ("pol", "Polish", "Polish"),
+    ("por", "Portuguese", "Portuguese"),
+    ("rum", "Romanian", "Romanian"),
+    ("rus", "Russian", "Russian"),
+    ("san", "Sanskrit", "Sanskrit"),
+    ("scr", "Croatian", "Croatian"),
+    ("slo", "Slovak", "Slovak"),
+    ("slv", "Slovenian", "Slovenian"),
+    ("som", "Somali", "Somali"),
+    ("spa", "Spanish", "Spanish"),
+    ("swa", "Swahili", "Swahili"),
+    ("swe", "Swedish", "Swedish"),
+    ("tah", "Tahitian", "Tahitian"),
+    ("tam", "Tamil", "Tamil"),
+    ("tgl", "Tagalog", "Tagalog"),
+    ("tha", "Thai", "Thai"),
+    ("tib", "Tibetan", "Tibetan"),
+    ("tur", "Turkish", "Turkish"),
+    ("ukr", "Ukrainian", "Ukrainian"),
+    ("urd", "Urdu", "Urdu"),
+    ("vie", "Vietnamese", "Vietnamese"),
+    ("yid", "Yiddish", "Yiddish"),
+];
+
+///The languages that glosses can be written in, as (variant name, English name, native name, ISO
+///639-1 code). Native names are written as in the CLDR, so some of them are not capitalized.
+const GLOSS_LANGUAGE_NAMES: &[(&str, &str, &str, &str)] = &[
+    ("English", "English", "English", "en"),
+    ("Dutch", "Dutch", "Nederlands", "nl"),
+    ("French", "French", "français", "fr"),
+    ("German", "German", "Deutsch", "de"),
+    ("Hungarian", "Hungarian", "magyar", "hu"),
+    ("Russian", "Russian", "русский", "ru"),
+    ("Slovenian", "Slovenian", "slovenščina", "sl"),
+    ("Spanish", "Spanish", "español", "es"),
+    ("Swedish", "Swedish", "svenska", "sv"),
+];
+
 fn main() {
     println!("cargo:rerun-if-changed=build.rs");
     println!("cargo:rerun-if-changed=data/entities.json");
@@ -41,6 +128,24 @@ fn main() {
     let entities_str = std::fs::read_to_string("data/entities.json").unwrap();
     let entities = json::parse(&entities_str).unwrap();
 
+    //translations of the entity texts, from files named like "data/labels/de.json"
+    println!("cargo:rerun-if-changed=data/labels");
+    let mut label_paths: Vec<_> = std::fs::read_dir("data/labels")
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| matches!(path.extension(), Some(ext) if ext == "json"))
+        .collect();
+    label_paths.sort();
+    let labels: Vec<(String, JsonValue)> = label_paths
+        .iter()
+        .map(|path| {
+            println!("cargo:rerun-if-changed={}", path.display());
+            let locale = path.file_stem().unwrap().to_str().unwrap().to_owned();
+            let labels_str = std::fs::read_to_string(path).unwrap();
+            (locale, json::parse(&labels_str).unwrap())
+        })
+        .collect();
+
     let mut content = String::new();
 
     content.push_str(&process(Enum {
@@ -82,6 +187,21 @@ fn main() {
         ],
     }));
 
+    content.push_str(&process_gloss_language_names());
+
+    content.push_str(&process(Enum {
+        name: "GlossGender",
+        all_name: None,
+        doc: "Grammatical gender of a gloss (usually a noun) in the target language.".into(),
+        entities: None,
+        variants: vec![
+            v("", "Unspecified"),
+            v("fem", "Feminine"),
+            v("masc", "Masculine"),
+            v("neut", "Neuter"),
+        ],
+    }));
+
     content.push_str(&process(Enum {
         name: "GlossType",
         all_name: None,
@@ -108,6 +228,7 @@ fn main() {
             v("io", "IrregularOkuriganaUsage"),
             v("oK", "OutdatedKanji"),
             v("rK", "RareKanjiForm"),
+            v("sK", "SearchOnlyKanjiForm"),
         ],
     }));
 
@@ -198,6 +319,7 @@ fn main() {
             v("v5t", "GodanTsuVerb"),
             v("v5u", "GodanUVerb"),
             v("v5u-s", "IrregularGodanUVerb"),
+            v("v5uru", "GodanUruVerb").when(cfg!(feature = "scope-archaic")),
             v("vi", "IntransitiveVerb"),
             v("vk", "KuruVerb"),
             v("vn", "IrregularGodanNuVerb"),
@@ -220,6 +342,8 @@ fn main() {
             v("gikun", "GikunOrJukujikun"),
             v("ik", "IrregularKanaUsage"),
             v("ok", "OutdatedKanaUsage"),
+            v("rk", "RareKanaForm"),
+            v("sk", "SearchOnlyKanaForm"),
             v("uK", "UsuallyWrittenUsingKanjiAlone"),
         ],
     }));
@@ -242,6 +366,7 @@ fn main() {
             v("dei", "Deity"),
             v("derog", "Derogatory"),
             v("doc", "Document"),
+            v("euph", "Euphemism"),
             v("ev", "Event"),
             v("fam", "FamiliarLanguage"),
             v("fem", "FemaleTermOrLanguage"),
@@ -276,6 +401,7 @@ fn main() {
             v("relig", "Religion"),
             v("sens", "Sensitive"),
             v("serv", "Service"),
+            v("ship", "ShipName"),
             v("sl", "Slang"),
             v("station", "RailwayStation"),
             v("surname", "Surname"),
@@ -309,11 +435,16 @@ fn main() {
             v("biochem", "Biochemistry"),
             v("biol", "Biology"),
             v("bot", "Botany"),
+            v("boxing", "Boxing"),
             v("bus", "Business"),
+            v("cards", "CardGames"),
             v("chem", "Chemistry"),
+            v("chmyth", "ChineseMythology"),
+            v("civeng", "CivilEngineering"),
             v("cloth", "Clothing"),
             v("comp", "Computing"),
             v("cryst", "Crystallography"),
+            v("dent", "Dentistry"),
             v("ecol", "Ecology"),
             v("econ", "Economics"),
             v("elec", "ElectricalEngineering"),
@@ -321,6 +452,8 @@ fn main() {
             v("embryo", "Embryology"),
             v("engr", "Engineering"),
             v("ent", "Entomology"),
+            v("figskt", "FigureSkating"),
+            v("film", "Film"),
             v("finc", "Finance"),
             v("fish", "Fishing"),
             v("food", "Food"),
@@ -335,16 +468,23 @@ fn main() {
             v("grmyth", "GreekMythology"),
             v("hanaf", "Hanafuda"),
             v("horse", "Horseracing"),
+            v("internet", "Internet"),
+            v("jpmyth", "JapaneseMythology"),
+            v("kabuki", "Kabuki"),
             v("law", "Law"),
             v("ling", "Linguistics"),
             v("logic", "Logic"),
             v("mahj", "Mahjong"),
+            v("manga", "Manga"),
             v("math", "Mathematics"),
             v("mech", "MechanicalEngineering"),
             v("med", "Medicine"),
             v("met", "Meteorology"),
             v("mil", "Military"),
+            v("mining", "Mining"),
+            v("motor", "Motorsport"),
             v("music", "Music"),
+            v("noh", "Noh"),
             v("ornith", "Ornithology"),
             v("paleo", "Paleontology"),
             v("pathol", "Pathology"),
@@ -353,21 +493,54 @@ fn main() {
             v("photo", "Photography"),
             v("physics", "Physics"),
             v("physiol", "Physiology"),
+            v("politics", "Politics"),
             v("print", "Printing"),
+            v("prowres", "ProfessionalWrestling"),
             v("psy", "Psychiatry"),
+            v("psyanal", "Psychoanalysis"),
             v("psych", "Psychology"),
             v("rail", "Railway"),
+            v("rommyth", "RomanMythology"),
             v("shogi", "Shogi"),
+            v("ski", "Skiing"),
             v("sports", "Sports"),
             v("stat", "Statistics"),
+            v("stockm", "StockMarket"),
             v("sumo", "Sumo"),
+            v("surg", "Surgery"),
             v("telec", "Telecommunications"),
             v("tradem", "Trademark"),
+            v("tv", "Television"),
+            v("vet", "VeterinaryTerms"),
             v("vidg", "VideoGame"),
             v("zool", "Zoology"),
         ],
     }));
 
+    content.push_str(&process(Enum {
+        name: "SourceLanguage",
+        all_name: None,
+        doc: "The language from which a loanword was borrowed.".into(),
+        entities: None,
+        variants: SOURCE_LANGUAGES
+            .iter()
+            .map(|&(code, name, _)| v(code, name))
+            .collect(),
+    }));
+    content.push_str(&process_display_names("SourceLanguage", SOURCE_LANGUAGES));
+
+    for &(enum_name, entity_group) in &[
+        ("Dialect", "dial"),
+        ("KanjiInfo", "ke_inf"),
+        ("PartOfSpeech", "pos"),
+        ("AllPartOfSpeech", "pos"),
+        ("ReadingInfo", "re_inf"),
+        ("SenseInfo", "misc"),
+        ("SenseTopic", "field"),
+    ] {
+        content.push_str(&process_labels(enum_name, entity_group, &labels));
+    }
+
     let out_dir = std::env::var_os("OUT_DIR").unwrap();
     let dest_path = std::path::Path::new(&out_dir).join("generated.rs");
     std::fs::write(&dest_path, content).unwrap();
@@ -375,6 +548,8 @@ fn main() {
 
 fn process(e: Enum) -> String {
     let mut lines = vec![];
+    //enums that correspond to JMdict entities have an `Unknown` variant for forward compatibility
+    let has_other = e.entities.is_some();
 
     //render the corresponding fully-populated enum, if requested
     if let Some(all_name) = e.all_name {
@@ -389,15 +564,27 @@ fn process(e: Enum) -> String {
 
     //enum declaration
     lines.push(format!("/// {}", e.doc));
-    lines.push("#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]".into());
+    lines.push("#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]".into());
     lines.push("#[non_exhaustive]".into());
     lines.push(format!("pub enum {} {{", e.name));
     for v in e.variants.iter().filter(|v| v.enabled) {
-        if let Some(ref entities) = e.entities {
+        if let Some(entities) = e.entities {
             lines.push(format!("  ///{}", entities[v.code].as_str().unwrap()));
         }
         lines.push(format!("  {},", v.name));
     }
+    if has_other {
+        lines.push(
+            "  ///An entity that is not known to this version of this crate. The JMdict code for"
+                .into(),
+        );
+        lines.push(
+            "  ///this entity is given in the payload. This occurs when the JMdict is updated with"
+                .into(),
+        );
+        lines.push("  ///new entities.".into());
+        lines.push("  Unknown(&'static str),".into());
+    }
     lines.push("}\n".into());
 
     //start impl Enum
@@ -412,6 +599,9 @@ fn process(e: Enum) -> String {
             e.name, v.name, v.code
         ));
     }
+    if has_other {
+        lines.push(format!("            {}::Unknown(code) => code,", e.name));
+    }
     lines.push("        }".into());
     lines.push("    }\n".into());
 
@@ -424,6 +614,12 @@ fn process(e: Enum) -> String {
             e.name, v.name, v.name
         ));
     }
+    if has_other {
+        lines.push(format!(
+            "            {}::Unknown(_) => \"Unknown\",",
+            e.name
+        ));
+    }
     lines.push("        }".into());
     lines.push("    }\n".into());
 
@@ -453,6 +649,32 @@ fn process(e: Enum) -> String {
     lines.push("        }".into());
     lines.push("    }\n".into());
 
+    //const VARIANT_COUNT
+    lines.push(format!(
+        "    const VARIANT_COUNT: usize = {};\n",
+        e.variants.iter().filter(|v| v.enabled).count()
+    ));
+
+    //fn to_index(&self) -> Option<usize>
+    lines.push("    fn to_index(&self) -> Option<usize> {".into());
+    lines.push("        match *self {".into());
+    for (idx, v) in e.variants.iter().filter(|v| v.enabled).enumerate() {
+        lines.push(format!(
+            "            {}::{} => Some({}),",
+            e.name, v.name, idx
+        ));
+    }
+    if has_other {
+        lines.push(format!("            {}::Unknown(_) => None,", e.name));
+    }
+    lines.push("        }".into());
+    lines.push("    }\n".into());
+
+    //fn from_index(usize) -> Option<Self>
+    lines.push("    fn from_index(index: usize) -> Option<Self> {".into());
+    lines.push("        Self::all_variants().get(index).copied()".into());
+    lines.push("    }\n".into());
+
     //fn all_variants() -> &'static [Self]
     lines.push("    fn all_variants() -> &'static [Self] {".into());
     lines.push("        &[".into());
@@ -468,10 +690,47 @@ fn process(e: Enum) -> String {
     //impl Display
     lines.push(format!("impl std::fmt::Display for {} {{", e.name));
     lines.push("    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {".into());
+    if has_other {
+        lines.push(format!(
+            "        if let {}::Unknown(code) = *self {{",
+            e.name
+        ));
+        lines.push("            return write!(f, \"Unknown({})\", code);".into());
+        lines.push("        }".into());
+    }
     lines.push("        write!(f, \"{}\", self.constant_name())".into());
     lines.push("    }".into());
     lines.push("}\n".into());
 
+    //impl FromStr (accepting both JMdict codes and constant names)
+    lines.push(format!("impl std::str::FromStr for {} {{", e.name));
+    lines.push("    type Err = ParseEnumError;\n".into());
+    lines.push("    fn from_str(s: &str) -> Result<Self, Self::Err> {".into());
+    lines.push(format!("        parse_enum(s, \"{}\")", e.name));
+    lines.push("    }".into());
+    lines.push("}\n".into());
+
+    //impl Serialize + Deserialize (using the JMdict codes as wire format)
+    lines.push("#[cfg(feature = \"serde\")]".into());
+    lines.push(format!("impl serde::Serialize for {} {{", e.name));
+    lines.push(
+        "    fn serialize<S: serde::Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {".into(),
+    );
+    lines.push("        s.serialize_str(self.code())".into());
+    lines.push("    }".into());
+    lines.push("}\n".into());
+    lines.push("#[cfg(feature = \"serde\")]".into());
+    lines.push(format!(
+        "impl<'de> serde::Deserialize<'de> for {} {{",
+        e.name
+    ));
+    lines.push(
+        "    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {".into(),
+    );
+    lines.push(format!("        deserialize_code(d, \"{}\")", e.name));
+    lines.push("    }".into());
+    lines.push("}\n".into());
+
     //impl EnumPayload
     lines.push(format!("impl EnumPayload for {} {{", e.name));
     lines.push("    fn to_u32(&self) -> u32 {".into());
@@ -479,6 +738,12 @@ fn process(e: Enum) -> String {
     for (idx, v) in e.variants.iter().filter(|v| v.enabled).enumerate() {
         lines.push(format!("            {}::{} => {},", e.name, v.name, idx));
     }
+    if has_other {
+        lines.push(format!(
+            "            {}::Unknown(code) => panic!(\"cannot encode {}::Unknown({{}}) as u32\", code),",
+            e.name, e.name
+        ));
+    }
     lines.push("        }".into());
     lines.push("    }\n".into());
     lines.push("    fn from_u32(code: u32) -> Self {".into());
@@ -492,6 +757,21 @@ fn process(e: Enum) -> String {
     ));
     lines.push("        }".into());
     lines.push("    }".into());
+    if has_other {
+        lines.push("".into());
+        lines.push("    fn unknown_code(&self) -> Option<&'static str> {".into());
+        lines.push("        match *self {".into());
+        lines.push(format!(
+            "            {}::Unknown(code) => Some(code),",
+            e.name
+        ));
+        lines.push("            _ => None,".into());
+        lines.push("        }".into());
+        lines.push("    }\n".into());
+        lines.push("    fn from_unknown_code(code: &'static str) -> Self {".into());
+        lines.push(format!("        {}::Unknown(code)", e.name));
+        lines.push("    }".into());
+    }
     lines.push("}\n".into());
 
     if let Some(all_name) = e.all_name {
@@ -519,6 +799,12 @@ fn process(e: Enum) -> String {
                 ));
             }
         }
+        if has_other {
+            lines.push(format!(
+                "            {}::Unknown(code) => Ok({}::Unknown(code)),",
+                all_name, e.name
+            ));
+        }
         lines.push("        }".into());
         lines.push("    }".into());
         lines.push("}\n".into());
@@ -536,6 +822,39 @@ fn process(e: Enum) -> String {
                 e.name, v.name, all_name, v.name
             ));
         }
+        if has_other {
+            lines.push(format!(
+                "            {}::Unknown(code) => {}::Unknown(code),",
+                e.name, all_name
+            ));
+        }
+        lines.push("        }".into());
+        lines.push("    }".into());
+        lines.push("}\n".into());
+    }
+
+    //fn description(&self) -> &str (only if we have entity expansion texts)
+    if let Some(entities) = e.entities {
+        lines.push(format!("impl {} {{", e.name));
+        lines.push(
+            "    ///Returns the human-readable description of this variant from the JMdict, e.g. for"
+                .into(),
+        );
+        lines.push("    ///display to the user. This is the same text as in the rustdoc. For the `Unknown` variant,"
+            .into(),
+        );
+        lines.push("    ///the JMdict code is returned since no description is known.".into());
+        lines.push("    pub fn description(&self) -> &'static str {".into());
+        lines.push("        match *self {".into());
+        for v in e.variants.iter().filter(|v| v.enabled) {
+            lines.push(format!(
+                "            {}::{} => {:?},",
+                e.name,
+                v.name,
+                entities[v.code].as_str().unwrap()
+            ));
+        }
+        lines.push(format!("            {}::Unknown(code) => code,", e.name));
         lines.push("        }".into());
         lines.push("    }".into());
         lines.push("}\n".into());
@@ -543,3 +862,120 @@ fn process(e: Enum) -> String {
 
     lines.join("\n")
 }
+
+fn process_display_names(enum_name: &str, variants: &[(&str, &str, &str)]) -> String {
+    let mut lines = vec![];
+    lines.push(format!("impl {} {{", enum_name));
+    lines.push(
+        "    ///Returns the English name of this variant, e.g. for display to the user.".into(),
+    );
+    lines.push("    pub fn name(&self) -> &'static str {".into());
+    lines.push("        match *self {".into());
+    for (_, name, display_name) in variants {
+        lines.push(format!(
+            "            {}::{} => \"{}\",",
+            enum_name, name, display_name
+        ));
+    }
+    lines.push("        }".into());
+    lines.push("    }".into());
+    lines.push("}\n".into());
+    lines.join("\n")
+}
+
+fn process_gloss_language_names() -> String {
+    let mut lines = vec![];
+    lines.push("impl AllGlossLanguage {".into());
+    let methods = [
+        (
+            "english_name",
+            "Returns the English name of this language, e.g. \"German\".",
+        ),
+        (
+            "native_name",
+            "Returns the name of this language in that language itself, e.g. \"Deutsch\".",
+        ),
+        (
+            "iso639_1",
+            "Returns the two-letter ISO 639-1 code for this language, e.g. \"de\".",
+        ),
+    ];
+    for (idx, (method, doc)) in methods.iter().enumerate() {
+        if idx > 0 {
+            lines.push("".into());
+        }
+        lines.push(format!("    ///{}", doc));
+        lines.push(format!("    pub fn {}(&self) -> &'static str {{", method));
+        lines.push("        match *self {".into());
+        for &(name, english, native, iso) in GLOSS_LANGUAGE_NAMES {
+            let value = [english, native, iso][idx];
+            lines.push(format!(
+                "            AllGlossLanguage::{} => \"{}\",",
+                name, value
+            ));
+        }
+        lines.push("        }".into());
+        lines.push("    }".into());
+    }
+    lines.push("}\n".into());
+
+    //the enum with only the enabled variants can delegate to the full enum
+    lines.push("impl GlossLanguage {".into());
+    for (idx, (method, doc)) in methods.iter().enumerate() {
+        if idx > 0 {
+            lines.push("".into());
+        }
+        lines.push(format!("    ///{}", doc));
+        lines.push(format!("    pub fn {}(&self) -> &'static str {{", method));
+        lines.push(format!(
+            "        AllGlossLanguage::from(*self).{}()",
+            method
+        ));
+        lines.push("    }".into());
+    }
+    lines.push("}\n".into());
+    lines.join("\n")
+}
+
+fn process_labels(enum_name: &str, entity_group: &str, labels: &[(String, JsonValue)]) -> String {
+    let mut lines = vec![];
+    lines.push(format!("impl {} {{", enum_name));
+    lines.push(
+        "    ///Returns a human-readable label for this variant in the given locale (e.g. \"en\" or"
+            .into(),
+    );
+    lines.push(
+        "    ///\"de\"). If no translation is available for that locale, the English description"
+            .into(),
+    );
+    lines.push("    ///from [description()](Self::description) is returned.".into());
+    lines.push("    pub fn label(&self, locale: &str) -> &'static str {".into());
+    let arms: Vec<_> = labels
+        .iter()
+        .flat_map(|(locale, translations)| {
+            translations[entity_group]
+                .entries()
+                .map(move |(code, text)| {
+                    format!(
+                        "            ({:?}, {:?}) => {:?},",
+                        locale,
+                        code,
+                        text.as_str().unwrap()
+                    )
+                })
+        })
+        .collect();
+    if arms.is_empty() {
+        //avoid a match with only the fallback arm (clippy complains about that)
+        lines.push("        let _ = locale;".into());
+        lines.push("        self.description()".into());
+    } else {
+        lines.push("        match (locale, self.code()) {".into());
+        lines.extend(arms);
+        lines.push("            _ => self.description(),".into());
+        lines.push("        }".into());
+    }
+    lines.push("    }".into());
+    lines.push("}\n".into());
+    lines.join("\n")
+}
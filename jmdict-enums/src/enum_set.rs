@@ -0,0 +1,217 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use crate::{Enum, EnumPayload};
+use std::marker::PhantomData;
+
+///A compact set of values of one of the enums in this crate, stored as a bitset.
+///
+///This is useful for working with the lists of enum values that the `jmdict` crate provides, e.g.
+///the parts of speech of a sense. Sets can be collected from iterators:
+///
+///```
+///# use jmdict_enums::*;
+///let set: EnumSet<KanjiInfo> = vec![KanjiInfo::Ateji, KanjiInfo::RareKanjiForm]
+///    .into_iter()
+///    .collect();
+///assert!(set.contains(KanjiInfo::Ateji));
+///assert!(!set.contains(KanjiInfo::OutdatedKanji));
+///
+///let other: EnumSet<KanjiInfo> = std::iter::once(KanjiInfo::Ateji).collect();
+///assert_eq!(set.intersection(other), other);
+///assert_eq!(set.len(), 2);
+///```
+///
+///Iteration yields values in the order in which the variants are declared in the enum. Values of
+///the `Unknown` variant cannot be stored in an EnumSet: `insert()` ignores them, and `contains()`
+///always returns false for them.
+pub struct EnumSet<E> {
+    bits: u128,
+    phantom: PhantomData<E>,
+}
+
+impl<E: Enum + EnumPayload + Copy> EnumSet<E> {
+    ///Returns an empty set.
+    pub fn new() -> Self {
+        Self::from_bits(0)
+    }
+
+    ///Returns a set containing all enabled variants of the enum.
+    pub fn all() -> Self
+    where
+        E: 'static,
+    {
+        E::all_variants().iter().collect()
+    }
+
+    fn from_bits(bits: u128) -> Self {
+        Self {
+            bits,
+            phantom: PhantomData,
+        }
+    }
+
+    fn bit(value: &E) -> u128 {
+        if value.unknown_code().is_some() {
+            return 0;
+        }
+        let idx = value.to_u32();
+        assert!(idx < 128, "too many variants for EnumSet");
+        1 << idx
+    }
+
+    ///Returns whether this set contains the given value.
+    pub fn contains(&self, value: E) -> bool {
+        self.bits & Self::bit(&value) != 0
+    }
+
+    ///Adds a value to this set. Returns whether the value was newly inserted.
+    pub fn insert(&mut self, value: E) -> bool {
+        let bit = Self::bit(&value);
+        let is_new = bit != 0 && self.bits & bit == 0;
+        self.bits |= bit;
+        is_new
+    }
+
+    ///Removes a value from this set. Returns whether the value was present.
+    pub fn remove(&mut self, value: E) -> bool {
+        let was_present = self.contains(value);
+        self.bits &= !Self::bit(&value);
+        was_present
+    }
+
+    ///Returns whether this set is empty.
+    pub fn is_empty(&self) -> bool {
+        self.bits == 0
+    }
+
+    ///Returns the number of values in this set.
+    pub fn len(&self) -> usize {
+        self.bits.count_ones() as usize
+    }
+
+    ///Returns the set of values that are in both `self` and `other`.
+    pub fn intersection(&self, other: Self) -> Self {
+        Self::from_bits(self.bits & other.bits)
+    }
+
+    ///Returns the set of values that are in `self` or `other` (or both).
+    pub fn union(&self, other: Self) -> Self {
+        Self::from_bits(self.bits | other.bits)
+    }
+
+    ///Returns the set of values that are in `self`, but not in `other`.
+    pub fn difference(&self, other: Self) -> Self {
+        Self::from_bits(self.bits & !other.bits)
+    }
+
+    ///Returns whether `self` and `other` have at least one value in common.
+    pub fn intersects(&self, other: Self) -> bool {
+        self.bits & other.bits != 0
+    }
+
+    ///Returns an iterator over the values in this set.
+    pub fn iter(&self) -> EnumSetIter<E> {
+        EnumSetIter {
+            bits: self.bits,
+            phantom: PhantomData,
+        }
+    }
+}
+
+//NOTE: These are implemented by hand because `derive` would require `E` to implement the
+//respective traits as well.
+
+impl<E> Clone for EnumSet<E> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<E> Copy for EnumSet<E> {}
+
+impl<E> PartialEq for EnumSet<E> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bits == other.bits
+    }
+}
+
+impl<E> Eq for EnumSet<E> {}
+
+impl<E> std::hash::Hash for EnumSet<E> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.bits.hash(state);
+    }
+}
+
+impl<E: Enum + EnumPayload + Copy> Default for EnumSet<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: Enum + EnumPayload + Copy + std::fmt::Debug> std::fmt::Debug for EnumSet<E> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<E: Enum + EnumPayload + Copy> std::iter::FromIterator<E> for EnumSet<E> {
+    fn from_iter<I: IntoIterator<Item = E>>(iter: I) -> Self {
+        let mut set = Self::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<'a, E: Enum + EnumPayload + Copy + 'a> std::iter::FromIterator<&'a E> for EnumSet<E> {
+    fn from_iter<I: IntoIterator<Item = &'a E>>(iter: I) -> Self {
+        iter.into_iter().copied().collect()
+    }
+}
+
+impl<E: Enum + EnumPayload + Copy> std::iter::Extend<E> for EnumSet<E> {
+    fn extend<I: IntoIterator<Item = E>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<E: Enum + EnumPayload + Copy> IntoIterator for EnumSet<E> {
+    type Item = E;
+    type IntoIter = EnumSetIter<E>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+///An iterator over the values in an [EnumSet].
+pub struct EnumSetIter<E> {
+    bits: u128,
+    phantom: PhantomData<E>,
+}
+
+impl<E: Enum + EnumPayload + Copy> Iterator for EnumSetIter<E> {
+    type Item = E;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.bits == 0 {
+            return None;
+        }
+        let idx = self.bits.trailing_zeros();
+        self.bits &= self.bits - 1;
+        Some(E::from_u32(idx))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.bits.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl<E: Enum + EnumPayload + Copy> ExactSizeIterator for EnumSetIter<E> {}
@@ -15,6 +15,12 @@
 //! system for the `jmdict` crate. To use the types from this crate, look at the re-exports of the
 //! same name in [the `jmdict` crate](https://docs.rs/jmdict/).
 
+mod enum_set;
+pub use enum_set::{EnumSet, EnumSetIter};
+mod registry;
+pub use registry::{enum_kind, enum_kinds, EnumKind, VariantInfo};
+use std::convert::TryFrom;
+
 ///Error type for all enum conversions of the form `impl TryFrom<AllFoo> for Foo`.
 ///
 ///The error is returned for variants from the full enum that are disabled in the main enum because
@@ -32,16 +38,110 @@
 #[derive(Clone, Copy, Default, Hash, PartialEq, Eq, Debug)]
 pub struct DisabledVariant;
 
+///Error type for all implementations of `FromStr` on the enums in this crate.
+///
+///Enums can be parsed from either their JMdict code or their constant name. The error is returned
+///if the input is neither, or if it refers to a variant that is disabled by the compile-time
+///configuration. For example:
+///
+///```
+///# use jmdict_enums::*;
+///assert_eq!("adj-na".parse(), Ok(PartOfSpeech::AdjectivalNoun));
+///assert_eq!("AdjectivalNoun".parse(), Ok(PartOfSpeech::AdjectivalNoun));
+///let err = "foo".parse::<PartOfSpeech>().unwrap_err();
+///assert_eq!(err.to_string(), "unknown or disabled PartOfSpeech: \"foo\"");
+///```
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub struct ParseEnumError {
+    enum_name: &'static str,
+    input: String,
+}
+
+impl ParseEnumError {
+    ///Returns the name of the enum type that the input was supposed to be parsed into.
+    pub fn enum_name(&self) -> &'static str {
+        self.enum_name
+    }
+
+    ///Returns the input that could not be parsed.
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+}
+
+impl std::fmt::Display for ParseEnumError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "unknown or disabled {}: {:?}",
+            self.enum_name, self.input
+        )
+    }
+}
+
+impl std::error::Error for ParseEnumError {}
+
+///Shared implementation of `FromStr` for all generated enums.
+fn parse_enum<E: Enum>(input: &str, enum_name: &'static str) -> Result<E, ParseEnumError> {
+    E::from_code(input)
+        .or_else(|| E::from_constant_name(input))
+        .ok_or_else(|| ParseEnumError {
+            enum_name,
+            input: input.into(),
+        })
+}
+
+///Error type for [GlossLanguage::try_from_code].
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+pub enum GlossLanguageCodeError {
+    ///The code does not refer to any language known to this crate.
+    UnknownCode,
+    ///The code refers to a language that is known to this crate, but disabled in `GlossLanguage`
+    ///by the compile-time configuration. Glosses in this language are not available.
+    Disabled(AllGlossLanguage),
+}
+
+impl std::fmt::Display for GlossLanguageCodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match *self {
+            GlossLanguageCodeError::UnknownCode => write!(f, "unknown gloss language code"),
+            GlossLanguageCodeError::Disabled(lang) => write!(
+                f,
+                "gloss language {:?} is disabled by compile-time flags",
+                lang.code()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for GlossLanguageCodeError {}
+
 ///Internal helper functions for serialization and deserialization of enum values.
 ///
 ///This is an internal trait; it is not re-exported by the `jmdict` crate and thus not part of the
 ///public API.
-pub trait EnumPayload {
+pub trait EnumPayload: Sized {
     fn to_u32(&self) -> u32;
     fn from_u32(code: u32) -> Self;
+
+    ///For enums that have an `Unknown` variant, returns the code stored in it. Values of that
+    ///variant cannot be encoded by `to_u32()`, so they need to be stored separately.
+    fn unknown_code(&self) -> Option<&'static str> {
+        None
+    }
+
+    ///For enums that have an `Unknown` variant, constructs a value of that variant. This is the
+    ///reverse of `unknown_code()`. Other enums panic.
+    fn from_unknown_code(code: &'static str) -> Self {
+        panic!("enum does not have an Unknown variant for code {:?}", code)
+    }
 }
 
 ///Common methods provided by all enums in this crate.
+///
+///All enums in this crate also implement `Ord`. Variants are ordered in the same way as the
+///respective entities are declared in the JMdict, so sorting yields a deterministic and
+///canonical order, e.g. for displaying lists of tags.
 pub trait Enum: Sized {
     ///Returns a list of all variant values in this enum. No particular order is guaranteed or
     ///implied.
@@ -64,29 +164,38 @@ pub trait Enum: Sized {
     ///such variant. This is the reverse of `self.constant_name()`, i.e.
     ///`Self::from_constant_name(self.constant_name()) == Some(self)`.
     fn from_constant_name(name: &str) -> Option<Self>;
+
+    ///The number of enabled variants in this enum, not counting the `Unknown` variant (if any).
+    ///This is the size of a dense table that is indexed by `to_index()`.
+    const VARIANT_COUNT: usize;
+
+    ///Returns the position of this variant within `all_variants()`, which is always less than
+    ///`VARIANT_COUNT`. This can be used as an index into a dense per-variant table, e.g.
+    ///`[usize; PartOfSpeech::VARIANT_COUNT]`. Returns `None` for the `Unknown` variant.
+    fn to_index(&self) -> Option<usize>;
+
+    ///Returns the variant at the given position within `all_variants()`, or `None` if the index
+    ///is out of range. This is the reverse of `self.to_index()`.
+    fn from_index(index: usize) -> Option<Self>;
 }
 
 ///PriorityInCorpus appears in struct [Priority]. It describes how often a dictionary entry
 ///appears in a certain corpus of text.
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum PriorityInCorpus {
     ///The vocabulary appears often within the given corpus.
     Primary,
     ///The vocabulary appears within the given corpus, but not particularly often.
     Secondary,
     ///The vocabulary does not appear in the given corpus. This is the `Default::default()` value.
+    #[default]
     Absent,
 }
 
-impl Default for PriorityInCorpus {
-    fn default() -> Self {
-        Self::Absent
-    }
-}
-
 impl PriorityInCorpus {
-    fn to_repr(&self) -> u32 {
-        match *self {
+    fn to_repr(self) -> u32 {
+        match self {
             Self::Absent => 0,
             Self::Primary => 1,
             Self::Secondary => 2,
@@ -120,10 +229,11 @@ impl PriorityInCorpus {
 ///    ichimango: Primary, //"ichi2" gets ignored
 ///    loanwords: Absent,
 ///    additional: Absent,
-///    frequency_bucket: 9,
+///    frequency_bucket: Some(9),
 ///};
 ///```
 #[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Priority {
     ///If not `Absent`, this vocabulary appears in the wordfreq file compiled by Alexandre Girardi
     ///from the Mainichi Shimbun. (A copy of the file can be obtained from the EDRDG.)
@@ -141,8 +251,8 @@ pub struct Priority {
     ///If `self.news != Absent`, this field contains a value between 1 and 48, indicating the
     ///frequency-of-use ranking for this vocabulary in the wordfreq file. The value 1 is used for
     ///the 500 most common words, the value 2 is used for the 500 next most common words, and so
-    ///on. If `self.news == Absent`, this value will be 0.
-    pub frequency_bucket: u16,
+    ///on. If `self.news == Absent`, this value will be `None`.
+    pub frequency_bucket: Option<u16>,
 }
 
 impl Priority {
@@ -156,15 +266,243 @@ impl Priority {
             || self.loanwords == Primary
             || self.additional != Absent
     }
+
+    ///Condenses this priority into a single value that can be used to rank vocabulary by how
+    ///common it is. Larger scores indicate more common vocabulary.
+    ///
+    ///The score is computed in two tiers. First, each of the four `PriorityInCorpus` fields
+    ///contributes 2 points if `Primary` and 1 point if `Secondary`. Only if these sums are equal,
+    ///the frequency bucket is used to break the tie, with lower buckets (i.e. more frequent
+    ///vocabulary) giving a higher score, and the absence of a bucket giving the lowest score.
+    ///
+    ///```
+    ///# use jmdict_enums::{PriorityInCorpus::*, Priority};
+    ///let p1 = Priority { news: Primary, frequency_bucket: Some(3), ..Priority::default() };
+    ///let p2 = Priority { news: Primary, frequency_bucket: Some(9), ..Priority::default() };
+    ///let p3 = Priority { ichimango: Primary, news: Secondary, ..Priority::default() };
+    ///assert!(p1.score() > p2.score());
+    ///assert!(p3.score() > p1.score());
+    ///assert!(p3.score() > Priority::default().score());
+    ///```
+    pub fn score(&self) -> PriorityScore {
+        use PriorityInCorpus::*;
+        let corpora = [self.news, self.ichimango, self.loanwords, self.additional];
+        let corpus_score: u32 = corpora
+            .iter()
+            .map(|p| match p {
+                Primary => 2,
+                Secondary => 1,
+                Absent => 0,
+            })
+            .sum();
+        //frequency buckets go from 1 (most common) to 48 (least common)
+        let bucket_score = match self.frequency_bucket {
+            None => 0,
+            Some(bucket) => 100 - bucket as u32,
+        };
+        PriorityScore(corpus_score * 100 + bucket_score)
+    }
+
+    ///Returns the priority markers (e.g. "news1", "nf17" or "spec2") that describe this priority
+    ///in the JMdict, in alphabetical order.
+    ///
+    ///For almost all elements, this is exactly the list of markers that appears in the JMdict.
+    ///The only exception are the rare elements with contradictory priority information (see
+    ///above): For those, only the markers corresponding to the highest priority are returned.
+    ///
+    ///```
+    ///# use jmdict_enums::{PriorityInCorpus::*, Priority};
+    ///let p = Priority {
+    ///    news: Primary,
+    ///    ichimango: Secondary,
+    ///    loanwords: Absent,
+    ///    additional: Absent,
+    ///    frequency_bucket: Some(9),
+    ///};
+    ///assert_eq!(p.markers(), vec!["ichi2", "news1", "nf09"]);
+    ///```
+    pub fn markers(&self) -> Vec<&'static str> {
+        let mut result = Vec::new();
+        let corpora = [
+            (self.loanwords, "gai1", "gai2"),
+            (self.ichimango, "ichi1", "ichi2"),
+            (self.news, "news1", "news2"),
+        ];
+        for &(prio, primary, secondary) in corpora.iter() {
+            match prio {
+                PriorityInCorpus::Primary => result.push(primary),
+                PriorityInCorpus::Secondary => result.push(secondary),
+                PriorityInCorpus::Absent => {}
+            }
+        }
+        if let Some(bucket) = self.frequency_bucket {
+            result.push(FREQUENCY_BUCKET_MARKERS[bucket as usize - 1]);
+        }
+        match self.additional {
+            PriorityInCorpus::Primary => result.push("spec1"),
+            PriorityInCorpus::Secondary => result.push("spec2"),
+            PriorityInCorpus::Absent => {}
+        }
+        result
+    }
+
+    ///Parses a list of priority markers (e.g. "news1", "nf17" or "spec2") as they appear in the
+    ///JMdict. This is the reverse of `self.markers()`. The order of the markers does not matter.
+    ///Contradictory markers are resolved in favor of the highest priority, as explained above.
+    ///
+    ///```
+    ///# use jmdict_enums::Priority;
+    ///let p = Priority::from_markers(vec!["news1", "ichi2", "nf09"]).unwrap();
+    ///assert_eq!(p.markers(), vec!["ichi2", "news1", "nf09"]);
+    ///assert_eq!(Priority::from_markers(p.markers()), Ok(p));
+    ///assert!(Priority::from_markers(vec!["nf49"]).is_err());
+    ///```
+    pub fn from_markers<'a, I: IntoIterator<Item = &'a str>>(
+        markers: I,
+    ) -> Result<Priority, ParsePriorityError> {
+        use PriorityInCorpus::*;
+        let mut result = Priority::default();
+        for marker in markers {
+            match marker {
+                "news1" => result.news = merge_cprio(result.news, Primary),
+                "news2" => result.news = merge_cprio(result.news, Secondary),
+                "ichi1" => result.ichimango = merge_cprio(result.ichimango, Primary),
+                "ichi2" => result.ichimango = merge_cprio(result.ichimango, Secondary),
+                "gai1" => result.loanwords = merge_cprio(result.loanwords, Primary),
+                "gai2" => result.loanwords = merge_cprio(result.loanwords, Secondary),
+                "spec1" => result.additional = merge_cprio(result.additional, Primary),
+                "spec2" => result.additional = merge_cprio(result.additional, Secondary),
+                _ => match parse_freq_bucket(marker) {
+                    Some(bucket) => {
+                        let old = result.frequency_bucket;
+                        result.frequency_bucket = Some(old.map_or(bucket, |b| b.min(bucket)));
+                    }
+                    None => {
+                        return Err(ParsePriorityError {
+                            marker: marker.into(),
+                        });
+                    }
+                },
+            };
+        }
+        Ok(result)
+    }
+}
+
+fn merge_cprio(old: PriorityInCorpus, new: PriorityInCorpus) -> PriorityInCorpus {
+    use PriorityInCorpus::*;
+    match (old, new) {
+        (Absent, _) => new,
+        (_, Primary) => Primary,
+        (Primary, _) => Primary,
+        (Secondary, _) => Secondary,
+    }
+}
+
+///Parses a frequency bucket marker for the news corpus, e.g. "nf18" => Some(18).
+fn parse_freq_bucket(marker: &str) -> Option<u16> {
+    //NOTE: This would be easier with a regex library, but I'm definitely not pulling in an entire
+    //regex crate for just this one thing.
+
+    let mut c = marker.chars();
+    if c.next()? != 'n' {
+        return None;
+    }
+    if c.next()? != 'f' {
+        return None;
+    }
+    let tens = c.next()?.to_digit(10)? as u16;
+    let ones = c.next()?.to_digit(10)? as u16;
+    if c.next().is_some() {
+        return None;
+    }
+    let result = 10 * tens + ones;
+
+    //only nf01..nf48 are allowed
+    if result == 0 || result > 48 {
+        None
+    } else {
+        Some(result)
+    }
+}
+
+///Displays the priority markers in the same notation as in the JMdict, separated by commas, e.g.
+///"ichi1, news2, nf36". A priority with all fields absent is displayed as an empty string.
+impl std::fmt::Display for Priority {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.markers().join(", "))
+    }
+}
+
+///Parses a comma-separated list of priority markers, e.g. "ichi1, news2, nf36". This is the
+///reverse of the `impl Display`.
+///
+///```
+///# use jmdict_enums::Priority;
+///let p: Priority = "ichi1,news2, nf36".parse().unwrap();
+///assert_eq!(p.to_string(), "ichi1, news2, nf36");
+///assert_eq!("".parse(), Ok(Priority::default()));
+///```
+impl std::str::FromStr for Priority {
+    type Err = ParsePriorityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Priority::from_markers(s.split(',').map(str::trim).filter(|m| !m.is_empty()))
+    }
+}
+
+///Error type for [Priority::from_markers] and the `impl FromStr` for [Priority].
+#[derive(Clone, Hash, PartialEq, Eq, Debug)]
+pub struct ParsePriorityError {
+    marker: String,
 }
 
+impl ParsePriorityError {
+    ///Returns the marker that could not be parsed.
+    pub fn marker(&self) -> &str {
+        &self.marker
+    }
+}
+
+impl std::fmt::Display for ParsePriorityError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown priority marker: {:?}", self.marker)
+    }
+}
+
+impl std::error::Error for ParsePriorityError {}
+
+///A single comparable value condensed from a [Priority] by [Priority::score()]. Larger scores
+///indicate more common vocabulary. The `Default::default()` value is the score of a [Priority]
+///with all fields absent, which is the lowest possible score.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PriorityScore(u32);
+
+impl PriorityScore {
+    ///Returns the score as a plain number. Only the relative order of these numbers is
+    ///meaningful; the exact formula may change in future versions.
+    pub fn value(&self) -> u32 {
+        self.0
+    }
+}
+
+//Indexed by `Priority::frequency_bucket` minus 1.
+static FREQUENCY_BUCKET_MARKERS: [&str; 48] = [
+    "nf01", "nf02", "nf03", "nf04", "nf05", "nf06", "nf07", "nf08", "nf09", "nf10", "nf11", "nf12",
+    "nf13", "nf14", "nf15", "nf16", "nf17", "nf18", "nf19", "nf20", "nf21", "nf22", "nf23", "nf24",
+    "nf25", "nf26", "nf27", "nf28", "nf29", "nf30", "nf31", "nf32", "nf33", "nf34", "nf35", "nf36",
+    "nf37", "nf38", "nf39", "nf40", "nf41", "nf42", "nf43", "nf44", "nf45", "nf46", "nf47", "nf48",
+];
+
 //Priority gets serialized into u32, same as the enum types. The lower 16 bits are used for the
 //frequency buckets. The higher 16 bits are evenly distributed among the four PriorityInCorpus
 //fields. The encoding could be denser if we wanted to, but u32 is the smallest encoding unit
 //available to us anyway, so we don't need to bother.
 impl EnumPayload for Priority {
     fn to_u32(&self) -> u32 {
-        let mut result = self.frequency_bucket as u32;
+        //the frequency bucket is never 0, so we can use 0 to encode its absence
+        let mut result = self.frequency_bucket.unwrap_or(0) as u32;
         result |= self.news.to_repr() << 16;
         result |= self.ichimango.to_repr() << 20;
         result |= self.loanwords.to_repr() << 24;
@@ -178,9 +516,120 @@ impl EnumPayload for Priority {
             ichimango: PriorityInCorpus::from_repr((code & 0xF00000) >> 20),
             loanwords: PriorityInCorpus::from_repr((code & 0xF000000) >> 24),
             additional: PriorityInCorpus::from_repr((code & 0xF0000000) >> 28),
-            frequency_bucket: (code & 0xFFFF) as u16,
+            frequency_bucket: match code & 0xFFFF {
+                0 => None,
+                bucket => Some(bucket as u16),
+            },
         }
     }
 }
 
+///Shared implementation of `Deserialize` for all generated enums. The wire format is the same as
+///returned by `Enum::code()`.
+#[cfg(feature = "serde")]
+fn deserialize_code<'de, D, E>(d: D, enum_name: &str) -> Result<E, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    E: Enum,
+{
+    use serde::de::Error;
+    let code = <std::borrow::Cow<'de, str> as serde::Deserialize>::deserialize(d)?;
+    E::from_code(&code).ok_or_else(|| {
+        D::Error::custom(format!(
+            "unknown or disabled {} code: {:?}",
+            enum_name, code
+        ))
+    })
+}
+
 include!(concat!(env!("OUT_DIR"), "/generated.rs"));
+
+impl AllGlossLanguage {
+    ///Returns whether the corresponding variant of `GlossLanguage` is enabled by the compile-time
+    ///configuration, i.e. whether glosses in this language are available.
+    ///
+    ///```
+    ///# use jmdict_enums::*;
+    ///#[cfg(feature = "translations-eng")]
+    ///assert!(AllGlossLanguage::English.is_enabled());
+    ///#[cfg(not(feature = "translations-ger"))]
+    ///assert!(!AllGlossLanguage::German.is_enabled());
+    ///```
+    pub fn is_enabled(&self) -> bool {
+        GlossLanguage::try_from(*self).is_ok()
+    }
+}
+
+impl SenseInfo {
+    ///Returns whether this info marks the sense as a proper name (e.g. of a person, a place or a
+    ///company), using one of the name types that the JMdict shares with the JMnedict.
+    ///
+    ///```
+    ///# use jmdict_enums::*;
+    ///assert!(SenseInfo::PlaceName.is_name_type());
+    ///assert!(!SenseInfo::Slang.is_name_type());
+    ///```
+    pub fn is_name_type(&self) -> bool {
+        use SenseInfo::*;
+        matches!(
+            self,
+            Character
+                | CompanyName
+                | Creature
+                | Deity
+                | Document
+                | Event
+                | Fiction
+                | GivenName
+                | Group
+                | Legend
+                | Mythology
+                | Object
+                | OrganizationName
+                | Other
+                | PersonName
+                | PlaceName
+                | ProductName
+                | Religion
+                | Service
+                | ShipName
+                | RailwayStation
+                | Surname
+                | UnclassifiedName
+                | WorkOfArt
+        )
+    }
+
+    ///Returns whether this info marks the sense as X-rated, vulgar, derogatory or otherwise
+    ///sensitive, i.e. as unsuitable for e.g. educational apps aimed at children.
+    ///
+    ///```
+    ///# use jmdict_enums::*;
+    ///assert!(SenseInfo::VulgarTerm.is_sensitive_content());
+    ///assert!(!SenseInfo::Slang.is_sensitive_content());
+    ///```
+    pub fn is_sensitive_content(&self) -> bool {
+        use SenseInfo::*;
+        matches!(self, XRated | VulgarTerm | Derogatory | Sensitive)
+    }
+}
+
+impl GlossLanguage {
+    ///Like `GlossLanguage::from_code()`, but reports why a code was rejected.
+    ///
+    ///```
+    ///# use jmdict_enums::*;
+    ///#[cfg(feature = "translations-eng")]
+    ///assert_eq!(GlossLanguage::try_from_code("eng"), Ok(GlossLanguage::English));
+    ///assert_eq!(GlossLanguage::try_from_code("xyz"), Err(GlossLanguageCodeError::UnknownCode));
+    ///#[cfg(not(feature = "translations-ger"))]
+    ///assert_eq!(
+    ///    GlossLanguage::try_from_code("ger"),
+    ///    Err(GlossLanguageCodeError::Disabled(AllGlossLanguage::German)),
+    ///);
+    ///```
+    pub fn try_from_code(code: &str) -> Result<Self, GlossLanguageCodeError> {
+        let lang = AllGlossLanguage::from_code(code).ok_or(GlossLanguageCodeError::UnknownCode)?;
+        GlossLanguage::try_from(lang).map_err(|_| GlossLanguageCodeError::Disabled(lang))
+    }
+}
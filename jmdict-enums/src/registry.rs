@@ -0,0 +1,114 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use crate::*;
+use std::marker::PhantomData;
+
+///Information about a single enum variant, as provided by [EnumKind::variants()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct VariantInfo {
+    ///The string that marks this variant in the JMdict, same as `Enum::code()`.
+    pub code: &'static str,
+    ///The variant name in Rust code, same as `Enum::constant_name()`.
+    pub constant_name: &'static str,
+    ///A human-readable description of this variant in English, if the enum provides one.
+    pub description: Option<&'static str>,
+}
+
+///A uniform, object-safe interface to the metadata of one of the enums in this crate. This is
+///useful for code that needs to handle all enums in the same way, e.g. a settings screen that
+///offers to filter entries by tag. The list of all enums is available from [enum_kinds()].
+///
+///```
+///# use jmdict_enums::*;
+///let kind = enum_kind("KanjiInfo").unwrap();
+///let info = kind.variant("ateji").unwrap();
+///assert_eq!(info.constant_name, "Ateji");
+///assert_eq!(info.description, Some("ateji (phonetic) reading"));
+///```
+pub trait EnumKind: Sync {
+    ///Returns the name of the enum type, e.g. "PartOfSpeech".
+    fn name(&self) -> &'static str;
+
+    ///Returns information about all enabled variants of this enum, in the same order as
+    ///`Enum::all_variants()`. The `Unknown` variant (if any) is not included.
+    fn variants(&self) -> Vec<VariantInfo>;
+
+    ///Returns information about the variant with the given JMdict code, or `None` if there is no
+    ///such enabled variant.
+    fn variant(&self, code: &str) -> Option<VariantInfo> {
+        self.variants().into_iter().find(|v| v.code == code)
+    }
+}
+
+struct Kind<E> {
+    name: &'static str,
+    describe: fn(&E) -> Option<&'static str>,
+    phantom: PhantomData<E>,
+}
+
+impl<E: Enum + Sync + 'static> EnumKind for Kind<E> {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn variants(&self) -> Vec<VariantInfo> {
+        E::all_variants()
+            .iter()
+            .map(|v| VariantInfo {
+                code: v.code(),
+                constant_name: v.constant_name(),
+                description: (self.describe)(v),
+            })
+            .collect()
+    }
+}
+
+macro_rules! kind {
+    ($enum: ident, $describe: expr) => {
+        Kind::<$enum> {
+            name: stringify!($enum),
+            describe: $describe,
+            phantom: PhantomData,
+        }
+    };
+}
+
+static DIALECT: Kind<Dialect> = kind!(Dialect, |v| Some(v.description()));
+static GLOSS_GENDER: Kind<GlossGender> = kind!(GlossGender, |_| None);
+static GLOSS_LANGUAGE: Kind<GlossLanguage> = kind!(GlossLanguage, |v| Some(v.english_name()));
+static GLOSS_TYPE: Kind<GlossType> = kind!(GlossType, |_| None);
+static KANJI_INFO: Kind<KanjiInfo> = kind!(KanjiInfo, |v| Some(v.description()));
+static PART_OF_SPEECH: Kind<PartOfSpeech> = kind!(PartOfSpeech, |v| Some(v.description()));
+static READING_INFO: Kind<ReadingInfo> = kind!(ReadingInfo, |v| Some(v.description()));
+static SENSE_INFO: Kind<SenseInfo> = kind!(SenseInfo, |v| Some(v.description()));
+static SENSE_TOPIC: Kind<SenseTopic> = kind!(SenseTopic, |v| Some(v.description()));
+static SOURCE_LANGUAGE: Kind<SourceLanguage> = kind!(SourceLanguage, |v| Some(v.name()));
+
+static ENUM_KINDS: [&dyn EnumKind; 10] = [
+    &DIALECT,
+    &GLOSS_GENDER,
+    &GLOSS_LANGUAGE,
+    &GLOSS_TYPE,
+    &KANJI_INFO,
+    &PART_OF_SPEECH,
+    &READING_INFO,
+    &SENSE_INFO,
+    &SENSE_TOPIC,
+    &SOURCE_LANGUAGE,
+];
+
+///Returns all enums whose values can appear in entries, sorted by name. For enums with an `All`
+///counterpart (e.g. `PartOfSpeech` and `AllPartOfSpeech`), only the enum with the enabled
+///variants is listed.
+pub fn enum_kinds() -> &'static [&'static dyn EnumKind] {
+    &ENUM_KINDS
+}
+
+///Returns the enum with the given name from [enum_kinds()], or `None` if there is no such enum.
+pub fn enum_kind(name: &str) -> Option<&'static dyn EnumKind> {
+    ENUM_KINDS.iter().copied().find(|k| k.name() == name)
+}
@@ -0,0 +1,197 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! Compile-time lookups of entries in the [jmdict crate](https://docs.rs/jmdict/).
+//!
+//! Applications that hard-code example words or a fixed vocabulary for their UI can use the
+//! [entry!] macro to find these entries while compiling, instead of looking them up at runtime:
+//!
+//! ```
+//! let entry = jmdict_macros::entry!("お母さん");
+//! assert_eq!(entry.number, 1002650);
+//! ```
+//!
+//! The build fails if the entry does not exist in the database:
+//!
+//! ```compile_fail
+//! let entry = jmdict_macros::entry!("存在しない言葉");
+//! ```
+//!
+//! # Cargo features
+//!
+//! This crate has its own copy of the database to perform the lookup in, so it must be built with
+//! the same features as the `jmdict` crate. For each feature of the `jmdict` crate that affects
+//! the contents of the database, this crate has a feature of the same name. For example:
+//!
+//! ```toml
+//! [dependencies]
+//! jmdict = { version = "2.0.0", features = ["scope-uncommon"] }
+//! jmdict-macros = { version = "2.0.0", features = ["scope-uncommon"] }
+//! ```
+//!
+//! If the features differ, the expansion of [entry!] fails to compile with an explanatory message.
+//! The `RUST_JMDICT_*` environment variables apply to both crates automatically.
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+use std::convert::TryFrom;
+
+const FINGERPRINT_MISMATCH_MESSAGE: &str = "jmdict and jmdict-macros were built with different \
+    features or data files; enable the same features on both crates";
+
+///Finds the entry with the given headword at compile time, and expands into an expression of type
+///`jmdict::Entry` that refers to this entry without performing any search at runtime.
+///
+///The first argument is the text of one of the entry's kanji elements or reading elements. When
+///multiple entries have this text, the text of one of the entry's reading elements must be given
+///as a second argument to disambiguate:
+///
+///```
+///let entry = jmdict_macros::entry!("上手", "じょうず");
+///assert_eq!(entry.number, 1353320);
+///```
+///
+///The build fails if no entry matches, or if multiple entries match.
+#[proc_macro]
+pub fn entry(input: TokenStream) -> TokenStream {
+    match expand_entry(input) {
+        Ok(output) => output,
+        Err((span, message)) => compile_error(span, &message),
+    }
+}
+
+fn expand_entry(input: TokenStream) -> Result<TokenStream, (Span, String)> {
+    let args = parse_string_args(input)?;
+    let (headword, reading) = match args.as_slice() {
+        [(_, headword)] => (headword, None),
+        [(_, headword), (_, reading)] => (headword, Some(reading)),
+        _ => {
+            let msg =
+                "expected a headword and optionally a reading, e.g. entry!(\"上手\", \"じょうず\")";
+            return Err((Span::call_site(), msg.into()));
+        }
+    };
+
+    let candidates: Vec<(usize, jmdict::Entry)> =
+        jmdict::__private::entry_indexes_with_headword(headword)
+            .map(|idx| (idx, jmdict::__private::entry_at(idx)))
+            .filter(|(_, entry)| match reading {
+                Some(reading) => entry.reading_elements().any(|r| r.text == reading),
+                None => true,
+            })
+            .collect();
+
+    let span = args[0].0;
+    let index = match candidates.as_slice() {
+        [(index, _)] => *index,
+        [] => {
+            return Err((
+                span,
+                format!(
+                    "no entry with headword {:?}{} exists in this build of the jmdict crate",
+                    headword,
+                    match reading {
+                        Some(reading) => format!(" and reading {:?}", reading),
+                        None => String::new(),
+                    }
+                ),
+            ))
+        }
+        _ => {
+            let numbers: Vec<_> = candidates
+                .iter()
+                .map(|(_, e)| e.number.to_string())
+                .collect();
+            let hint = if reading.is_none() {
+                "; to disambiguate, give a kanji element as headword and a reading as second argument"
+            } else {
+                ""
+            };
+            return Err((
+                span,
+                format!(
+                    "headword {:?} is ambiguous: it matches the entries {}{}",
+                    headword,
+                    numbers.join(", "),
+                    hint
+                ),
+            ));
+        }
+    };
+
+    //The index is only meaningful for a database that is identical to ours. Since the jmdict crate
+    //in the user's dependency tree may be built with different features than our copy, this is
+    //checked at compile time through the build fingerprint.
+    let output = format!(
+        concat!(
+            "{{ const _: () = ::core::assert!(",
+            "::jmdict::__private::is_build_fingerprint({:?}), {:?}); ",
+            "::jmdict::__private::entry_at({}) }}",
+        ),
+        jmdict::BUILD_FINGERPRINT,
+        FINGERPRINT_MISMATCH_MESSAGE,
+        index
+    );
+    Ok(output.parse().unwrap())
+}
+
+///Parses a comma-separated list of string literals. Returns each string along with the span of its
+///literal.
+fn parse_string_args(input: TokenStream) -> Result<Vec<(Span, String)>, (Span, String)> {
+    let mut args = Vec::new();
+    let mut expect_comma = false;
+    for token in input {
+        let span = token.span();
+        if expect_comma {
+            match token {
+                TokenTree::Punct(ref p) if p.as_char() == ',' => expect_comma = false,
+                _ => return Err((span, "expected `,`".into())),
+            }
+            continue;
+        }
+        //string literals passed through macro_rules! may arrive wrapped in an invisible group
+        let token = match token {
+            TokenTree::Group(ref g) if g.delimiter() == Delimiter::None => {
+                let mut inner = g.stream().into_iter();
+                match (inner.next(), inner.next()) {
+                    (Some(inner), None) => inner,
+                    _ => token,
+                }
+            }
+            _ => token,
+        };
+        match litrs::StringLit::try_from(&token) {
+            Ok(lit) => args.push((span, lit.value().to_owned())),
+            Err(_) => return Err((span, "expected a string literal".into())),
+        }
+        expect_comma = true;
+    }
+    Ok(args)
+}
+
+///Builds the tokens for `::core::compile_error!("message")`, such that the error is reported at
+///the given location.
+fn compile_error(span: Span, message: &str) -> TokenStream {
+    let mut lit = Literal::string(message);
+    lit.set_span(span);
+    let mut body = Group::new(Delimiter::Parenthesis, TokenTree::from(lit).into());
+    body.set_span(span);
+
+    let mut tokens = Vec::new();
+    for segment in &["core", "compile_error"] {
+        let mut colon1 = Punct::new(':', Spacing::Joint);
+        let mut colon2 = Punct::new(':', Spacing::Alone);
+        colon1.set_span(span);
+        colon2.set_span(span);
+        tokens.push(TokenTree::from(colon1));
+        tokens.push(TokenTree::from(colon2));
+        tokens.push(TokenTree::from(Ident::new(segment, span)));
+    }
+    let mut bang = Punct::new('!', Spacing::Alone);
+    bang.set_span(span);
+    tokens.push(TokenTree::from(bang));
+    tokens.push(TokenTree::from(body));
+    tokens.into_iter().collect()
+}
@@ -0,0 +1,321 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! Deltas between two entrypacks, which only contain the entries that were added, changed or
+//! removed. Since most entries stay the same between two JMdict snapshots, a delta is much smaller
+//! than the entrypack it produces. See [DeltaHeaderObj] for the format.
+
+use crate::entrypack::{hex_encode, open_data_file, parse_header};
+use crate::format::{DeltaHeaderObj, HeaderObj, RemovedObj};
+use std::cmp::Ordering;
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+///The most recent format version of entrypack deltas, which is written by
+///[write_entrypack_delta()].
+pub const DELTA_VERSION: u32 = 1;
+
+///The number of entries that a delta adds, changes or removes. Returned by
+///[write_entrypack_delta()].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DeltaStats {
+    pub added: u64,
+    pub changed: u64,
+    pub removed: u64,
+}
+
+///Writes a delta that turns the entrypack at `old_path` into the entrypack at `new_path`. Both
+///entrypacks must have a header (i.e. format version 2 or later), and their entries must be ordered
+///by sequence number, as is the case for all entrypacks written by [write_entrypack()].
+///
+///[write_entrypack()]: crate::write_entrypack
+pub fn write_entrypack_delta<W: Write>(
+    old_path: &Path,
+    new_path: &Path,
+    out: &mut W,
+) -> Result<DeltaStats, String> {
+    let mut old = EntryReader::open(old_path)?;
+    let mut new = EntryReader::open(new_path)?;
+    let delta_header = DeltaHeaderObj {
+        entrypack_delta: DELTA_VERSION,
+        base_sha256sum: old.header.sha256sum.clone(),
+        header: new.header.clone(),
+    };
+    let write_err = |err: std::io::Error| format!("cannot write delta: {}", err);
+    serde_json::to_writer(&mut *out, &delta_header).map_err(|err| write_err(err.into()))?;
+    out.write_all(b"\n").map_err(write_err)?;
+
+    let mut stats = DeltaStats::default();
+    let (mut old_entry, mut new_entry) = (old.next_entry()?, new.next_entry()?);
+    loop {
+        let order = match (&old_entry, &new_entry) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((old_n, _)), Some((new_n, _))) => old_n.cmp(new_n),
+        };
+        match order {
+            Ordering::Less => {
+                let (n, _) = old_entry.take().unwrap();
+                let removed = RemovedObj { removed: n };
+                serde_json::to_writer(&mut *out, &removed).map_err(|err| write_err(err.into()))?;
+                out.write_all(b"\n").map_err(write_err)?;
+                stats.removed += 1;
+                old_entry = old.next_entry()?;
+            }
+            Ordering::Equal => {
+                let (_, old_line) = old_entry.take().unwrap();
+                let (_, new_line) = new_entry.take().unwrap();
+                if old_line != new_line {
+                    out.write_all(&new_line).map_err(write_err)?;
+                    stats.changed += 1;
+                }
+                old_entry = old.next_entry()?;
+                new_entry = new.next_entry()?;
+            }
+            Ordering::Greater => {
+                let (_, new_line) = new_entry.take().unwrap();
+                out.write_all(&new_line).map_err(write_err)?;
+                stats.added += 1;
+                new_entry = new.next_entry()?;
+            }
+        }
+    }
+
+    //since both files were read in full, we can also check them against their headers
+    old.check()?;
+    new.check()?;
+    Ok(stats)
+}
+
+///Applies the delta at `delta_path` to the entrypack at `old_path`, and writes the resulting
+///entrypack into `out`. Fails if the delta was not made for this entrypack, or if the result does
+///not match the entry count and checksum announced by the delta. (In the latter case, parts of the
+///result may already have been written into `out`.) Returns the header of the result.
+pub fn apply_entrypack_delta<W: Write>(
+    old_path: &Path,
+    delta_path: &Path,
+    out: &mut W,
+) -> Result<HeaderObj, String> {
+    use sha2::{Digest, Sha256};
+
+    let mut old = EntryReader::open(old_path)?;
+    let mut delta = DeltaReader::open(delta_path)?;
+    if delta.header.base_sha256sum != old.header.sha256sum {
+        return Err(format!(
+            "{} does not apply to {}: it was made for the entrypack with the checksum {}, but {} has the checksum {}",
+            delta_path.display(), old_path.display(), delta.header.base_sha256sum,
+            old_path.display(), old.header.sha256sum,
+        ));
+    }
+    let header = delta.header.header.clone();
+    let write_err = |err: std::io::Error| format!("cannot write entrypack: {}", err);
+    serde_json::to_writer(&mut *out, &header).map_err(|err| write_err(err.into()))?;
+    out.write_all(b"\n").map_err(write_err)?;
+
+    let mut hasher = Sha256::new();
+    let mut entry_count = 0;
+    let mut emit = |line: &[u8]| -> Result<(), String> {
+        hasher.update(line);
+        entry_count += 1;
+        out.write_all(line).map_err(write_err)
+    };
+    let (mut old_entry, mut change) = (old.next_entry()?, delta.next_change()?);
+    loop {
+        let order = match (&old_entry, &change) {
+            (None, None) => break,
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (Some((old_n, _)), Some((n, _))) => old_n.cmp(n),
+        };
+        match order {
+            Ordering::Less => {
+                emit(&old_entry.take().unwrap().1)?;
+                old_entry = old.next_entry()?;
+            }
+            Ordering::Equal => {
+                if let (_, Some(new_line)) = change.take().unwrap() {
+                    emit(&new_line)?;
+                }
+                old_entry = old.next_entry()?;
+                change = delta.next_change()?;
+            }
+            Ordering::Greater => {
+                match change.take().unwrap() {
+                    (_, Some(new_line)) => emit(&new_line)?,
+                    (n, None) => {
+                        return Err(format!(
+                            "{} removes entry {}, which does not exist in {}",
+                            delta_path.display(),
+                            n,
+                            old_path.display()
+                        ))
+                    }
+                }
+                change = delta.next_change()?;
+            }
+        }
+    }
+
+    old.check()?;
+    let sha256sum = hex_encode(&hasher.finalize());
+    if entry_count != header.entry_count || sha256sum != header.sha256sum {
+        return Err(format!(
+            "applying {} to {} did not produce the expected result: expected {} entries with the checksum {}, but got {} entries with the checksum {}",
+            delta_path.display(), old_path.display(), header.entry_count, header.sha256sum,
+            entry_count, sha256sum,
+        ));
+    }
+    Ok(header)
+}
+
+///Reads the entries of an entrypack one by one, while checking that they are ordered by sequence
+///number and computing their checksum.
+struct EntryReader<'p> {
+    path: &'p Path,
+    reader: Box<dyn BufRead>,
+    header: HeaderObj,
+    hasher: sha2::Sha256,
+    entry_count: u64,
+    last_n: Option<u32>,
+}
+
+impl<'p> EntryReader<'p> {
+    fn open(path: &'p Path) -> Result<Self, String> {
+        use sha2::Digest;
+        let mut reader = open_data_file(path);
+        let mut line = String::new();
+        reader
+            .read_line(&mut line)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        let header = parse_header(&line, path)?.ok_or_else(|| {
+            format!(
+                "{} is an entrypack in format version 1, which cannot be used with deltas (Convert the JMdict again with the current version of jmdict-entrypack.)",
+                path.display()
+            )
+        })?;
+        Ok(Self {
+            path,
+            reader,
+            header,
+            hasher: sha2::Sha256::new(),
+            entry_count: 0,
+            last_n: None,
+        })
+    }
+
+    ///Returns the sequence number and the line (including the trailing newline) of the next entry.
+    fn next_entry(&mut self) -> Result<Option<(u32, Vec<u8>)>, String> {
+        use sha2::Digest;
+        let mut line = Vec::new();
+        let len = self
+            .reader
+            .read_until(b'\n', &mut line)
+            .map_err(|err| format!("cannot read {}: {}", self.path.display(), err))?;
+        if len == 0 {
+            return Ok(None);
+        }
+        self.hasher.update(&line);
+        self.entry_count += 1;
+
+        let n = parse_sequence_number(&line)
+            .ok_or_else(|| format!("invalid entry in {}", self.path.display()))?;
+        if let Some(last_n) = self.last_n {
+            if n <= last_n {
+                return Err(format!(
+                    "{} is not ordered by sequence number: entry {} comes after entry {}",
+                    self.path.display(),
+                    n,
+                    last_n
+                ));
+            }
+        }
+        self.last_n = Some(n);
+        Ok(Some((n, line)))
+    }
+
+    ///Checks the entries read so far against the header. This should be called after reading all
+    ///entries.
+    fn check(self) -> Result<(), String> {
+        use sha2::Digest;
+        let sha256sum = hex_encode(&self.hasher.finalize());
+        if self.entry_count != self.header.entry_count || sha256sum != self.header.sha256sum {
+            return Err(format!(
+                "{} is truncated or corrupted (Use `jmdict-entrypack --check` for a detailed check.)",
+                self.path.display()
+            ));
+        }
+        Ok(())
+    }
+}
+
+///A line of an entrypack delta: the sequence number of an entry, along with its new line, or None
+///if the entry is removed.
+type Change = (u32, Option<Vec<u8>>);
+
+///Reads the lines of an entrypack delta one by one.
+struct DeltaReader<'p> {
+    path: &'p Path,
+    reader: Box<dyn BufRead>,
+    header: DeltaHeaderObj,
+}
+
+impl<'p> DeltaReader<'p> {
+    fn open(path: &'p Path) -> Result<Self, String> {
+        #[derive(serde::Deserialize)]
+        struct VersionObj {
+            entrypack_delta: u32,
+        }
+
+        let mut reader = open_data_file(path);
+        let mut line = String::new();
+        reader
+            .read_line(&mut line)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        let version = serde_json::from_str::<VersionObj>(&line)
+            .map_err(|_| format!("{} is not an entrypack delta", path.display()))?
+            .entrypack_delta;
+        if version > DELTA_VERSION {
+            return Err(format!(
+                "{} is an entrypack delta in format version {}, but this version of jmdict-traverse only supports versions up to {}",
+                path.display(), version, DELTA_VERSION,
+            ));
+        }
+        let header = serde_json::from_str(&line)
+            .map_err(|err| format!("invalid delta header in {}: {}", path.display(), err))?;
+        Ok(Self {
+            path,
+            reader,
+            header,
+        })
+    }
+
+    ///Returns the next change, or None at the end of the delta.
+    fn next_change(&mut self) -> Result<Option<Change>, String> {
+        let mut line = Vec::new();
+        let len = self
+            .reader
+            .read_until(b'\n', &mut line)
+            .map_err(|err| format!("cannot read {}: {}", self.path.display(), err))?;
+        if len == 0 {
+            return Ok(None);
+        }
+        if let Ok(obj) = serde_json::from_slice::<RemovedObj>(&line) {
+            return Ok(Some((obj.removed, None)));
+        }
+        let n = parse_sequence_number(&line)
+            .ok_or_else(|| format!("invalid line in {}", self.path.display()))?;
+        Ok(Some((n, Some(line))))
+    }
+}
+
+fn parse_sequence_number(line: &[u8]) -> Option<u32> {
+    #[derive(serde::Deserialize)]
+    struct SeqObj {
+        n: u32,
+    }
+    serde_json::from_slice::<SeqObj>(line).ok().map(|obj| obj.n)
+}
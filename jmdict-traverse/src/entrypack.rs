@@ -4,30 +4,104 @@
 * Refer to the file "LICENSE" for details.
 *******************************************************************************/
 
+use crate::format::HeaderObj;
 use hex_literal::hex;
-use std::path::PathBuf;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
 
-const ENTRYPACK_URL: &str = "https://dl.xyrillian.de/jmdict/entrypack-v1-2021-07-19.json.gz";
-const ENTRYPACK_SHA256SUM: [u8; 32] =
-    hex!("6d539f6b1841c213815ec9daa89bf9e5c1046e627f96db50ce800e995c1ca9ca");
+///The most recent format version of the entrypack, which is written by [write_entrypack()]. All
+///versions up to this one can be read.
+pub const ENTRYPACK_VERSION: u32 = 2;
+
+///The hard-coded download location for the entrypack. This can be overridden with a mirror list
+///in `RUST_JMDICT_MIRRORS`, see [Mirror::list_from_env()].
+const DEFAULT_MIRRORS: &[Mirror<&str>] = &[Mirror {
+    url: "https://dl.xyrillian.de/jmdict/entrypack-v1-2021-07-19.json.gz",
+    sha256sum: hex!("6d539f6b1841c213815ec9daa89bf9e5c1046e627f96db50ce800e995c1ca9ca"),
+}];
+
+///A download location for the entrypack, along with the expected checksum of the file found there.
+struct Mirror<S> {
+    url: S,
+    sha256sum: [u8; 32],
+}
+
+impl<S: AsRef<str>> Mirror<S> {
+    ///The name of the file in the cache directory.
+    fn basename(&self) -> &str {
+        self.url.as_ref().rsplit('/').next().unwrap()
+    }
+}
+
+impl Mirror<String> {
+    ///Reads the mirror list from the file referenced by `RUST_JMDICT_MIRRORS`, or returns None if
+    ///that variable is not set. Each line of the file contains a SHA-256 checksum and a URL in the
+    ///same format as the output of `sha256sum`. Empty lines and lines starting with `#` are ignored.
+    fn list_from_env() -> Option<Vec<Self>> {
+        let path = std::env::var_os("RUST_JMDICT_MIRRORS")?;
+        let path = Path::new(&path);
+        let contents = std::fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("cannot read {}: {}", path.display(), err));
+
+        let mirrors: Vec<_> = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| {
+                let mut fields = line.split_whitespace();
+                match (fields.next().and_then(parse_sha256sum), fields.next()) {
+                    (Some(sha256sum), Some(url)) => Mirror {
+                        url: url.to_owned(),
+                        sha256sum,
+                    },
+                    _ => panic!("invalid line in {}: {:?}", path.display(), line),
+                }
+            })
+            .collect();
+        if mirrors.is_empty() {
+            panic!("no mirrors listed in {}", path.display());
+        }
+        Some(mirrors)
+    }
+}
+
+fn parse_sha256sum(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 {
+        return None;
+    }
+    let mut result = [0; 32];
+    for (idx, byte) in result.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(hex.get(2 * idx..2 * idx + 2)?, 16).ok()?;
+    }
+    Some(result)
+}
 
 pub struct EntryPack {
     pub path: PathBuf,
-    pub sha256sum: Option<&'static [u8; 32]>,
+    ///Whether the file is the official JMdict XML file rather than an entrypack.
+    pub is_xml: bool,
 }
 
 impl EntryPack {
     pub fn locate_or_download() -> Self {
+        //the official JMdict XML file takes precedence over any entrypack, if given
+        if let Some(path_str) = std::env::var_os("RUST_JMDICT_SOURCE") {
+            return Self {
+                path: path_str.into(),
+                is_xml: true,
+            };
+        }
+
         match std::env::var_os("RUST_JMDICT_ENTRYPACK") {
             //download from hard-coded source if explicity requested
             Some(s) if s == "default" => Self {
-                path: download_to_cache(ENTRYPACK_URL),
-                sha256sum: Some(&ENTRYPACK_SHA256SUM),
+                path: download_from_mirrors(),
+                is_xml: false,
             },
             //use override path if explicitly given
             Some(path_str) => Self {
                 path: path_str.into(),
-                sha256sum: None,
+                is_xml: false,
             },
             //default behavior: use file from repository for development builds, otherwise download
             //from hard-coded source
@@ -36,64 +110,472 @@ impl EntryPack {
                 if local_path.exists() {
                     Self {
                         path: local_path.into(),
-                        sha256sum: None,
+                        is_xml: false,
                     }
                 } else {
                     Self {
-                        path: download_to_cache(ENTRYPACK_URL),
-                        sha256sum: Some(&ENTRYPACK_SHA256SUM),
+                        path: download_from_mirrors(),
+                        is_xml: false,
                     }
                 }
             }
         }
     }
 
-    pub fn contents(&self) -> String {
-        use libflate::gzip::Decoder;
-        use sha2::{Digest, Sha256};
-        use std::io::Read;
-
-        let data = std::fs::read(&self.path).unwrap();
-        if let Some(expected_hash) = self.sha256sum {
-            let hash = Sha256::digest(&data[..]);
-            assert_eq!(&hash[..], expected_hash);
+    ///Returns the header of the entrypack, or None for entrypacks in format version 1 (which do
+    ///not have a header) and for JMdict XML files.
+    pub fn header(&self) -> Option<HeaderObj> {
+        if self.is_xml {
+            return None;
         }
+        let mut line = String::new();
+        open_data_file(&self.path).read_line(&mut line).unwrap();
+        parse_header(&line, &self.path).unwrap_or_else(|err| panic!("{}", err))
+    }
 
-        //check for GZip magic number
-        if data[0] == 31 && data[1] == 139 {
-            let mut decoder = Decoder::new(&data[..]).unwrap();
-            let mut result = String::with_capacity(100 << 20);
-            decoder.read_to_string(&mut result).unwrap();
-            result
+    ///Calls the given function with each entry line of the entrypack (without trailing newline).
+    ///For JMdict XML files, each `<entry>` is converted into the corresponding entrypack line on
+    ///the fly. The same line buffer is reused for each call, so the data file is never held in
+    ///memory as a whole, and reading does not allocate for each line.
+    pub fn for_each_line<F: FnMut(&str)>(&self, mut f: F) {
+        let mut reader = open_data_file(&self.path);
+        if self.is_xml {
+            for line in crate::xml::XmlEntries::new(reader) {
+                let line = line
+                    .unwrap_or_else(|err| panic!("cannot read {}: {}", self.path.display(), err));
+                f(&line);
+            }
         } else {
-            String::from_utf8(data).unwrap()
+            let mut line = String::new();
+            let mut header = None;
+            let mut entry_count = 0;
+            loop {
+                line.clear();
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+                let line = line.trim_end_matches(&['\r', '\n'][..]);
+                if entry_count == 0 && header.is_none() {
+                    header = parse_header(line, &self.path).unwrap_or_else(|err| panic!("{}", err));
+                    if header.is_some() {
+                        continue;
+                    }
+                }
+                f(line);
+                entry_count += 1;
+            }
+
+            //NOTE: The checksum is not verified here since that would make each build noticeably
+            //slower (build scripts are compiled without optimizations). A truncated file is the
+            //most likely kind of corruption, and is caught by comparing the entry count.
+            if let Some(header) = header {
+                if entry_count != header.entry_count {
+                    panic!(
+                        "{} is truncated or corrupted: its header announces {} entries, but {} were found (Use `jmdict-entrypack --check` for a detailed check.)",
+                        self.path.display(), header.entry_count, entry_count,
+                    );
+                }
+            }
+        }
+    }
+}
+
+///If the given line is the header of an entrypack, parses it. Fails if the entrypack has a format
+///version that we cannot read.
+pub(crate) fn parse_header(line: &str, path: &Path) -> Result<Option<HeaderObj>, String> {
+    //check the version first, since the rest of the header might look different in future versions
+    #[derive(serde::Deserialize)]
+    struct VersionObj {
+        entrypack: u32,
+    }
+    let version = match serde_json::from_str::<VersionObj>(line) {
+        Ok(obj) => obj.entrypack,
+        Err(_) => return Ok(None),
+    };
+    if version > ENTRYPACK_VERSION {
+        return Err(format!(
+            "{} is an entrypack in format version {}, but this version of the jmdict crate only supports versions up to {} (Update the jmdict crate, or convert the JMdict with the jmdict-entrypack tool from the same version as the jmdict crate.)",
+            path.display(), version, ENTRYPACK_VERSION,
+        ));
+    }
+    serde_json::from_str(line)
+        .map(Some)
+        .map_err(|err| format!("invalid entrypack header in {}: {}", path.display(), err))
+}
+
+///Converts a JMdict XML file into an entrypack in the most recent format version. Since the header
+///describes all entries, the converted entries are held in memory until the end.
+///
+///Malformed XML is reported as an error of kind [std::io::ErrorKind::InvalidData]. Nothing is
+///written to `out` in that case.
+pub fn write_entrypack<R: BufRead, W: Write>(xml: R, out: &mut W) -> std::io::Result<()> {
+    use sha2::{Digest, Sha256};
+    let mut entries = crate::xml::XmlEntries::new(xml);
+    let mut body = Vec::new();
+    let mut entry_count = 0;
+    let mut creation_date = None;
+    for line in &mut entries {
+        let line = line?;
+        if let Some(date) = crate::find_creation_date(&line) {
+            creation_date = Some(date.to_owned());
         }
+        body.extend_from_slice(line.as_bytes());
+        body.push(b'\n');
+        entry_count += 1;
     }
+
+    let header = HeaderObj {
+        entrypack: ENTRYPACK_VERSION,
+        //the pseudo-entry with the creation date is only a fallback for the XML comment
+        creation_date: entries.header.creation_date.take().or(creation_date),
+        entry_count,
+        sha256sum: hex_encode(&Sha256::digest(&body)),
+        ..entries.header
+    };
+    serde_json::to_writer(&mut *out, &header)?;
+    out.write_all(b"\n")?;
+    out.write_all(&body)
 }
 
-fn download_to_cache(url: &str) -> PathBuf {
-    //construct path of the form "$HOME/.cache/rust-jmdict/entrypack-YYYY-MM-DD.json.gz"
-    let base_dirs = directories::BaseDirs::new().unwrap();
-    let mut path = PathBuf::new();
-    path.push(base_dirs.cache_dir());
-    path.push("rust-jmdict");
-    std::fs::create_dir_all(&path).unwrap();
-    let basename = url.rsplit('/').next().unwrap();
-    path.push(&basename);
+///Reads the entrypack at the given path in full, and checks that its entries match the entry count
+///and checksum in its header. Returns the header if the check succeeds.
+pub fn check_entrypack(path: &Path) -> Result<HeaderObj, String> {
+    use sha2::{Digest, Sha256};
+    let mut reader = open_data_file(path);
+    let mut line = Vec::new();
+    reader
+        .read_until(b'\n', &mut line)
+        .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+    let line_str = std::str::from_utf8(&line).unwrap_or_default();
+    let header = parse_header(line_str, path)?.ok_or_else(|| {
+        format!(
+            "{} is an entrypack in format version 1, which does not have a header to check against",
+            path.display()
+        )
+    })?;
 
-    //only need to download if not present yet
-    if !path.exists() {
-        //download with `curl`
-        let status = std::process::Command::new("curl")
-            .arg("--fail")
-            .arg("--silent")
-            .arg("--output")
-            .arg(path.as_os_str())
-            .arg(url)
-            .status()
-            .expect("failed to execute curl");
-        assert!(status.success(), "{}", status);
+    let mut hasher = Sha256::new();
+    let mut entry_count = 0;
+    loop {
+        line.clear();
+        let len = reader
+            .read_until(b'\n', &mut line)
+            .map_err(|err| format!("cannot read {}: {}", path.display(), err))?;
+        if len == 0 {
+            break;
+        }
+        hasher.update(&line);
+        entry_count += 1;
+    }
+
+    if entry_count != header.entry_count {
+        return Err(format!(
+            "{} is truncated or corrupted: its header announces {} entries, but {} were found",
+            path.display(),
+            header.entry_count,
+            entry_count
+        ));
+    }
+    let sha256sum = hex_encode(&hasher.finalize());
+    if sha256sum != header.sha256sum {
+        return Err(format!(
+            "{} is corrupted: its header announces the checksum {}, but the entries have the checksum {}",
+            path.display(),
+            header.sha256sum,
+            sha256sum
+        ));
+    }
+    Ok(header)
+}
+
+pub(crate) fn hex_encode(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+///Opens a data file for reading, with decompression if necessary. The compression format (GZip,
+///Zstandard or XZ) is detected from the magic number at the start of the file.
+///
+///Zstandard and XZ are only supported when the `zstd` and `xz` features are enabled, respectively.
+///Files in these formats cause a panic otherwise.
+pub fn open_data_file(path: &Path) -> Box<dyn BufRead> {
+    let file = std::fs::File::open(path).unwrap();
+    let mut reader = BufReader::with_capacity(1 << 16, file);
+    let magic = reader.fill_buf().unwrap();
+    if magic.starts_with(&[0x1F, 0x8B]) {
+        let decoder = libflate::gzip::Decoder::new(reader).unwrap();
+        Box::new(BufReader::with_capacity(1 << 16, decoder))
+    } else if magic.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+        #[cfg(not(feature = "zstd"))]
+        panic!(
+            "{} is compressed with Zstandard, which requires the `zstd` feature of jmdict-traverse (or the `decode-zstd` feature of jmdict)",
+            path.display()
+        );
+        #[cfg(feature = "zstd")]
+        {
+            let decoder = ruzstd::decoding::StreamingDecoder::new(reader)
+                .unwrap_or_else(|err| panic!("cannot decode {}: {}", path.display(), err));
+            Box::new(BufReader::with_capacity(1 << 16, decoder))
+        }
+    } else if magic.starts_with(&[0xFD, b'7', b'z', b'X', b'Z', 0x00]) {
+        #[cfg(not(feature = "xz"))]
+        panic!(
+            "{} is compressed with XZ, which requires the `xz` feature of jmdict-traverse (or the `decode-xz` feature of jmdict)",
+            path.display()
+        );
+        #[cfg(feature = "xz")]
+        Box::new(BufReader::with_capacity(1 << 16, XzDecoder::new(reader)))
+    } else {
+        Box::new(reader)
+    }
+}
+
+///Decodes an XZ stream on a separate thread, since lzma-rs can only decode into a [Write]. The
+///decoded data is passed back in chunks through a bounded channel, so that only a few chunks are
+///held in memory at once.
+#[cfg(feature = "xz")]
+struct XzDecoder {
+    chunks: std::sync::mpsc::Receiver<std::io::Result<Vec<u8>>>,
+    current: std::io::Cursor<Vec<u8>>,
+}
+
+#[cfg(feature = "xz")]
+impl XzDecoder {
+    fn new<R: BufRead + Send + 'static>(mut input: R) -> Self {
+        let (sender, chunks) = std::sync::mpsc::sync_channel(4);
+        let mut output = XzChunkWriter {
+            sender: sender.clone(),
+            buf: Vec::new(),
+        };
+        std::thread::spawn(move || {
+            let result = lzma_rs::xz_decompress(&mut input, &mut output)
+                .map_err(|err| std::io::Error::new(std::io::ErrorKind::InvalidData, err))
+                .and_then(|()| output.flush());
+            if let Err(err) = result {
+                //if the receiving side is gone, there is nobody left to report this to
+                let _ = sender.send(Err(err));
+            }
+        });
+        Self {
+            chunks,
+            current: std::io::Cursor::new(Vec::new()),
+        }
     }
+}
+
+#[cfg(feature = "xz")]
+impl std::io::Read for XzDecoder {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        loop {
+            let n = self.current.read(buf)?;
+            if n > 0 || buf.is_empty() {
+                return Ok(n);
+            }
+            match self.chunks.recv() {
+                Ok(Ok(chunk)) => self.current = std::io::Cursor::new(chunk),
+                Ok(Err(err)) => return Err(err),
+                //the decoder thread has finished without an error
+                Err(_) => return Ok(0),
+            }
+        }
+    }
+}
+
+///The output side of [XzDecoder].
+#[cfg(feature = "xz")]
+struct XzChunkWriter {
+    sender: std::sync::mpsc::SyncSender<std::io::Result<Vec<u8>>>,
+    buf: Vec<u8>,
+}
+
+#[cfg(feature = "xz")]
+impl Write for XzChunkWriter {
+    fn write(&mut self, data: &[u8]) -> std::io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= 1 << 16 {
+            self.flush()?;
+        }
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::take(&mut self.buf);
+        //a send error means that the XzDecoder was dropped, so decoding can stop early
+        self.sender
+            .send(Ok(chunk))
+            .map_err(|_| std::io::Error::from(std::io::ErrorKind::BrokenPipe))
+    }
+}
+
+fn download_from_mirrors() -> PathBuf {
+    match Mirror::list_from_env() {
+        Some(mirrors) => download_to_cache(&mirrors),
+        None => download_to_cache(DEFAULT_MIRRORS),
+    }
+}
+
+fn download_to_cache<S: AsRef<str>>(mirrors: &[Mirror<S>]) -> PathBuf {
+    //construct paths of the form "$CACHE_DIR/entrypack-v1-YYYY-MM-DD.json.gz"
+    let cache_dir = cache_dir();
+    std::fs::create_dir_all(&cache_dir).unwrap_or_else(|err| {
+        panic!(
+            "could not create cache directory {}: {} (Put the path of a writable directory in the RUST_JMDICT_CACHE_DIR environment variable to use that instead.)",
+            cache_dir.display(), err
+        )
+    });
+    let basenames: Vec<&str> = mirrors.iter().map(Mirror::basename).collect();
+
+    //only need to download if not present yet, but do not keep using a corrupted file forever
+    for mirror in mirrors {
+        let path = cache_dir.join(mirror.basename());
+        if !path.exists() {
+            continue;
+        }
+        if has_checksum(&path, &mirror.sha256sum) {
+            purge_cache_if_requested(&cache_dir, &basenames);
+            return path;
+        }
+        println!(
+            "cargo:warning=checksum mismatch for cached file {}, downloading it again",
+            path.display()
+        );
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    //try all mirrors in order
+    let mut errors = Vec::new();
+    for mirror in mirrors {
+        let (url, path) = (mirror.url.as_ref(), cache_dir.join(mirror.basename()));
+        let result = download(url, &path).and_then(|()| {
+            if has_checksum(&path, &mirror.sha256sum) {
+                Ok(())
+            } else {
+                let _ = std::fs::remove_file(&path);
+                Err(format!("checksum mismatch for {}", url))
+            }
+        });
+        match result {
+            Ok(()) => {
+                purge_cache_if_requested(&cache_dir, &basenames);
+                return path;
+            }
+            Err(err) => {
+                if mirrors.len() > 1 {
+                    println!("cargo:warning={}", err);
+                }
+                errors.push(err);
+            }
+        }
+    }
+    panic!("{} ({})", errors.join("; "), DOWNLOAD_HINT);
+}
+
+///Returns the directory where downloaded entrypacks are stored. This is `RUST_JMDICT_CACHE_DIR` if
+///given, or else a subdirectory of the user's cache directory (e.g. `$HOME/.cache/rust-jmdict`).
+fn cache_dir() -> PathBuf {
+    if let Some(path) = std::env::var_os("RUST_JMDICT_CACHE_DIR") {
+        return path.into();
+    }
+    let base_dirs = directories::BaseDirs::new().unwrap_or_else(|| {
+        panic!("could not find the home directory for the download cache (Put the path of a writable directory in the RUST_JMDICT_CACHE_DIR environment variable to use that instead.)")
+    });
+    base_dirs.cache_dir().join("rust-jmdict")
+}
+
+fn has_checksum(path: &Path, sha256sum: &[u8; 32]) -> bool {
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    let mut file = std::fs::File::open(path).unwrap();
+    std::io::copy(&mut file, &mut hasher).unwrap();
+    hasher.finalize()[..] == sha256sum[..]
+}
+
+///If `RUST_JMDICT_PURGE_CACHE` is set, removes all entrypacks except for those that are currently
+///in use from the cache directory. This is not done by default since other projects on the same
+///machine may use different versions of this crate, and thus different entrypacks.
+fn purge_cache_if_requested(cache_dir: &Path, current_basenames: &[&str]) {
+    if std::env::var_os("RUST_JMDICT_PURGE_CACHE").is_none() {
+        return;
+    }
+    for dir_entry in std::fs::read_dir(cache_dir).unwrap() {
+        let dir_entry = dir_entry.unwrap();
+        let name = dir_entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with("entrypack-") && !current_basenames.contains(&&*name) {
+            std::fs::remove_file(dir_entry.path()).unwrap_or_else(|err| {
+                panic!("could not remove {}: {}", dir_entry.path().display(), err)
+            });
+        }
+    }
+}
+
+//NOTE: Download errors are reported with hints for working around them, because they occur in the
+//build script of a dependency, where users may not expect network access at all.
+const DOWNLOAD_HINT: &str = "To build without network access, download the file manually and put its path in the RUST_JMDICT_ENTRYPACK environment variable.";
+
+#[cfg(not(feature = "ureq"))]
+fn download(url: &str, path: &Path) -> Result<(), String> {
+    //download with `curl`
+    let status = std::process::Command::new("curl")
+        .arg("--fail")
+        .arg("--silent")
+        .arg("--output")
+        .arg(path.as_os_str())
+        .arg(url)
+        .status()
+        .map_err(|err| {
+            format!(
+                "could not execute curl to download {}: {} (Install curl, or enable the \"builtin-download\" feature of the jmdict crate to use a built-in HTTP client instead.)",
+                url, err
+            )
+        })?;
+    if !status.success() {
+        //do not leave a partial download in the cache
+        let _ = std::fs::remove_file(path);
+        return Err(format!("download of {} with curl failed: {}", url, status));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "ureq")]
+fn download(url: &str, path: &Path) -> Result<(), String> {
+    const ATTEMPTS: u32 = 3;
+
+    //download into a temporary file first, so that interrupted downloads do not end up in the cache
+    let tmp_path = path.with_extension("part");
+    for attempt in 1..=ATTEMPTS {
+        match download_once(url, &tmp_path) {
+            Ok(()) => {
+                std::fs::rename(&tmp_path, path).unwrap();
+                return Ok(());
+            }
+            Err(err) if attempt < ATTEMPTS => {
+                println!(
+                    "cargo:warning=download of {} failed (attempt {}/{}): {}",
+                    url, attempt, ATTEMPTS, err
+                );
+                std::thread::sleep(std::time::Duration::from_secs(2 << attempt));
+            }
+            Err(err) => {
+                let _ = std::fs::remove_file(&tmp_path);
+                return Err(format!(
+                    "download of {} failed after {} attempts: {} (If you are behind a proxy, set the HTTPS_PROXY and NO_PROXY environment variables.)",
+                    url, ATTEMPTS, err
+                ));
+            }
+        }
+    }
+    unreachable!()
+}
 
-    path
+#[cfg(feature = "ureq")]
+fn download_once(url: &str, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    //NOTE: ureq picks up HTTPS_PROXY, ALL_PROXY and NO_PROXY from the environment by default.
+    let mut response = ureq::get(url).call()?;
+    let mut file = std::fs::File::create(path)?;
+    std::io::copy(&mut response.body_mut().as_reader(), &mut file)?;
+    file.sync_all()?;
+    Ok(())
 }
@@ -0,0 +1,155 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! User-supplied filters for selecting entries at build time, for combinations that cannot be
+//! expressed with the Cargo features of the `jmdict` crate.
+
+use crate::format::SenseObj;
+use crate::RawEntry;
+use jmdict_enums::{
+    AllPartOfSpeech, Dialect, Enum, Priority, PriorityScore, SenseInfo, SenseTopic,
+};
+use serde::Deserialize;
+
+///The contents of the file referenced by `RUST_JMDICT_FILTER`. All fields are optional.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct FilterConfig {
+    ///Priority markers as accepted by `Priority::from_str()`, e.g. "news1, nf20".
+    #[serde(default)]
+    min_priority: Option<String>,
+    #[serde(default)]
+    required_tags: Vec<String>,
+    #[serde(default)]
+    excluded_tags: Vec<String>,
+    #[serde(default)]
+    max_entries: Option<usize>,
+}
+
+///A filter for selecting entries at build time. It is read from the JSON file whose path is given
+///in the `RUST_JMDICT_FILTER` environment variable, for example:
+///
+///```json
+///{
+///  "min_priority": "news1, nf20",
+///  "required_tags": ["v*"],
+///  "excluded_tags": ["arch", "vulg", "ksb"],
+///  "max_entries": 5000
+///}
+///```
+///
+///Tags are the codes of parts of speech, topics, sense infos and dialects, as they appear in the
+///JMdict. A tag ending in `*` stands for all codes with that prefix (e.g. "v*" for all verbs).
+pub struct BuildFilter {
+    ///Only entries with a kanji element or reading element at least this common are included.
+    min_score: Option<PriorityScore>,
+    ///Only entries with at least one sense having all of these tags are included.
+    required_tags: Vec<String>,
+    ///Senses having any of these tags are excluded.
+    excluded_tags: Vec<String>,
+    ///At most this many entries are included, preferring the most common ones.
+    max_entries: Option<usize>,
+}
+
+impl BuildFilter {
+    ///Reads the filter from the file referenced by `RUST_JMDICT_FILTER`, or returns None if that
+    ///variable is not set. Invalid filter files cause a panic.
+    pub fn from_env() -> Option<Self> {
+        let path = std::env::var_os("RUST_JMDICT_FILTER")?;
+        let path = std::path::Path::new(&path);
+        let contents = std::fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("cannot read {}: {}", path.display(), err));
+        Some(Self::parse(&contents).unwrap_or_else(|msg| panic!("{}: {}", path.display(), msg)))
+    }
+
+    ///Parses the contents of a filter file. See type-level docs for the expected format.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let cfg: FilterConfig =
+            serde_json::from_str(contents).map_err(|err| format!("cannot parse: {}", err))?;
+
+        for tag in cfg.required_tags.iter().chain(cfg.excluded_tags.iter()) {
+            if !is_known_tag(tag) {
+                return Err(format!("unknown tag: {:?}", tag));
+            }
+        }
+        let min_score = match cfg.min_priority {
+            Some(markers) => {
+                let prio: Priority = markers
+                    .parse()
+                    .map_err(|err| format!("invalid min_priority: {}", err))?;
+                Some(prio.score())
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            min_score,
+            required_tags: cfg.required_tags,
+            excluded_tags: cfg.excluded_tags,
+            max_entries: cfg.max_entries,
+        })
+    }
+
+    pub(crate) fn max_entries(&self) -> Option<usize> {
+        self.max_entries
+    }
+
+    ///Checks the entry-level conditions of this filter. `senses` are all senses of the entry in
+    ///the data file, including those that were skipped: Since only the senses with English glosses
+    ///carry tags in the JMdict, required tags cannot be checked on the senses of non-English builds.
+    pub(crate) fn accepts_entry(&self, entry: &RawEntry, senses: &[SenseObj]) -> bool {
+        if let Some(min_score) = self.min_score {
+            if entry.priority_score() < min_score {
+                return false;
+            }
+        }
+        self.required_tags.is_empty()
+            || senses.iter().any(|obj| {
+                let tags = sense_tags(obj);
+                (self.required_tags.iter()).all(|pattern| has_tag(&tags, pattern))
+            })
+    }
+
+    pub(crate) fn accepts_sense(&self, obj: &SenseObj) -> bool {
+        let tags = sense_tags(obj);
+        !self
+            .excluded_tags
+            .iter()
+            .any(|pattern| has_tag(&tags, pattern))
+    }
+}
+
+fn sense_tags<'a>(obj: &'a SenseObj) -> Vec<&'a str> {
+    (obj.p.iter())
+        .chain(obj.f.iter())
+        .chain(obj.m.iter())
+        .chain(obj.dial.iter())
+        .map(|t| &**t)
+        .collect()
+}
+
+fn has_tag(tags: &[&str], pattern: &str) -> bool {
+    tags.iter().any(|tag| tag_matches(pattern, tag))
+}
+
+fn tag_matches(pattern: &str, tag: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => tag.starts_with(prefix),
+        None => tag == pattern,
+    }
+}
+
+fn is_known_tag(pattern: &str) -> bool {
+    fn matches_any<E: Enum + 'static>(pattern: &str) -> bool {
+        E::all_variants()
+            .iter()
+            .any(|v| tag_matches(pattern, v.code()))
+    }
+    matches_any::<AllPartOfSpeech>(pattern)
+        || matches_any::<SenseTopic>(pattern)
+        || matches_any::<SenseInfo>(pattern)
+        || matches_any::<Dialect>(pattern)
+}
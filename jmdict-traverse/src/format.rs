@@ -0,0 +1,206 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! Data types describing the format of the entrypack. Each line of the entrypack contains one
+//! [EntryObj] in JSON encoding. Since format version 2, these lines are preceded by one line
+//! containing a [HeaderObj].
+//!
+//! Strings are borrowed from the input where possible, so that parsing an entry does not need to
+//! allocate a separate String for each of its texts.
+
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+///A string within an entrypack entry. This is a separate type since `#[serde(borrow)]` only
+///borrows `Cow` values that appear directly as a struct field, not those within a `Vec`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Text<'a>(#[serde(borrow)] pub Cow<'a, str>);
+
+impl<'a> std::ops::Deref for Text<'a> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<'a> From<String> for Text<'a> {
+    fn from(s: String) -> Self {
+        Text(Cow::Owned(s))
+    }
+}
+
+//NOTE: Empty lists and absent attributes are omitted from the JSON encoding to keep the file small.
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct EntryObj<'a> {
+    pub n: u32,
+    #[serde(rename = "K", default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub kanji_elements: Vec<KanjiElementObj<'a>>,
+    #[serde(rename = "R", default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub reading_elements: Vec<ReadingElementObj<'a>>,
+    #[serde(rename = "S", default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub senses: Vec<SenseObj<'a>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct KanjiElementObj<'a> {
+    #[serde(borrow)]
+    pub t: Text<'a>,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub i: Vec<Text<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub p: Vec<Text<'a>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ReadingElementObj<'a> {
+    #[serde(borrow)]
+    pub t: Text<'a>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub n: bool,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub r: Vec<Text<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub i: Vec<Text<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub p: Vec<Text<'a>>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct SenseObj<'a> {
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub stagk: Vec<Text<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub stagr: Vec<Text<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub p: Vec<Text<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub xref: Vec<Text<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub ant: Vec<Text<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub f: Vec<Text<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub m: Vec<Text<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub i: Vec<Text<'a>>,
+    #[serde(rename = "L", default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub lsources: Vec<LSourceObj<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub dial: Vec<Text<'a>>,
+    #[serde(rename = "G", default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub glosses: Vec<GlossObj<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Vec::is_empty")]
+    pub ex: Vec<ExampleObj<'a>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct LSourceObj<'a> {
+    #[serde(borrow)]
+    pub t: Text<'a>,
+    #[serde(default, borrow, skip_serializing_if = "Option::is_none")]
+    pub l: Option<Text<'a>>,
+    #[serde(
+        rename = "type",
+        default,
+        borrow,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub ls_type: Option<Text<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Option::is_none")]
+    pub wasei: Option<Text<'a>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct GlossObj<'a> {
+    #[serde(borrow)]
+    pub t: Text<'a>,
+    #[serde(default, borrow, skip_serializing_if = "Option::is_none")]
+    pub l: Option<Text<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Option::is_none")]
+    pub g_gend: Option<Text<'a>>,
+    #[serde(default, borrow, skip_serializing_if = "Option::is_none")]
+    pub g_type: Option<Text<'a>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ExampleObj<'a> {
+    #[serde(borrow)]
+    pub src: ExampleSourceObj<'a>,
+    #[serde(borrow)]
+    pub t: Text<'a>,
+    #[serde(rename = "S", borrow)]
+    pub sentences: Vec<ExampleSentenceObj<'a>>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ExampleSourceObj<'a> {
+    #[serde(
+        rename = "type",
+        default,
+        borrow,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub exsrc_type: Option<Text<'a>>,
+    #[serde(borrow)]
+    pub id: Text<'a>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct ExampleSentenceObj<'a> {
+    #[serde(borrow)]
+    pub t: Text<'a>,
+    #[serde(default, borrow, skip_serializing_if = "Option::is_none")]
+    pub l: Option<Text<'a>>,
+}
+
+///The first line of an entrypack in format version 2 or later, which describes the entries that
+///follow. (Entrypacks in format version 1 do not have a header; their first line is already an
+///entry.)
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct HeaderObj {
+    ///The format version of the entrypack. This field is required, which distinguishes headers
+    ///from entries.
+    pub entrypack: u32,
+    ///The creation date of the JMdict snapshot, e.g. "2021-07-19".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub creation_date: Option<String>,
+    ///The revision of the JMdict DTD, e.g. "1.09".
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revision: Option<String>,
+    ///The entities defined in the JMdict DTD, mapping each entity name (e.g. "n") to its
+    ///description (e.g. "noun (common) (futsuumeishi)").
+    #[serde(default)]
+    pub entities: BTreeMap<String, String>,
+    ///The number of entries following the header.
+    pub entry_count: u64,
+    ///The SHA-256 checksum of all lines following the header (each including its trailing
+    ///newline), in hex encoding.
+    pub sha256sum: String,
+}
+
+///The first line of an entrypack delta (see `write_entrypack_delta()`). It is followed by one line
+///for each entry that was added or changed (containing the new entry, in the same format as in an
+///entrypack) or removed (containing a [RemovedObj]), in order of sequence numbers.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct DeltaHeaderObj {
+    ///The format version of the delta. This field is required, which distinguishes delta headers
+    ///from entrypack headers.
+    pub entrypack_delta: u32,
+    ///The checksum (as in [HeaderObj::sha256sum]) of the entrypack that this delta applies to.
+    pub base_sha256sum: String,
+    ///The header of the entrypack that results from applying this delta.
+    pub header: HeaderObj,
+}
+
+///A line in an entrypack delta that removes the entry with the given sequence number.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct RemovedObj {
+    pub removed: u32,
+}
@@ -0,0 +1,114 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! Assignment of JLPT levels to entries. The JMdict does not contain this information, so it has
+//! to be supplied from an external list.
+
+use crate::format::EntryObj;
+use std::collections::HashMap;
+
+///A list of JLPT levels, as read from the file whose path is given in the `RUST_JMDICT_JLPT_LIST`
+///environment variable. Each line of the file has the form `<level> <key>`, where the level is
+///one of "N5" through "N1", and the key is either the sequence number of an entry or a headword.
+///Empty lines and lines starting with `#` are ignored. For example:
+///
+///```text
+///N5 1358280
+///N5 食べる
+///N3 あっさり
+///```
+///
+///A headword matches an entry if it is equal to one of its kanji elements, or to one of its
+///reading elements if the entry does not have any kanji elements.
+pub struct JlptList {
+    by_seq: HashMap<u32, u8>,
+    by_headword: HashMap<String, u8>,
+    ///If set, only entries with at least this level (i.e. this level or easier) are included.
+    min_level: Option<u8>,
+}
+
+impl JlptList {
+    ///Reads the list from the file referenced by `RUST_JMDICT_JLPT_LIST`, or returns None if that
+    ///variable is not set. Invalid files cause a panic.
+    ///
+    ///If the `RUST_JMDICT_JLPT_LEVEL` variable is also set (e.g. to "N3"), only entries with
+    ///that level or an easier level (i.e. N3, N4 and N5 in the example) are included.
+    pub fn from_env() -> Option<Self> {
+        let min_level = std::env::var("RUST_JMDICT_JLPT_LEVEL").ok().map(|val| {
+            parse_level(&val)
+                .unwrap_or_else(|| panic!("invalid value for RUST_JMDICT_JLPT_LEVEL: {:?}", val))
+        });
+        let path = match std::env::var_os("RUST_JMDICT_JLPT_LIST") {
+            Some(path) => std::path::PathBuf::from(path),
+            None => {
+                if min_level.is_some() {
+                    panic!("RUST_JMDICT_JLPT_LEVEL requires RUST_JMDICT_JLPT_LIST to be set");
+                }
+                return None;
+            }
+        };
+        let contents = std::fs::read_to_string(&path)
+            .unwrap_or_else(|err| panic!("cannot read {}: {}", path.display(), err));
+
+        let mut list = Self {
+            by_seq: HashMap::new(),
+            by_headword: HashMap::new(),
+            min_level,
+        };
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut fields = line.split_whitespace();
+            let (level, key) = match (fields.next().and_then(parse_level), fields.next()) {
+                (Some(level), Some(key)) => (level, key),
+                _ => panic!("invalid line in {}: {:?}", path.display(), line),
+            };
+            //if a word is listed multiple times, it counts towards the easiest level
+            let stored = match key.parse() {
+                Ok(seq) => list.by_seq.entry(seq).or_insert(level),
+                Err(_) => list.by_headword.entry(key.into()).or_insert(level),
+            };
+            *stored = (*stored).max(level);
+        }
+        Some(list)
+    }
+
+    ///Returns the JLPT level of the given entry (5 for N5, 4 for N4, etc.), if it is listed.
+    pub(crate) fn level_of(&self, obj: &EntryObj) -> Option<u8> {
+        if let Some(&level) = self.by_seq.get(&obj.n) {
+            return Some(level);
+        }
+        let headwords = if obj.kanji_elements.is_empty() {
+            obj.reading_elements
+                .iter()
+                .map(|r| &*r.t)
+                .collect::<Vec<_>>()
+        } else {
+            obj.kanji_elements.iter().map(|k| &*k.t).collect()
+        };
+        headwords
+            .into_iter()
+            .filter_map(|text| self.by_headword.get(text).copied())
+            .max()
+    }
+
+    ///Returns whether an entry with the given level shall be included.
+    pub(crate) fn accepts(&self, level: Option<u8>) -> bool {
+        match self.min_level {
+            Some(min_level) => level.is_some_and(|l| l >= min_level),
+            None => true,
+        }
+    }
+}
+
+///Parses a level like "N3" into the corresponding number.
+fn parse_level(text: &str) -> Option<u8> {
+    match text.strip_prefix('N')?.parse() {
+        Ok(level @ 1..=5) => Some(level),
+        _ => None,
+    }
+}
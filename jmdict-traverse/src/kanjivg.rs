@@ -0,0 +1,137 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! Stroke order data for kanji and kana. The JMdict does not contain this information, so it has
+//! to be supplied from an external KanjiVG file.
+
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::io::BufRead;
+
+///The contents of a KanjiVG file, as read from the file whose path is given in the
+///`RUST_JMDICT_KANJIVG` environment variable. This is usually the combined file from the KanjiVG
+///releases (e.g. `kanjivg-20230110.xml.gz`), but a single SVG file from the KanjiVG repository
+///works as well. It can be compressed like the JMdict file itself.
+///
+///Only the strokes of the default form of each character are read. Variant forms (e.g. the ones
+///with `-Kaisho` in their IDs) are ignored.
+pub struct KanjiVgFile {
+    ///All characters in the file, in order of their Unicode codepoints.
+    pub characters: Vec<KanjiVgCharacter>,
+}
+
+///A single character in a [KanjiVgFile].
+pub struct KanjiVgCharacter {
+    ///The character itself.
+    pub text: char,
+    ///The SVG path data (i.e. the `d` attribute) of each stroke, in stroke order. The coordinates
+    ///refer to the 109x109 canvas that is used throughout KanjiVG.
+    pub strokes: Vec<String>,
+}
+
+impl KanjiVgFile {
+    ///Reads the file referenced by `RUST_JMDICT_KANJIVG`, or returns None if that variable is not
+    ///set. Invalid files cause a panic.
+    pub fn from_env() -> Option<Self> {
+        let path = std::path::PathBuf::from(std::env::var_os("RUST_JMDICT_KANJIVG")?);
+        let reader = crate::open_data_file(&path);
+        Some(Self::parse(reader).unwrap_or_else(|msg| panic!("{}: {}", path.display(), msg)))
+    }
+
+    ///Parses the contents of a KanjiVG file. Strokes are recognized by their IDs of the form
+    ///`kvg:<codepoint>-s<number>`, so that the enclosing structure does not matter.
+    pub fn parse<R: BufRead>(reader: R) -> Result<Self, String> {
+        let mut reader = Reader::from_reader(reader);
+        let mut buf = Vec::new();
+        //strokes are collected with their numbers, since the file order is not guaranteed to be
+        //the stroke order
+        let mut strokes: Vec<(char, u32, String)> = Vec::new();
+        loop {
+            match reader.read_event_into(&mut buf) {
+                Ok(Event::Start(e)) | Ok(Event::Empty(e)) => {
+                    if e.name().as_ref() == b"path" {
+                        if let Some(stroke) = parse_stroke(&e)? {
+                            strokes.push(stroke);
+                        }
+                    }
+                }
+                Ok(Event::Eof) => break,
+                Ok(_) => {}
+                Err(err) => {
+                    return Err(format!(
+                        "XML error at position {}: {}",
+                        reader.buffer_position(),
+                        err
+                    ))
+                }
+            }
+            buf.clear();
+        }
+
+        strokes.sort_by_key(|(text, number, _)| (*text, *number));
+        let mut characters: Vec<KanjiVgCharacter> = Vec::new();
+        for (text, number, path) in strokes {
+            match characters.last_mut() {
+                Some(c) if c.text == text => {
+                    if number as usize != c.strokes.len() + 1 {
+                        return Err(format!("stroke {} of {:?} is missing", number - 1, text));
+                    }
+                    c.strokes.push(path);
+                }
+                _ => {
+                    if number != 1 {
+                        return Err(format!("stroke 1 of {:?} is missing", text));
+                    }
+                    characters.push(KanjiVgCharacter {
+                        text,
+                        strokes: vec![path],
+                    });
+                }
+            }
+        }
+        Ok(Self { characters })
+    }
+}
+
+///Returns the character, stroke number and path data of the given `<path>` element, or None if it
+///is not a stroke of the default form of a character.
+fn parse_stroke(e: &BytesStart) -> Result<Option<(char, u32, String)>, String> {
+    let mut id = None;
+    let mut path = None;
+    for attr in e.attributes() {
+        let attr = attr.map_err(|err| err.to_string())?;
+        let value = attr.unescape_value().map_err(|err| err.to_string())?;
+        match attr.key.as_ref() {
+            b"id" => id = Some(value.into_owned()),
+            b"d" => path = Some(value.into_owned()),
+            _ => {}
+        }
+    }
+    let id = match id {
+        Some(id) => id,
+        None => return Ok(None),
+    };
+
+    //the ID looks like `kvg:04e00-s1`, or `kvg:04e00-Kaisho-s1` for variant forms
+    let (codepoint, number) = match id
+        .strip_prefix("kvg:")
+        .and_then(|rest| rest.split_once("-s"))
+    {
+        Some(pair) => pair,
+        None => return Ok(None),
+    };
+    let text = u32::from_str_radix(codepoint, 16)
+        .ok()
+        .and_then(char::from_u32);
+    let (text, number) = match (text, number.parse()) {
+        (Some(text), Ok(number)) => (text, number),
+        _ => return Ok(None),
+    };
+    match path {
+        Some(path) => Ok(Some((text, number, path))),
+        None => Err(format!("stroke {:?} has no path data", id)),
+    }
+}
@@ -9,122 +9,520 @@
 //! This code is in a separate crate because, if we put it in the `jmdict` crate itself, its
 //! `build.rs` could not import it.
 //!
-//! # Compatibility promise
+//! # Stable API
 //!
-//! **There is none.** Although this crate is published on crates.io for technical reasons, this
-//! crate is internal to the `jmdict` crate. Its API may change at any time, including in
-//! bugfix releases. Use the [API provided by the `jmdict` crate](https://docs.rs/jmdict/) instead.
+//! The parsing pipeline that the `jmdict` crate uses to build its database can also be used by
+//! other crates, e.g. to generate their own payloads, indexes or exports from the JMdict. For this
+//! purpose, the following parts of this crate follow semantic versioning:
+//!
+//! * the [Visitor] and [ShardVisitor] traits,
+//! * [process_dictionary()], [process_dictionary_sharded()] and [locate_data_file()],
+//! * [Options] along with [enum_list_from_env()], [BuildFilter::from_env()] and
+//!   [JlptList::from_env()], and
+//! * the entry types that visitors receive, i.e. [RawEntry] and all `Raw*` types contained in it.
+//!
+//! All other parts of this crate are internal to the `jmdict` crate and the `jmdict-entrypack`
+//! tool, and may change at any time, including in bugfix releases.
+//!
+//! A visitor receives each entry that is selected by the [Options]:
+//!
+//! ```no_run
+//! use jmdict_traverse::{Options, RawEntry, Visitor};
+//!
+//! #[derive(Default)]
+//! struct ReadingCounter {
+//!     count: usize,
+//! }
+//!
+//! impl Visitor for ReadingCounter {
+//!     fn process_entry(&mut self, entry: &RawEntry) {
+//!         self.count += entry.r_ele.len();
+//!     }
+//! }
+//!
+//! let mut counter = ReadingCounter::default();
+//! jmdict_traverse::process_dictionary(&mut counter, Options::default());
+//! println!("the JMdict contains {} reading elements", counter.count);
+//! ```
+//!
+//! The data file is located in the same way as for the `jmdict` crate, so the
+//! `RUST_JMDICT_ENTRYPACK` and `RUST_JMDICT_SOURCE` environment variables apply. Downloading the
+//! default entrypack requires the `ureq` feature of this crate. Fields of the entry types are named
+//! after the respective elements and attributes of the JMdict XML file; refer to its DTD for
+//! details. Enum types come from the `jmdict-enums` crate.
+//!
+//! New fields may be added to the entry types and to [Options] (which are therefore marked
+//! `#[non_exhaustive]`), and new methods with default implementations may be added to [Visitor]
+//! without a major version bump.
 
+use format::*;
 use jmdict_enums::{
-    AllGlossLanguage, AllPartOfSpeech, Dialect, Enum, GlossLanguage, GlossType, KanjiInfo,
-    PartOfSpeech, Priority, PriorityInCorpus, ReadingInfo, SenseInfo, SenseTopic,
+    AllGlossLanguage, AllPartOfSpeech, Dialect, Enum, EnumPayload, GlossGender, GlossLanguage,
+    GlossType, KanjiInfo, PartOfSpeech, Priority, PriorityScore, ReadingInfo, SenseInfo,
+    SenseTopic, SourceLanguage,
 };
-use json::JsonValue;
+use std::collections::HashSet;
 use std::convert::TryInto;
 
+mod delta;
+pub use delta::{apply_entrypack_delta, write_entrypack_delta, DeltaStats, DELTA_VERSION};
 mod entrypack;
+mod filter;
+pub use filter::BuildFilter;
+mod format;
+mod jlpt;
+mod kanjivg;
+mod radk;
 use entrypack::EntryPack;
-
+pub use entrypack::{check_entrypack, open_data_file, write_entrypack, ENTRYPACK_VERSION};
+pub use format::{DeltaHeaderObj, HeaderObj, RemovedObj};
+pub use jlpt::JlptList;
+pub use kanjivg::{KanjiVgCharacter, KanjiVgFile};
+pub use radk::{RadkFile, RadkRadical};
+mod xml;
+pub use xml::XmlEntries;
+
+///An entry of the JMdict, as seen by a [Visitor]. Only those parts of the entry that are selected by
+///the [Options] are included.
+#[non_exhaustive]
 pub struct RawEntry<'a> {
+    ///The sequence number of this entry.
     pub ent_seq: u32,
+    ///The JLPT level of this entry (5 for N5, 4 for N4, etc.), if a [JlptList] was given.
+    pub jlpt_level: Option<u8>,
+    ///The kanji elements of this entry. This may be empty.
     pub k_ele: Vec<RawKanjiElement<'a>>,
+    ///The reading elements of this entry. This is never empty.
     pub r_ele: Vec<RawReadingElement<'a>>,
+    ///The senses of this entry. This is never empty.
     pub sense: Vec<RawSense<'a>>,
 }
 
+impl<'a> RawEntry<'a> {
+    ///Returns the priority score of the most common kanji element or reading element.
+    pub fn priority_score(&self) -> PriorityScore {
+        let k_scores = self.k_ele.iter().map(|k| k.ke_pri.score());
+        let r_scores = self.r_ele.iter().map(|r| r.re_pri.score());
+        k_scores.chain(r_scores).max().unwrap_or_default()
+    }
+}
+
+///A kanji element of a [RawEntry].
+#[non_exhaustive]
 pub struct RawKanjiElement<'a> {
+    ///The text of this element.
     pub keb: &'a str,
     pub ke_inf: Vec<KanjiInfo>,
     pub ke_pri: Priority,
 }
 
+///A reading element of a [RawEntry].
+#[non_exhaustive]
 pub struct RawReadingElement<'a> {
+    ///The text of this element.
     pub reb: &'a str,
+    ///Whether this reading does not belong to any of the kanji elements of the entry.
     pub re_nokanji: bool,
+    ///If not empty, this reading only belongs to the kanji elements with these texts.
     pub re_restr: Vec<&'a str>,
     pub re_inf: Vec<ReadingInfo>,
     pub re_pri: Priority,
 }
 
+///A sense of a [RawEntry].
+#[non_exhaustive]
 pub struct RawSense<'a> {
+    ///The position of this sense within its entry in the JMdict, starting at 1. This can differ
+    ///from the position within `RawEntry::sense` when senses have been filtered out.
+    pub number: u32,
+    ///If not empty, this sense only applies to the kanji elements with these texts.
     pub stagk: Vec<&'a str>,
+    ///If not empty, this sense only applies to the reading elements with these texts.
     pub stagr: Vec<&'a str>,
     pub pos: Vec<PartOfSpeech>,
+    ///Cross-references to related entries, in the format of the JMdict (e.g. "上手・じょうず・1").
     pub xref: Vec<&'a str>,
+    ///Cross-references to antonyms, in the same format as `xref`.
     pub ant: Vec<&'a str>,
     pub field: Vec<SenseTopic>,
     pub misc: Vec<SenseInfo>,
+    ///Additional information about this sense as free text.
     pub s_inf: Vec<&'a str>,
     pub lsource: Vec<RawLSource<'a>>,
     pub dial: Vec<Dialect>,
     pub gloss: Vec<RawGloss<'a>>,
+    pub example: Vec<RawExample<'a>>,
 }
 
+///The source of a loanword in a [RawSense].
+#[non_exhaustive]
 pub struct RawLSource<'a> {
     //NOTE: We do not use the GlossLanguage enum for the lang attribute, because doing so would add
     //a very long tail of rare loanword source languages to that enum. (Also, we could not restrict
     //variants of GlossLanguage to feature flags in the way we currently do.)
     pub text: &'a str,
-    pub lang: &'a str,
+    pub lang: SourceLanguage,
+    ///Whether the source word only describes part of this sense (`ls_type="part"`).
     pub is_partial: bool,
+    ///Whether this word was constructed from words in the source language, but does not exist
+    ///in that language (`ls_wasei="y"`).
     pub is_wasei: bool,
 }
 
+///A gloss of a [RawSense].
+#[non_exhaustive]
 pub struct RawGloss<'a> {
-    //NOTE: g_gend and pri are not mapped since they do not actually occur in any entries
+    //NOTE: pri is not mapped since it does not actually occur in any entries
     pub text: &'a str,
     pub lang: GlossLanguage,
+    pub g_gend: GlossGender,
     pub g_type: GlossType,
 }
 
+///An example sentence of a [RawSense].
+#[non_exhaustive]
+pub struct RawExample<'a> {
+    ///The ID of the example sentence in the Tatoeba project.
+    pub source_id: u32,
+    ///The form of the headword that is used in the example sentence.
+    pub text: &'a str,
+    ///The example sentence in Japanese.
+    pub sentence: &'a str,
+    pub translations: Vec<RawExampleTranslation<'a>>,
+}
+
+///A translation of a [RawExample].
+#[non_exhaustive]
+pub struct RawExampleTranslation<'a> {
+    pub text: &'a str,
+    pub lang: GlossLanguage,
+}
+
 ///Strategy for processing a JMdict file.
 pub trait Visitor {
+    ///This is called once for each entry that is selected by the [Options], in the order in which
+    ///the entries appear in the data file (i.e. ordered by sequence number).
     fn process_entry(&mut self, entry: &RawEntry);
 
     ///This is called once for each file that was read from disk. The build script uses this to
     ///generate `cargo:rerun-if-changed` directives.
     fn notify_data_file_path(&mut self, _path: &str) {}
+
+    ///This is called once with the creation date of the JMdict snapshot (in the format
+    ///"YYYY-MM-DD"), if the data file contains one. The date is reported regardless of which
+    ///entries are selected by the [Options], after all entries have been processed.
+    fn notify_creation_date(&mut self, _date: &str) {}
 }
 
 ///Options for traversing a JMdict file. This controls which entries the [Visitor] visits, and
 ///which parts of the entries it sees.
+///
+///The [default](Options::default) options select all entries and all of their contents, like the
+///`full` feature of the `jmdict` crate. Options are constructed by changing individual fields of
+///the default options:
+///
+///```
+///let mut opts = jmdict_traverse::Options::default();
+///opts.with_archaic = false;
+///```
+#[non_exhaustive]
 pub struct Options {
+    ///If true, only the first few entries are visited, like with the `db-minimal` feature of the
+    ///`jmdict` crate.
     pub is_db_minimal: bool,
+    ///If false, kanji elements and reading elements without any priority are skipped.
     pub with_uncommon: bool,
+    ///If false, senses with the "archaic" label are skipped.
     pub with_archaic: bool,
+    ///If false, senses that describe proper names are skipped.
+    pub with_names: bool,
+    ///If false, senses that are marked as X-rated, vulgar, derogatory or sensitive are skipped.
+    pub with_sensitive: bool,
+    ///If given, only senses with at least one of these topics are included.
+    pub topics: Option<Vec<SenseTopic>>,
+    ///If given, only entries with at least one of these parts of speech are included.
+    pub parts_of_speech: Option<Vec<PartOfSpeech>>,
+    ///If given, entries and senses are additionally filtered as described by the filter.
+    pub filter: Option<BuildFilter>,
+    ///If given, JLPT levels are assigned to entries from this list, and entries are filtered by
+    ///level if the list asks for that.
+    pub jlpt: Option<JlptList>,
+    ///If true, values that cannot be represented by our enums (e.g. because a newer JMdict uses a
+    ///loanword source language that we do not know yet) are skipped with a build warning instead
+    ///of causing a panic. Example sentences that cannot be parsed are skipped in the same way.
+    pub is_tolerant: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            is_db_minimal: false,
+            with_uncommon: true,
+            with_archaic: true,
+            with_names: true,
+            with_sensitive: true,
+            topics: None,
+            parts_of_speech: None,
+            filter: None,
+            jlpt: None,
+            is_tolerant: false,
+        }
+    }
 }
 
-///Entry point for this file. All other functions are called directly or indirectly from this fn.
+///A [Visitor] that can be split into shards, so that entries can be visited on multiple threads.
+///See [process_dictionary_sharded()].
+pub trait ShardVisitor: Visitor + Default + Send {
+    ///Appends the results of `shard` to `self`. Shards are merged in the order in which their
+    ///entries appear in the data file, so `shard` has visited the entries directly following those
+    ///visited by `self`. Shards do not receive any of the `notify_` calls.
+    fn merge(&mut self, shard: Self);
+}
+
+///Reads a comma-separated list of enum codes (e.g. "med,pharm,anat") from the given environment
+///variable, for use in [Options]. A code ending in `*` stands for all codes with that prefix (e.g.
+///"v*" for all kinds of verbs). Returns None if the variable is not set or empty.
+pub fn enum_list_from_env<E: Enum + Copy + 'static>(var: &str) -> Option<Vec<E>> {
+    let val = std::env::var(var).ok()?;
+    let mut list: Vec<E> = Vec::new();
+    for code in val.split(',').map(str::trim).filter(|c| !c.is_empty()) {
+        let len_before = list.len();
+        match code.strip_suffix('*') {
+            Some(prefix) => list.extend(
+                E::all_variants()
+                    .iter()
+                    .filter(|v| v.code().starts_with(prefix))
+                    .copied(),
+            ),
+            None => list.extend(E::from_code(code)),
+        }
+        if list.len() == len_before {
+            panic!("invalid value in {}: {:?}", var, code);
+        }
+    }
+    if list.is_empty() {
+        None
+    } else {
+        Some(list)
+    }
+}
+
+///Returns the path of the data file that [process_dictionary()] reads from, downloading it first if
+///necessary. This is the JMdict XML file given in `RUST_JMDICT_SOURCE` or the entrypack given in
+///`RUST_JMDICT_ENTRYPACK`, if any. Otherwise, when running in the repository of the `jmdict` crate,
+///its development copy is used; else the default entrypack is downloaded.
+pub fn locate_data_file() -> std::path::PathBuf {
+    EntryPack::locate_or_download().path
+}
+
+//NOTE: This is the entry point for this file. All other functions are called directly or
+//indirectly from here.
+
+///Visits all entries of the JMdict that are selected by the given options. The data file is
+///located as described in [locate_data_file()].
+///
+///Entries that are skipped by the options are not visited at all. Entries without any remaining
+///reading elements or senses are skipped as well.
 pub fn process_dictionary<V: Visitor>(v: &mut V, opts: Options) {
     let entrypack = EntryPack::locate_or_download();
     v.notify_data_file_path(&entrypack.path.to_string_lossy());
+    let selection = select_entries(&entrypack, &opts);
+
+    let mut creation_date = entrypack.header().and_then(|header| header.creation_date);
+    let scan_for_creation_date = creation_date.is_none();
+    let mut is_done = false;
+    entrypack.for_each_line(|entry_str| {
+        if let Some(date) = find_creation_date(entry_str).filter(|_| scan_for_creation_date) {
+            creation_date = Some(date.to_owned());
+        }
+        if !is_done {
+            is_done = !visit_line(v, entry_str, &opts, selection.as_ref());
+        }
+    });
+
+    if let Some(date) = creation_date {
+        v.notify_creation_date(&date);
+    }
+}
+
+///How many lines of the data file are processed by each shard in [process_dictionary_sharded()].
+const LINES_PER_SHARD: usize = 4096;
+
+///Like [process_dictionary()], but parses and visits entries on the given number of threads.
+///
+///The data file is still read on the calling thread, but its lines are split into shards of
+///consecutive entries. Each shard is visited by a fresh visitor on one of the worker threads, and
+///the shard visitors are then merged into `v` in the original order of entries.
+pub fn process_dictionary_sharded<V: ShardVisitor>(v: &mut V, opts: Options, threads: usize) {
+    if threads <= 1 {
+        return process_dictionary(v, opts);
+    }
 
-    for entry_str in entrypack.contents().split('\n') {
-        if !entry_str.is_empty() {
-            let entry_obj = json::parse(entry_str).unwrap();
-            if let Some(entry_raw) = RawEntry::from_obj(&entry_obj, &opts) {
-                if opts.is_db_minimal && entry_raw.ent_seq >= 1010000 {
-                    //for db-minimal, only process entries from data/entries-100.json
-                    return;
+    let entrypack = EntryPack::locate_or_download();
+    v.notify_data_file_path(&entrypack.path.to_string_lossy());
+    let selection = select_entries(&entrypack, &opts);
+
+    let mut creation_date = entrypack.header().and_then(|header| header.creation_date);
+    let scan_for_creation_date = creation_date.is_none();
+    let opts = &opts;
+    let selection = selection.as_ref();
+    let mut shards = std::thread::scope(|scope| {
+        //NOTE: Each worker owns its receiver, so that a worker panicking (e.g. because of a parse
+        //error) does not leave the reading thread stuck on a full channel.
+        let mut senders = Vec::with_capacity(threads);
+        let mut workers = Vec::with_capacity(threads);
+        for _ in 0..threads {
+            let (sender, receiver) = std::sync::mpsc::sync_channel::<(usize, String)>(2);
+            senders.push(sender);
+            workers.push(scope.spawn(move || {
+                let mut shards = Vec::new();
+                for (shard_index, lines) in receiver {
+                    let mut shard = V::default();
+                    for line in lines.lines() {
+                        if !visit_line(&mut shard, line, opts, selection) {
+                            break;
+                        }
+                    }
+                    shards.push((shard_index, shard));
                 }
-                v.process_entry(&entry_raw);
+                shards
+            }));
+        }
+
+        //distribute shards round-robin across the workers
+        let mut shard_count = 0;
+        let mut lines = String::new();
+        let mut line_count = 0;
+        let mut send_shard = |lines: String| {
+            //if the worker has panicked, sending fails and the panic is propagated when joining below
+            let sender = &senders[shard_count % threads];
+            sender.send((shard_count, lines)).ok();
+            shard_count += 1;
+        };
+        entrypack.for_each_line(|entry_str| {
+            if let Some(date) = find_creation_date(entry_str).filter(|_| scan_for_creation_date) {
+                creation_date = Some(date.to_owned());
+            }
+            lines.push_str(entry_str);
+            lines.push('\n');
+            line_count += 1;
+            if line_count == LINES_PER_SHARD {
+                send_shard(std::mem::take(&mut lines));
+                line_count = 0;
             }
+        });
+        if line_count > 0 {
+            send_shard(lines);
         }
+        drop(senders);
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().unwrap())
+            .collect::<Vec<_>>()
+    });
+
+    shards.sort_by_key(|(shard_index, _)| *shard_index);
+    for (_, shard) in shards {
+        v.merge(shard);
+    }
+    if let Some(date) = creation_date {
+        v.notify_creation_date(&date);
+    }
+}
+
+///Parses one line of the data file and visits the resulting entry (unless it is filtered out by the
+///given options, or not contained in the given selection). Returns false when all remaining lines
+///shall be skipped.
+fn visit_line<V: Visitor>(
+    v: &mut V,
+    entry_str: &str,
+    opts: &Options,
+    selection: Option<&HashSet<u32>>,
+) -> bool {
+    if entry_str.is_empty() {
+        return true;
+    }
+    let entry_obj: EntryObj = serde_json::from_str(entry_str).unwrap();
+    if let Some(entry_raw) = RawEntry::from_obj(&entry_obj, opts) {
+        if opts.is_db_minimal && entry_raw.ent_seq >= 1010000 {
+            //for db-minimal, only process entries from data/entries-100.json (but keep reading
+            //to find the creation date)
+            return false;
+        }
+        if selection.is_none_or(|s| s.contains(&entry_raw.ent_seq)) {
+            v.process_entry(&entry_raw);
+        }
+    }
+    true
+}
+
+///If the number of entries is limited by the `RUST_JMDICT_MAX_ENTRIES` environment variable or by
+///the [BuildFilter], returns the sequence numbers of the most common entries up to that limit. To
+///rank the entries, this reads the entire data file once before the actual traversal.
+fn select_entries(entrypack: &EntryPack, opts: &Options) -> Option<HashSet<u32>> {
+    let env_limit = std::env::var("RUST_JMDICT_MAX_ENTRIES").ok().map(|val| {
+        val.parse()
+            .unwrap_or_else(|_| panic!("invalid value for RUST_JMDICT_MAX_ENTRIES: {:?}", val))
+    });
+    let filter_limit = opts.filter.as_ref().and_then(BuildFilter::max_entries);
+    let max_entries: usize = env_limit.into_iter().chain(filter_limit).min()?;
+
+    struct Ranking(Vec<(std::cmp::Reverse<PriorityScore>, u32)>);
+
+    impl Visitor for Ranking {
+        fn process_entry(&mut self, entry: &RawEntry) {
+            let score = entry.priority_score();
+            self.0.push((std::cmp::Reverse(score), entry.ent_seq));
+        }
+    }
+
+    let mut ranking = Ranking(Vec::new());
+    let mut is_done = false;
+    entrypack.for_each_line(|entry_str| {
+        if !is_done {
+            is_done = !visit_line(&mut ranking, entry_str, opts, None);
+        }
+    });
+
+    //most common entries first; among equally common entries, prefer those that come first in the
+    //JMdict (which roughly corresponds to the order in which they were added)
+    ranking.0.sort_unstable();
+    ranking.0.truncate(max_entries);
+    Some(ranking.0.into_iter().map(|(_, ent_seq)| ent_seq).collect())
+}
+
+///The JMdict carries its creation date in the gloss of a pseudo-entry at the very end of the
+///file, e.g. "Japanese-Multilingual Dictionary Project - Creation Date: 2021-07-19". We search the
+///raw text of each line for it since that entry may be filtered out (or not even parsed with
+///db-minimal). This is only needed if the entrypack does not have a header with the creation date.
+fn find_creation_date(contents: &str) -> Option<&str> {
+    const MARKER: &str = "Creation Date: ";
+    let start = contents.rfind(MARKER)? + MARKER.len();
+    let rest = &contents[start..];
+    let len = rest
+        .find(|c: char| !(c.is_ascii_digit() || c == '-'))
+        .unwrap_or(rest.len());
+    if len == 0 {
+        None
+    } else {
+        Some(&rest[..len])
     }
 }
 
 trait Object<'a>: Sized {
-    fn from_obj(obj: &'a JsonValue, opts: &'_ Options) -> Option<Self>;
+    type Source: 'a;
+
+    fn from_obj(obj: &'a Self::Source, opts: &'_ Options) -> Option<Self>;
 
-    fn collect(array: &'a JsonValue, opts: &'_ Options) -> Vec<Self> {
-        assert!(array.is_null() || array.is_array());
+    fn collect(array: &'a [Self::Source], opts: &'_ Options) -> Vec<Self> {
         array
-            .members()
+            .iter()
             .filter_map(|obj| Self::from_obj(obj, opts))
             .collect()
     }
 
-    fn collect_or_none(array: &'a JsonValue, opts: &'_ Options) -> Option<Vec<Self>> {
+    fn collect_or_none(array: &'a [Self::Source], opts: &'_ Options) -> Option<Vec<Self>> {
         let vec = Self::collect(array, opts);
         if vec.is_empty() {
             None
@@ -135,153 +533,168 @@ trait Object<'a>: Sized {
 }
 
 impl<'a> Object<'a> for RawEntry<'a> {
-    fn from_obj(obj: &'a JsonValue, opts: &'_ Options) -> Option<Self> {
-        Some(Self {
-            ent_seq: obj["n"].as_u32().unwrap(),
-            k_ele: RawKanjiElement::collect(&obj["K"], opts),
-            r_ele: RawReadingElement::collect_or_none(&obj["R"], opts)?,
-            sense: RawSense::collect_or_none(&obj["S"], opts)?,
-        })
+    type Source = EntryObj<'a>;
+
+    fn from_obj(obj: &'a EntryObj<'a>, opts: &'_ Options) -> Option<Self> {
+        let jlpt_level = opts.jlpt.as_ref().and_then(|list| list.level_of(obj));
+        if !opts
+            .jlpt
+            .as_ref()
+            .is_none_or(|list| list.accepts(jlpt_level))
+        {
+            return None;
+        }
+        let sense = RawSense::collect_numbered(&obj.senses, opts)?;
+        if let Some(parts_of_speech) = &opts.parts_of_speech {
+            let mut pos = sense.iter().flat_map(|s| s.pos.iter());
+            if !pos.any(|p| parts_of_speech.contains(p)) {
+                return None;
+            }
+        }
+        let entry = Self {
+            ent_seq: obj.n,
+            jlpt_level,
+            k_ele: RawKanjiElement::collect(&obj.kanji_elements, opts),
+            r_ele: RawReadingElement::collect_or_none(&obj.reading_elements, opts)?,
+            sense,
+        };
+        if let Some(filter) = &opts.filter {
+            if !filter.accepts_entry(&entry, &obj.senses) {
+                return None;
+            }
+        }
+        Some(entry)
     }
 }
 
 impl<'a> Object<'a> for RawKanjiElement<'a> {
-    fn from_obj(obj: &'a JsonValue, opts: &'_ Options) -> Option<Self> {
-        if !opts.with_uncommon && obj["p"].is_empty() {
+    type Source = KanjiElementObj<'a>;
+
+    fn from_obj(obj: &'a KanjiElementObj<'a>, opts: &'_ Options) -> Option<Self> {
+        if !opts.with_uncommon && obj.p.is_empty() {
             return None;
         }
         Some(Self {
-            keb: obj["t"].as_str().unwrap(),
-            ke_inf: Object::collect(&obj["i"], opts),
-            ke_pri: parse_prio(Object::collect(&obj["p"], opts)),
+            keb: &obj.t,
+            ke_inf: Object::collect(&obj.i, opts),
+            ke_pri: parse_prio(Object::collect(&obj.p, opts), opts),
         })
     }
 }
 
 impl<'a> Object<'a> for RawReadingElement<'a> {
-    fn from_obj(obj: &'a JsonValue, opts: &'_ Options) -> Option<Self> {
-        if !opts.with_uncommon && obj["p"].is_empty() {
+    type Source = ReadingElementObj<'a>;
+
+    fn from_obj(obj: &'a ReadingElementObj<'a>, opts: &'_ Options) -> Option<Self> {
+        if !opts.with_uncommon && obj.p.is_empty() {
             return None;
         }
         Some(Self {
-            reb: obj["t"].as_str().unwrap(),
-            re_nokanji: obj["n"].as_bool().unwrap_or(false),
-            re_restr: Object::collect(&obj["r"], opts),
-            re_inf: Object::collect(&obj["i"], opts),
-            re_pri: parse_prio(Object::collect(&obj["p"], opts)),
+            reb: &obj.t,
+            re_nokanji: obj.n,
+            re_restr: Object::collect(&obj.r, opts),
+            re_inf: Object::collect(&obj.i, opts),
+            re_pri: parse_prio(Object::collect(&obj.p, opts), opts),
         })
     }
 }
 
-fn parse_prio(markers: Vec<&str>) -> Priority {
-    use PriorityInCorpus::*;
-    let mut result = Priority {
-        news: Absent,
-        ichimango: Absent,
-        loanwords: Absent,
-        additional: Absent,
-        frequency_bucket: 0,
-    };
-    for marker in markers {
-        match marker {
-            "news1" => result.news = merge_cprio(result.news, Primary),
-            "news2" => result.news = merge_cprio(result.news, Secondary),
-            "ichi1" => result.ichimango = merge_cprio(result.ichimango, Primary),
-            "ichi2" => result.ichimango = merge_cprio(result.ichimango, Secondary),
-            "gai1" => result.loanwords = merge_cprio(result.loanwords, Primary),
-            "gai2" => result.loanwords = merge_cprio(result.loanwords, Secondary),
-            "spec1" => result.additional = merge_cprio(result.additional, Primary),
-            "spec2" => result.additional = merge_cprio(result.additional, Secondary),
-            _ => match parse_freq_bucket(marker) {
-                Some(bucket) => {
-                    if result.frequency_bucket == 0 || result.frequency_bucket > bucket {
-                        result.frequency_bucket = bucket;
-                    }
-                }
-                None => {
-                    panic!("unknown priority marker: {}", marker);
-                }
-            },
-        };
-    }
-    result
-}
-
-fn merge_cprio(old: PriorityInCorpus, new: PriorityInCorpus) -> PriorityInCorpus {
-    use PriorityInCorpus::*;
-    match (old, new) {
-        (Absent, _) => new,
-        (_, Primary) => Primary,
-        (Primary, _) => Primary,
-        (Secondary, _) => Secondary,
+fn parse_prio(mut markers: Vec<&str>, opts: &Options) -> Priority {
+    loop {
+        match Priority::from_markers(markers.iter().copied()) {
+            Ok(prio) => return prio,
+            Err(err) if opts.is_tolerant => {
+                warn_once(format!("skipping {}", err));
+                markers.retain(|&m| m != err.marker());
+            }
+            Err(err) => panic!("{}", err),
+        }
     }
 }
 
-///Parses a frequency bucket marker for the news corpus, e.g. "nf18" => Some(18).
-fn parse_freq_bucket(marker: &str) -> Option<u16> {
-    //NOTE: This would be easier with a regex library, but I'm definitely not pulling in an entire
-    //regex crate for just this one thing.
-
-    let mut c = marker.chars();
-    if c.next()? != 'n' {
-        return None;
-    }
-    if c.next()? != 'f' {
-        return None;
-    }
-    let tens = c.next()?.to_digit(10)? as u16;
-    let ones = c.next()?.to_digit(10)? as u16;
-    if c.next().is_some() {
-        return None;
-    }
-    let result = 10 * tens + ones;
-
-    //only nf01..nf48 are allowed
-    if result == 0 || result > 48 {
-        None
-    } else {
-        Some(result)
+impl<'a> RawSense<'a> {
+    ///Like `Object::collect_or_none()`, but also fills in the `number` field of each sense.
+    fn collect_numbered(array: &'a [SenseObj<'a>], opts: &'_ Options) -> Option<Vec<Self>> {
+        let vec: Vec<_> = array
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, obj)| {
+                let sense = Self::from_obj(obj, opts)?;
+                Some(Self {
+                    number: idx as u32 + 1,
+                    ..sense
+                })
+            })
+            .collect();
+        if vec.is_empty() {
+            None
+        } else {
+            Some(vec)
+        }
     }
 }
 
 impl<'a> Object<'a> for RawSense<'a> {
-    fn from_obj(obj: &'a JsonValue, opts: &'_ Options) -> Option<Self> {
-        let misc = Object::collect(&obj["m"], opts);
+    type Source = SenseObj<'a>;
+
+    fn from_obj(obj: &'a SenseObj<'a>, opts: &'_ Options) -> Option<Self> {
+        let misc = Object::collect(&obj.m, opts);
         if !opts.with_archaic && misc.contains(&SenseInfo::Archaism) {
             return None;
         }
+        if !opts.with_names && misc.iter().any(SenseInfo::is_name_type) {
+            return None;
+        }
+        if !opts.with_sensitive && misc.iter().any(SenseInfo::is_sensitive_content) {
+            return None;
+        }
+        if let Some(filter) = &opts.filter {
+            if !filter.accepts_sense(obj) {
+                return None;
+            }
+        }
+        let field = Object::collect(&obj.f, opts);
+        if let Some(topics) = &opts.topics {
+            if !field.iter().any(|t| topics.contains(t)) {
+                return None;
+            }
+        }
 
         Some(Self {
-            stagk: Object::collect(&obj["stagk"], opts),
-            stagr: Object::collect(&obj["stagr"], opts),
-            pos: Object::collect(&obj["p"], opts),
-            xref: Object::collect(&obj["xref"], opts),
-            ant: Object::collect(&obj["ant"], opts),
-            field: Object::collect(&obj["f"], opts),
+            number: 0, //filled in by collect_numbered()
+            stagk: Object::collect(&obj.stagk, opts),
+            stagr: Object::collect(&obj.stagr, opts),
+            pos: Object::collect(&obj.p, opts),
+            xref: Object::collect(&obj.xref, opts),
+            ant: Object::collect(&obj.ant, opts),
+            field,
             misc,
-            s_inf: Object::collect(&obj["i"], opts),
-            lsource: Object::collect(&obj["L"], opts),
-            dial: Object::collect(&obj["dial"], opts),
-            gloss: Object::collect_or_none(&obj["G"], opts)?,
+            s_inf: Object::collect(&obj.i, opts),
+            lsource: Object::collect(&obj.lsources, opts),
+            dial: Object::collect(&obj.dial, opts),
+            gloss: Object::collect_or_none(&obj.glosses, opts)?,
+            example: Object::collect(&obj.ex, opts),
         })
     }
 }
 
 impl<'a> Object<'a> for RawLSource<'a> {
-    fn from_obj(obj: &'a JsonValue, _opts: &'_ Options) -> Option<Self> {
-        let is_partial = match obj["type"].as_str().unwrap_or("full") {
+    type Source = LSourceObj<'a>;
+
+    fn from_obj(obj: &'a LSourceObj<'a>, opts: &'_ Options) -> Option<Self> {
+        let is_partial = match obj.ls_type.as_deref().unwrap_or("full") {
             "full" => false,
             "part" => true,
             val => panic!("unknown ls_type: {}", val),
         };
-        let is_wasei = match obj["wasei"].as_str().unwrap_or("n") {
+        let is_wasei = match obj.wasei.as_deref().unwrap_or("n") {
             "n" => false,
             "y" => true,
             val => panic!("unknown ls_wasei: {}", val),
         };
         Some(Self {
-            text: obj["t"].as_str().unwrap(),
-            lang: obj["l"].as_str().unwrap_or("eng"),
+            text: &obj.t,
+            lang: optional_enum(obj.l.as_deref(), "eng", "SourceLanguage", opts)?,
             is_partial,
             is_wasei,
         })
@@ -289,77 +702,196 @@ impl<'a> Object<'a> for RawLSource<'a> {
 }
 
 impl<'a> Object<'a> for RawGloss<'a> {
-    fn from_obj(obj: &'a JsonValue, opts: &'_ Options) -> Option<Self> {
+    type Source = GlossObj<'a>;
+
+    fn from_obj(obj: &'a GlossObj<'a>, opts: &'_ Options) -> Option<Self> {
+        //in tolerant mode, an unknown gender or type is treated as if it was not given
+        let g_gend = optional_enum(obj.g_gend.as_deref(), "", "GlossGender", opts);
+        let g_type = optional_enum(obj.g_type.as_deref(), "", "GlossType", opts);
         Some(Self {
-            text: obj["t"].as_str().unwrap(),
-            lang: GlossLanguage::from_obj(&obj["l"], opts)?,
-            g_type: optional_enum(&obj["g_type"], "", "GlossType"),
+            text: &obj.t,
+            lang: gloss_language(obj.l.as_deref(), opts)?,
+            g_gend: g_gend.unwrap_or(GlossGender::Unspecified),
+            g_type: g_type.unwrap_or(GlossType::RegularTranslation),
         })
     }
 }
 
-impl<'a> Object<'a> for &'a str {
-    fn from_obj(obj: &'a JsonValue, _opts: &'_ Options) -> Option<Self> {
-        Some(obj.as_str().unwrap())
+impl<'a> Object<'a> for RawExample<'a> {
+    type Source = ExampleObj<'a>;
+
+    fn from_obj(obj: &'a ExampleObj<'a>, opts: &'_ Options) -> Option<Self> {
+        //malformed examples are skipped entirely in tolerant mode, like unknown enum values
+        let invalid = |problem: String| -> Option<Self> {
+            if opts.is_tolerant {
+                warn_once(format!("skipping example {}", problem));
+                None
+            } else {
+                panic!("invalid example {}", problem)
+            }
+        };
+        match obj.src.exsrc_type.as_deref().unwrap_or("tat") {
+            "tat" => {}
+            val => return invalid(format!("with unknown exsrc_type: {}", val)),
+        };
+        let source_id = match obj.src.id.parse() {
+            Ok(id) => id,
+            Err(_) => return invalid(format!("with non-numeric source ID: {:?}", &*obj.src.id)),
+        };
+        let is_japanese = |s: &ExampleSentenceObj| s.l.as_deref() == Some("jpn");
+        let sentence = match obj.sentences.iter().find(|s| is_japanese(s)) {
+            Some(s) => &*s.t,
+            None => {
+                return invalid(format!(
+                    "without Japanese sentence (source ID {})",
+                    source_id
+                ))
+            }
+        };
+        Some(Self {
+            source_id,
+            text: &obj.t,
+            sentence,
+            translations: obj
+                .sentences
+                .iter()
+                .filter(|s| !is_japanese(s))
+                .filter_map(|s| Object::from_obj(s, opts))
+                .collect(),
+        })
     }
 }
 
-impl<'a> Object<'a> for Dialect {
-    fn from_obj(obj: &'a JsonValue, _opts: &'_ Options) -> Option<Self> {
-        Some(required_enum(obj, "Dialect"))
+impl<'a> Object<'a> for RawExampleTranslation<'a> {
+    type Source = ExampleSentenceObj<'a>;
+
+    fn from_obj(obj: &'a ExampleSentenceObj<'a>, opts: &'_ Options) -> Option<Self> {
+        Some(Self {
+            text: &obj.t,
+            lang: gloss_language(obj.l.as_deref(), opts)?,
+        })
     }
 }
 
-impl<'a> Object<'a> for GlossLanguage {
-    fn from_obj(obj: &'a JsonValue, _opts: &'_ Options) -> Option<Self> {
-        let lang: AllGlossLanguage = optional_enum(obj, "eng", "AllGlossLanguage");
-        lang.try_into().ok()
+impl<'a> Object<'a> for &'a str {
+    type Source = Text<'a>;
+
+    fn from_obj(obj: &'a Text<'a>, _opts: &'_ Options) -> Option<Self> {
+        Some(obj)
+    }
+}
+
+impl<'a> Object<'a> for Dialect {
+    type Source = Text<'a>;
+
+    fn from_obj(obj: &'a Text<'a>, _opts: &'_ Options) -> Option<Self> {
+        Some(entity_enum(obj))
     }
 }
 
 impl<'a> Object<'a> for KanjiInfo {
-    fn from_obj(obj: &'a JsonValue, _opts: &'_ Options) -> Option<Self> {
-        Some(required_enum(obj, "KanjiInfo"))
+    type Source = Text<'a>;
+
+    fn from_obj(obj: &'a Text<'a>, _opts: &'_ Options) -> Option<Self> {
+        Some(entity_enum(obj))
     }
 }
 
 impl<'a> Object<'a> for PartOfSpeech {
-    fn from_obj(obj: &'a JsonValue, _opts: &'_ Options) -> Option<Self> {
-        let lang: AllPartOfSpeech = optional_enum(obj, "eng", "AllPartOfSpeech");
-        lang.try_into().ok()
+    type Source = Text<'a>;
+
+    fn from_obj(obj: &'a Text<'a>, _opts: &'_ Options) -> Option<Self> {
+        let pos: AllPartOfSpeech = entity_enum(obj);
+        pos.try_into().ok()
     }
 }
 
 impl<'a> Object<'a> for ReadingInfo {
-    fn from_obj(obj: &'a JsonValue, _opts: &'_ Options) -> Option<Self> {
-        Some(required_enum(obj, "ReadingInfo"))
+    type Source = Text<'a>;
+
+    fn from_obj(obj: &'a Text<'a>, _opts: &'_ Options) -> Option<Self> {
+        Some(entity_enum(obj))
     }
 }
 
 impl<'a> Object<'a> for SenseInfo {
-    fn from_obj(obj: &'a JsonValue, _opts: &'_ Options) -> Option<Self> {
-        Some(required_enum(obj, "SenseInfo"))
+    type Source = Text<'a>;
+
+    fn from_obj(obj: &'a Text<'a>, _opts: &'_ Options) -> Option<Self> {
+        Some(entity_enum(obj))
     }
 }
 
 impl<'a> Object<'a> for SenseTopic {
-    fn from_obj(obj: &'a JsonValue, _opts: &'_ Options) -> Option<Self> {
-        Some(required_enum(obj, "SenseTopic"))
+    type Source = Text<'a>;
+
+    fn from_obj(obj: &'a Text<'a>, _opts: &'_ Options) -> Option<Self> {
+        Some(entity_enum(obj))
     }
 }
 
-fn optional_enum<E: Enum>(obj: &JsonValue, default: &'static str, enum_name: &'static str) -> E {
-    let code = obj.as_str().unwrap_or(default);
+///Parses the language of a gloss. Returns None if that language is disabled by feature flags.
+fn gloss_language(code: Option<&str>, opts: &Options) -> Option<GlossLanguage> {
+    let lang: AllGlossLanguage = optional_enum(code, "eng", "AllGlossLanguage", opts)?;
+    lang.try_into().ok()
+}
+
+///Parses a value of one of the enums that do not have an `Unknown` variant. Unknown codes cause a
+///panic, or are skipped with a warning if `opts.is_tolerant` is set.
+fn optional_enum<E: Enum>(
+    code: Option<&str>,
+    default: &'static str,
+    enum_name: &'static str,
+    opts: &Options,
+) -> Option<E> {
+    let code = code.unwrap_or(default);
     match E::from_code(code) {
-        Some(val) => val,
+        Some(val) => Some(val),
+        None if opts.is_tolerant => {
+            warn_once(format!(
+                "skipping unknown {} representation: {}",
+                enum_name, code
+            ));
+            None
+        }
         None => panic!("unknown {} representation: {}", enum_name, code),
     }
 }
 
-fn required_enum<E: Enum>(obj: &JsonValue, enum_name: &'static str) -> E {
-    let code = obj.as_str().unwrap();
-    match E::from_code(code) {
-        Some(val) => val,
-        None => panic!("unknown {} representation: {}", enum_name, code),
+///Reports a problem with the data file as a build warning. Each distinct message is only reported
+///once, since a new code in the JMdict usually appears in many entries at once.
+fn warn_once(message: String) {
+    use std::collections::BTreeSet;
+    use std::sync::Mutex;
+    static REPORTED: Mutex<BTreeSet<String>> = Mutex::new(BTreeSet::new());
+    let is_new = REPORTED.lock().unwrap().insert(message.clone());
+    if is_new {
+        println!("cargo:warning={}", message);
     }
 }
+
+///Parses a value of one of the enums that correspond to JMdict entities. Unknown codes are mapped
+///into the `Unknown` variant instead of causing a panic.
+fn entity_enum<E: Enum + EnumPayload>(code: &str) -> E {
+    E::from_code(code).unwrap_or_else(|| E::from_unknown_code(intern(code)))
+}
+
+///Returns a `'static` copy of the given string. Each distinct string is only leaked once.
+fn intern(text: &str) -> &'static str {
+    use std::cell::RefCell;
+    use std::collections::HashSet;
+    thread_local! {
+        static INTERNED: RefCell<HashSet<&'static str>> = RefCell::new(HashSet::new());
+    }
+    INTERNED.with(|interned| {
+        let mut interned = interned.borrow_mut();
+        match interned.get(text) {
+            Some(s) => s,
+            None => {
+                let s: &'static str = Box::leak(text.to_owned().into_boxed_str());
+                interned.insert(s);
+                s
+            }
+        }
+    })
+}
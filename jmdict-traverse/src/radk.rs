@@ -0,0 +1,95 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! Assignment of kanji to their components. The JMdict does not contain this information, so it
+//! has to be supplied from an external RADKFILE.
+
+use std::io::Read;
+
+///The contents of a RADKFILE, as read from the file whose path is given in the
+///`RUST_JMDICT_RADKFILE` environment variable. The file must be in UTF-8 (the original RADKFILE
+///and RADKFILE2 from the EDRDG are in EUC-JP, and need to be converted first with e.g. `iconv -f
+///EUC-JP -t UTF-8`). It can be compressed like the JMdict file itself.
+///
+///Each radical starts with a line of the form `$ <radical> <stroke count>`, which may be followed
+///by further fields (e.g. the name of an image file) that are ignored. The following lines
+///contain all kanji that contain this radical. Lines starting with `#` are ignored. For example:
+///
+///```text
+///$ 一 1
+///七丁万三上下不世丙両
+///$ 日 4
+///早明旦星
+///```
+pub struct RadkFile {
+    ///All radicals in the order in which they appear in the file.
+    pub radicals: Vec<RadkRadical>,
+}
+
+///A single radical in a [RadkFile].
+pub struct RadkRadical {
+    ///The radical itself.
+    pub text: char,
+    ///The number of strokes in this radical.
+    pub stroke_count: u8,
+    ///All kanji that contain this radical, in ascending order and without duplicates.
+    pub kanji: Vec<char>,
+}
+
+impl RadkFile {
+    ///Reads the file referenced by `RUST_JMDICT_RADKFILE`, or returns None if that variable is not
+    ///set. Invalid files cause a panic.
+    pub fn from_env() -> Option<Self> {
+        let path = std::path::PathBuf::from(std::env::var_os("RUST_JMDICT_RADKFILE")?);
+        let mut contents = String::new();
+        crate::open_data_file(&path)
+            .read_to_string(&mut contents)
+            .unwrap_or_else(|err| panic!("cannot read {}: {}", path.display(), err));
+        Some(Self::parse(&contents).unwrap_or_else(|msg| panic!("{}: {}", path.display(), msg)))
+    }
+
+    ///Parses the contents of a RADKFILE. See type-level docs for the expected format.
+    pub fn parse(contents: &str) -> Result<Self, String> {
+        let mut radicals: Vec<RadkRadical> = Vec::new();
+        for line in contents.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(fields) = line.strip_prefix('$') {
+                let mut fields = fields.split_whitespace();
+                let text = fields.next().and_then(|t| {
+                    let mut chars = t.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => Some(c),
+                        _ => None,
+                    }
+                });
+                let stroke_count = fields.next().and_then(|s| s.parse().ok());
+                match (text, stroke_count) {
+                    (Some(text), Some(stroke_count)) => radicals.push(RadkRadical {
+                        text,
+                        stroke_count,
+                        kanji: Vec::new(),
+                    }),
+                    _ => return Err(format!("invalid radical line: {:?}", line)),
+                }
+                continue;
+            }
+            match radicals.last_mut() {
+                Some(radical) => radical
+                    .kanji
+                    .extend(line.chars().filter(|c| !c.is_whitespace())),
+                None => return Err(format!("kanji line before first radical line: {:?}", line)),
+            }
+        }
+
+        for radical in &mut radicals {
+            radical.kanji.sort_unstable();
+            radical.kanji.dedup();
+        }
+        Ok(Self { radicals })
+    }
+}
@@ -0,0 +1,390 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! Conversion of the official JMdict XML file into the entrypack format.
+//!
+//! The entrypack contains one JSON object per line, one for each `<entry>` in the XML. Element
+//! names are abbreviated, and enum values are given as the bare entity names (e.g. `&n;` becomes
+//! `"n"`), so that we do not have to deal with the DTD when parsing.
+
+use crate::format::*;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Reader;
+use std::io::{BufRead, Error, ErrorKind};
+
+///Reports malformed input. All parse errors are reported this way, so that callers can tell them
+///apart from I/O errors.
+fn invalid_data(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
+
+fn from_utf8(bytes: &[u8]) -> std::io::Result<&str> {
+    std::str::from_utf8(bytes).map_err(|err| invalid_data(err.to_string()))
+}
+
+///A minimal DOM node for the contents of an `<entry>` element.
+struct Element {
+    name: String,
+    attrs: Vec<(String, String)>,
+    text: String,
+    children: Vec<Element>,
+}
+
+impl Element {
+    fn from_start(start: &BytesStart) -> std::io::Result<Self> {
+        let attrs = start
+            .attributes()
+            .map(|attr| {
+                let attr = attr.map_err(|err| invalid_data(err.to_string()))?;
+                let key = from_utf8(attr.key.as_ref())?.to_owned();
+                let value = attr
+                    .unescape_value()
+                    .map_err(|err| invalid_data(err.to_string()))?;
+                Ok((key, value.into_owned()))
+            })
+            .collect::<std::io::Result<_>>()?;
+        Ok(Self {
+            name: from_utf8(start.name().as_ref())?.to_owned(),
+            attrs,
+            text: String::new(),
+            children: Vec::new(),
+        })
+    }
+
+    fn attr(&self, key: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Element> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    fn child<'a>(&'a self, name: &'a str) -> std::io::Result<&'a Element> {
+        self.children(name)
+            .next()
+            .ok_or_else(|| invalid_data(format!("missing <{}> in <{}>", name, self.name)))
+    }
+
+    fn child_text<'a>(&'a self, name: &'a str) -> std::io::Result<&'a str> {
+        self.child(name).map(|c| c.text.as_str())
+    }
+
+    ///Returns the texts of all children with the given name.
+    fn texts<'a>(&self, name: &'a str) -> Vec<Text<'a>> {
+        self.children(name).map(|c| c.text.clone().into()).collect()
+    }
+
+    ///Returns the value of the given attribute, if it is present.
+    fn attr_text<'a>(&self, key: &str) -> Option<Text<'a>> {
+        self.attr(key).map(|value| value.to_owned().into())
+    }
+}
+
+///An iterator that reads a JMdict XML file and yields the corresponding lines of an entrypack
+///file (without trailing newlines). Only one entry is held in memory at any time.
+///
+///Malformed input (e.g. invalid XML, or an entry without `<ent_seq>`) is reported as an error of
+///kind [ErrorKind::InvalidData]. Errors from the underlying reader are passed on as they are.
+///Iteration ends after the first error.
+pub struct XmlEntries<R> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+    ///The stack of elements that are currently open below `<JMdict>`.
+    stack: Vec<Element>,
+    ///The metadata found outside of the entries so far. The fields describing the entries
+    ///themselves (`entry_count` and `sha256sum`) are left empty.
+    pub(crate) header: HeaderObj,
+    is_done: bool,
+}
+
+impl<R: BufRead> XmlEntries<R> {
+    ///Creates an iterator over the entries of the JMdict XML file that is read from `reader`.
+    ///Decompression has to be done by the caller, e.g. with [open_data_file()](crate::open_data_file).
+    pub fn new(reader: R) -> Self {
+        //NOTE: Text is not trimmed since some glosses have significant whitespace. Whitespace
+        //between elements ends up in the text of container elements, where it is ignored.
+        Self {
+            reader: Reader::from_reader(reader),
+            buf: Vec::new(),
+            stack: Vec::new(),
+            header: HeaderObj::default(),
+            is_done: false,
+        }
+    }
+
+    ///Reads until the end of the next entry, and returns its entrypack line. Returns None at the
+    ///end of the file.
+    fn read_entry(&mut self) -> std::io::Result<Option<String>> {
+        loop {
+            self.buf.clear();
+            let event = match self.reader.read_event_into(&mut self.buf) {
+                Ok(event) => event,
+                Err(quick_xml::Error::Io(err)) => {
+                    return Err(Error::new(err.kind(), err.to_string()))
+                }
+                Err(err) => {
+                    return Err(invalid_data(format!(
+                        "XML error at position {}: {}",
+                        self.reader.error_position(),
+                        err
+                    )))
+                }
+            };
+            match event {
+                Event::Start(start) => {
+                    if start.name().as_ref() != b"JMdict" {
+                        self.stack.push(Element::from_start(&start)?);
+                    }
+                }
+                Event::Empty(start) => {
+                    let element = Element::from_start(&start)?;
+                    match self.stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None => {
+                            return Err(invalid_data(format!(
+                                "unexpected empty element <{}/>",
+                                element.name
+                            )))
+                        }
+                    }
+                }
+                Event::Text(text) => {
+                    if let Some(element) = self.stack.last_mut() {
+                        element.text.push_str(&unescape(from_utf8(&text)?)?);
+                    }
+                }
+                Event::CData(text) => {
+                    if let Some(element) = self.stack.last_mut() {
+                        element.text.push_str(from_utf8(&text)?);
+                    }
+                }
+                Event::End(end) => {
+                    if end.name().as_ref() == b"JMdict" {
+                        continue;
+                    }
+                    //quick-xml checks that end tags match their start tags
+                    let element = self.stack.pop().unwrap();
+                    match self.stack.last_mut() {
+                        Some(parent) => parent.children.push(element),
+                        None if element.name == "entry" => {
+                            let obj = entry_to_obj(&element)?;
+                            return Ok(Some(serde_json::to_string(&obj).unwrap()));
+                        }
+                        None => {
+                            return Err(invalid_data(format!(
+                                "unexpected top-level element <{}>",
+                                element.name
+                            )))
+                        }
+                    }
+                }
+                Event::Eof => return Ok(None),
+                //the DTD is not needed for parsing since we keep entity references unexpanded, but
+                //it provides some metadata for the entrypack header
+                Event::DocType(text) => {
+                    let dtd = from_utf8(&text)?;
+                    self.header.entities.extend(parse_entity_definitions(dtd));
+                    self.header.revision = find_revision(dtd).map(str::to_owned);
+                }
+                //the creation date appears in a comment like `<!-- JMdict created: 2021-07-19 -->`
+                Event::Comment(text) => {
+                    let comment = from_utf8(&text)?.trim();
+                    if let Some(date) = comment.strip_prefix("JMdict created:") {
+                        self.header.creation_date = Some(date.trim().to_owned());
+                    }
+                }
+                Event::Decl(_) | Event::PI(_) => {}
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for XmlEntries<R> {
+    type Item = std::io::Result<String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done {
+            return None;
+        }
+        let result = self.read_entry().transpose();
+        if !matches!(result, Some(Ok(_))) {
+            self.is_done = true;
+        }
+        result
+    }
+}
+
+///Yields the name and value of each `<!ENTITY name "value">` in the given DTD.
+fn parse_entity_definitions(dtd: &str) -> impl Iterator<Item = (String, String)> + '_ {
+    dtd.split("<!ENTITY").skip(1).filter_map(|definition| {
+        let definition = definition.trim_start();
+        let name_len = definition.find(char::is_whitespace)?;
+        let value = definition[name_len..].trim_start().strip_prefix('"')?;
+        let value_len = value.find('"')?;
+        Some((
+            definition[..name_len].to_owned(),
+            value[..value_len].to_owned(),
+        ))
+    })
+}
+
+///Finds the revision of the DTD in its changelog comments, which contain lines like `Rev 1.09`.
+///Returns the most recent revision mentioned there.
+fn find_revision(dtd: &str) -> Option<&str> {
+    dtd.match_indices("Rev ")
+        .map(|(idx, marker)| {
+            let rest = &dtd[idx + marker.len()..];
+            let len = rest
+                .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+                .unwrap_or(rest.len());
+            rest[..len].trim_end_matches('.')
+        })
+        .filter(|revision| !revision.is_empty())
+        .max_by_key(|revision| {
+            let parts: Vec<u32> = revision.split('.').filter_map(|p| p.parse().ok()).collect();
+            parts
+        })
+}
+
+///Replaces character references and predefined entities with the characters they represent.
+///All other entity references (e.g. `&n;`) are replaced by the entity name (e.g. `n`).
+fn unescape(raw: &str) -> std::io::Result<String> {
+    let mut result = String::with_capacity(raw.len());
+    let mut rest = raw;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        let len = rest[start..]
+            .find(';')
+            .ok_or_else(|| invalid_data(format!("unterminated entity reference in {:?}", raw)))?;
+        let name = &rest[start + 1..start + len];
+        match name {
+            "amp" => result.push('&'),
+            "lt" => result.push('<'),
+            "gt" => result.push('>'),
+            "quot" => result.push('"'),
+            "apos" => result.push('\''),
+            _ => match name.strip_prefix('#') {
+                Some(number) => {
+                    let codepoint = match number.strip_prefix('x') {
+                        Some(hex) => u32::from_str_radix(hex, 16),
+                        None => number.parse(),
+                    };
+                    match codepoint.ok().and_then(std::char::from_u32) {
+                        Some(c) => result.push(c),
+                        None => {
+                            return Err(invalid_data(format!(
+                                "invalid character reference: &{};",
+                                name
+                            )))
+                        }
+                    }
+                }
+                None => result.push_str(name),
+            },
+        }
+        rest = &rest[start + len + 1..];
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+fn entry_to_obj(entry: &Element) -> std::io::Result<EntryObj<'static>> {
+    let ent_seq = entry.child_text("ent_seq")?;
+    Ok(EntryObj {
+        n: ent_seq
+            .parse()
+            .map_err(|_| invalid_data(format!("invalid <ent_seq>: {:?}", ent_seq)))?,
+        kanji_elements: entry
+            .children("k_ele")
+            .map(kanji_element_to_obj)
+            .collect::<std::io::Result<_>>()?,
+        reading_elements: entry
+            .children("r_ele")
+            .map(reading_element_to_obj)
+            .collect::<std::io::Result<_>>()?,
+        senses: entry
+            .children("sense")
+            .map(sense_to_obj)
+            .collect::<std::io::Result<_>>()?,
+    })
+}
+
+fn kanji_element_to_obj(k_ele: &Element) -> std::io::Result<KanjiElementObj<'static>> {
+    Ok(KanjiElementObj {
+        t: k_ele.child_text("keb")?.to_owned().into(),
+        i: k_ele.texts("ke_inf"),
+        p: k_ele.texts("ke_pri"),
+    })
+}
+
+fn reading_element_to_obj(r_ele: &Element) -> std::io::Result<ReadingElementObj<'static>> {
+    Ok(ReadingElementObj {
+        t: r_ele.child_text("reb")?.to_owned().into(),
+        n: r_ele.children("re_nokanji").next().is_some(),
+        r: r_ele.texts("re_restr"),
+        i: r_ele.texts("re_inf"),
+        p: r_ele.texts("re_pri"),
+    })
+}
+
+fn sense_to_obj(sense: &Element) -> std::io::Result<SenseObj<'static>> {
+    Ok(SenseObj {
+        stagk: sense.texts("stagk"),
+        stagr: sense.texts("stagr"),
+        p: sense.texts("pos"),
+        xref: sense.texts("xref"),
+        ant: sense.texts("ant"),
+        f: sense.texts("field"),
+        m: sense.texts("misc"),
+        i: sense.texts("s_inf"),
+        lsources: sense.children("lsource").map(lsource_to_obj).collect(),
+        dial: sense.texts("dial"),
+        glosses: sense.children("gloss").map(gloss_to_obj).collect(),
+        ex: sense
+            .children("example")
+            .map(example_to_obj)
+            .collect::<std::io::Result<_>>()?,
+    })
+}
+
+fn lsource_to_obj(lsource: &Element) -> LSourceObj<'static> {
+    LSourceObj {
+        t: lsource.text.clone().into(),
+        l: lsource.attr_text("xml:lang"),
+        ls_type: lsource.attr_text("ls_type"),
+        wasei: lsource.attr_text("ls_wasei"),
+    }
+}
+
+fn gloss_to_obj(gloss: &Element) -> GlossObj<'static> {
+    GlossObj {
+        t: gloss.text.clone().into(),
+        l: gloss.attr_text("xml:lang"),
+        g_gend: gloss.attr_text("g_gend"),
+        g_type: gloss.attr_text("g_type"),
+    }
+}
+
+fn example_to_obj(example: &Element) -> std::io::Result<ExampleObj<'static>> {
+    let source = example.child("ex_srce")?;
+    Ok(ExampleObj {
+        src: ExampleSourceObj {
+            exsrc_type: source.attr_text("exsrc_type"),
+            id: source.text.clone().into(),
+        },
+        t: example.child_text("ex_text")?.to_owned().into(),
+        sentences: example
+            .children("ex_sent")
+            .map(|s| ExampleSentenceObj {
+                t: s.text.clone().into(),
+                l: s.attr_text("xml:lang"),
+            })
+            .collect(),
+    })
+}
@@ -0,0 +1,239 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains the [Dictionary] trait, which allows code to be generic over where its
+//! entries come from.
+
+use crate::*;
+
+///A collection of [entries](Entry) along with lookups into it.
+///
+///This is implemented by [EmbeddedDictionary] (the database compiled into this binary, as seen
+///through [entries()] and the other free functions of this crate), as well as by slices and vectors
+///of entries. Libraries that are generic over this trait can therefore be tested against a small,
+///hand-picked set of entries:
+///
+///```
+///use jmdict::{Dictionary, EmbeddedDictionary, SourceLanguage};
+///
+///fn count_german_loanwords(dict: &impl Dictionary) -> usize {
+///    dict.entries_with_loanword_source(SourceLanguage::German).count()
+///}
+///
+///let fixture: Vec<_> = jmdict::entries().take(10).collect();
+///assert!(count_german_loanwords(&fixture) <= count_german_loanwords(&EmbeddedDictionary));
+///```
+///
+///Only [entries()](Dictionary::entries) must be implemented. The lookups have default
+///implementations that scan through all entries, which implementors can override with faster
+///versions. Lookups yield entries in the same order as [entries()](Dictionary::entries).
+pub trait Dictionary {
+    ///Returns an iterator over all entries in this dictionary.
+    fn entries(&self) -> Box<dyn Iterator<Item = Entry> + '_>;
+
+    ///Returns an iterator over all entries that have a [kanji element](KanjiElement) or
+    ///[reading element](ReadingElement) with exactly the given text.
+    fn entries_with_headword<'a>(&'a self, text: &'a str) -> Box<dyn Iterator<Item = Entry> + 'a> {
+        Box::new(self.entries().filter(move |entry| {
+            entry.kanji_elements().any(|k| k.text == text)
+                || entry.reading_elements().any(|r| r.text == text)
+        }))
+    }
+
+    ///Returns an iterator over all entries that use the given kanji in at least one of their
+    ///[kanji elements](KanjiElement). See [entries_containing_kanji()].
+    fn entries_containing_kanji(&self, kanji: char) -> Box<dyn Iterator<Item = Entry> + '_> {
+        Box::new(
+            self.entries()
+                .filter(move |entry| entry.kanji_elements().any(|k| k.text.contains(kanji))),
+        )
+    }
+
+    ///Returns an iterator over all entries that have a [LoanwordSource] with the given language in
+    ///at least one of their senses. See [entries_with_loanword_source()].
+    fn entries_with_loanword_source(
+        &self,
+        language: SourceLanguage,
+    ) -> Box<dyn Iterator<Item = Entry> + '_> {
+        Box::new(self.entries().filter(move |entry| {
+            entry
+                .senses()
+                .flat_map(|s| s.loanword_sources())
+                .any(|ls| ls.language == language)
+        }))
+    }
+
+    ///Returns an iterator over all entries that have at least one sense marked as an
+    ///[expression](PartOfSpeech::Expression). See [expressions()].
+    fn expressions(&self) -> Box<dyn Iterator<Item = Entry> + '_> {
+        Box::new(self.entries().filter(|entry| {
+            entry
+                .senses()
+                .flat_map(|s| s.parts_of_speech())
+                .any(|pos| pos == PartOfSpeech::Expression)
+        }))
+    }
+
+    ///Returns an iterator over all entries that have at least one sense marked as a
+    ///[proverb](SenseInfo::Proverb). See [proverbs()].
+    fn proverbs(&self) -> Box<dyn Iterator<Item = Entry> + '_> {
+        Box::new(self.entries().filter(|entry| {
+            let mut infos = entry.senses().flat_map(|s| s.infos());
+            infos.any(|info| info == SenseInfo::Proverb)
+        }))
+    }
+
+    ///Returns an iterator over all entries that have at least one sense marked as a
+    ///[yojijukugo](SenseInfo::Yojijukugo). See [yojijukugo()].
+    fn yojijukugo(&self) -> Box<dyn Iterator<Item = Entry> + '_> {
+        Box::new(self.entries().filter(|entry| {
+            let mut infos = entry.senses().flat_map(|s| s.infos());
+            infos.any(|info| info == SenseInfo::Yojijukugo)
+        }))
+    }
+
+    ///Searches the texts of all kanji elements, reading elements and glosses in this dictionary
+    ///for the given query. See [search()].
+    fn search<'a>(&'a self, query: &'a str) -> Box<dyn Iterator<Item = SearchHit> + 'a> {
+        self.search_with_options(query, SearchOptions::default())
+    }
+
+    ///Like [search()](Dictionary::search), but allows to customize how the query is matched. See
+    ///[search_with_options()].
+    fn search_with_options<'a>(
+        &'a self,
+        query: &'a str,
+        opts: SearchOptions,
+    ) -> Box<dyn Iterator<Item = SearchHit> + 'a> {
+        Box::new(search::search_entries(self.entries(), query, opts))
+    }
+}
+
+///The database that was compiled into this binary. Its implementation of [Dictionary] uses the
+///same indexes as the free functions of this crate, e.g. [entries_containing_kanji()].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct EmbeddedDictionary;
+
+impl Dictionary for EmbeddedDictionary {
+    fn entries(&self) -> Box<dyn Iterator<Item = Entry> + '_> {
+        Box::new(entries())
+    }
+
+    fn entries_with_headword<'a>(&'a self, text: &'a str) -> Box<dyn Iterator<Item = Entry> + 'a> {
+        Box::new(IndexedEntries::from(payload::entries_with_headword(text)))
+    }
+
+    fn entries_containing_kanji(&self, kanji: char) -> Box<dyn Iterator<Item = Entry> + '_> {
+        Box::new(entries_containing_kanji(kanji))
+    }
+
+    fn entries_with_loanword_source(
+        &self,
+        language: SourceLanguage,
+    ) -> Box<dyn Iterator<Item = Entry> + '_> {
+        Box::new(entries_with_loanword_source(language))
+    }
+
+    fn expressions(&self) -> Box<dyn Iterator<Item = Entry> + '_> {
+        Box::new(expressions())
+    }
+
+    fn proverbs(&self) -> Box<dyn Iterator<Item = Entry> + '_> {
+        Box::new(proverbs())
+    }
+
+    fn yojijukugo(&self) -> Box<dyn Iterator<Item = Entry> + '_> {
+        Box::new(yojijukugo())
+    }
+}
+
+///Combines several [Dictionary] sources into one.
+///
+///The combined dictionary yields the entries of all sources ordered by
+///[sequence number](Entry::number), regardless of the order of entries within each source. When
+///multiple sources contain an entry with the same sequence number, it is only yielded once (from
+///the source that comes first in the list). Lookups are performed in each source (using its own
+///indexes, if any), and their results are merged in the same way. Searches go through the
+///combined entries, so hits are ordered by sequence number as well.
+///
+///```
+///use jmdict::{Dictionary, EmbeddedDictionary, MergedDictionary};
+///
+///let favorites: Vec<_> = jmdict::entries().take(3).collect();
+///let merged = MergedDictionary::new(&[&favorites, &EmbeddedDictionary]);
+///assert_eq!(merged.entries().count(), jmdict::entries().count());
+///```
+///
+///Since the entries of all sources need to be sorted, each call to
+///[entries()](Dictionary::entries) or a lookup collects the respective entries into a buffer first.
+pub struct MergedDictionary<'a> {
+    sources: Vec<&'a dyn Dictionary>,
+}
+
+impl<'a> MergedDictionary<'a> {
+    ///Combines the given sources. Earlier sources take precedence over later ones.
+    pub fn new(sources: &[&'a dyn Dictionary]) -> Self {
+        Self {
+            sources: sources.to_vec(),
+        }
+    }
+
+    fn merge<'b, I: Iterator<Item = Entry> + 'b>(
+        &'b self,
+        lookup: impl Fn(&'b dyn Dictionary) -> I,
+    ) -> Box<dyn Iterator<Item = Entry> + 'b> {
+        let mut entries: Vec<Entry> = self.sources.iter().flat_map(|&d| lookup(d)).collect();
+        //the sort is stable, so for each sequence number, the entry from the first source is kept
+        entries.sort_by_key(|e| e.number);
+        entries.dedup_by_key(|e| e.number);
+        Box::new(entries.into_iter())
+    }
+}
+
+impl<'a> Dictionary for MergedDictionary<'a> {
+    fn entries(&self) -> Box<dyn Iterator<Item = Entry> + '_> {
+        self.merge(|d| d.entries())
+    }
+
+    fn entries_with_headword<'b>(&'b self, text: &'b str) -> Box<dyn Iterator<Item = Entry> + 'b> {
+        self.merge(move |d| d.entries_with_headword(text))
+    }
+
+    fn entries_containing_kanji(&self, kanji: char) -> Box<dyn Iterator<Item = Entry> + '_> {
+        self.merge(|d| d.entries_containing_kanji(kanji))
+    }
+
+    fn entries_with_loanword_source(
+        &self,
+        language: SourceLanguage,
+    ) -> Box<dyn Iterator<Item = Entry> + '_> {
+        self.merge(|d| d.entries_with_loanword_source(language))
+    }
+
+    fn expressions(&self) -> Box<dyn Iterator<Item = Entry> + '_> {
+        self.merge(|d| d.expressions())
+    }
+
+    fn proverbs(&self) -> Box<dyn Iterator<Item = Entry> + '_> {
+        self.merge(|d| d.proverbs())
+    }
+
+    fn yojijukugo(&self) -> Box<dyn Iterator<Item = Entry> + '_> {
+        self.merge(|d| d.yojijukugo())
+    }
+}
+
+impl Dictionary for [Entry] {
+    fn entries(&self) -> Box<dyn Iterator<Item = Entry> + '_> {
+        Box::new(self.iter().copied())
+    }
+}
+
+impl Dictionary for Vec<Entry> {
+    fn entries(&self) -> Box<dyn Iterator<Item = Entry> + '_> {
+        Box::new(self.iter().copied())
+    }
+}
@@ -0,0 +1,224 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains [write_payload()] and [read_payload()], which export the embedded database
+//! into a standalone file and read it back.
+
+use crate::*;
+use std::io::{Error, ErrorKind, Read, Result, Write};
+
+///The most recent version of the payload file format, which is written by [write_payload()].
+pub const PAYLOAD_FILE_VERSION: u32 = 1;
+
+const MAGIC: &[u8; 8] = b"JMDICTPF";
+
+///Writes the database that was compiled into this binary into a single file, e.g. for consumers
+///that are not written in Rust.
+///
+///The file contains the same arrays that this crate embeds, but always in decoded form (i.e. the
+///`compact-payload` and `compressed-payload` features do not affect the file). Its layout is as
+///follows, with all integers in little-endian byte order:
+///
+///* the magic bytes `JMDICTPF`,
+///* the format version as u32 (see [PAYLOAD_FILE_VERSION]),
+///* the number of sections as u32,
+///* for each section, the length of its name as u8, its name in ASCII, and the length of its
+///  contents in bytes as u64,
+///* the contents of all sections, in the same order as their names.
+///
+///The section `metadata` contains lines of the form `key=value`, with the keys `jmdict_version`
+///(the version of the crate that wrote the file), `build_fingerprint` (see [BUILD_FINGERPRINT]),
+///`entry_count`, `entry_order` (`sequence_number` or `frequency`, see [EntryOrder]) and, if known,
+///`creation_date` (see [DatabaseInfo::creation_date]). The section `strings` contains all texts in
+///UTF-8. All other sections are arrays of u32, such as `entry_offsets` and `payload`. Their
+///structure is explained in the "Payload structure" section of `CONTRIBUTING.md`, and may change
+///between versions of this crate even if the file format version stays the same.
+///
+///```
+///let mut file = Vec::new();
+///jmdict::write_payload(&mut file).unwrap();
+///let payload = jmdict::read_payload(&file[..]).unwrap();
+///assert!(payload.is_from_this_build());
+///```
+pub fn write_payload<W: Write>(out: &mut W) -> Result<()> {
+    let info = database_info();
+    let mut metadata = format!(
+        "jmdict_version={}\nbuild_fingerprint={}\nentry_count={}\nentry_order={}\n",
+        env!("CARGO_PKG_VERSION"),
+        BUILD_FINGERPRINT,
+        entries().len(),
+        match info.entry_order {
+            EntryOrder::SequenceNumber => "sequence_number",
+            EntryOrder::Frequency => "frequency",
+        },
+    );
+    if let Some(creation_date) = info.creation_date {
+        metadata.push_str(&format!("creation_date={}\n", creation_date));
+    }
+
+    let u32_sections = payload::u32_sections();
+    let mut sections: Vec<(&str, usize)> = vec![
+        ("metadata", metadata.len()),
+        ("strings", payload::all_texts().len()),
+    ];
+    sections.extend(
+        u32_sections
+            .iter()
+            .map(|(name, vals)| (*name, vals.len() * 4)),
+    );
+
+    out.write_all(MAGIC)?;
+    out.write_all(&PAYLOAD_FILE_VERSION.to_le_bytes())?;
+    out.write_all(&(sections.len() as u32).to_le_bytes())?;
+    for (name, len) in sections {
+        out.write_all(&[name.len() as u8])?;
+        out.write_all(name.as_bytes())?;
+        out.write_all(&(len as u64).to_le_bytes())?;
+    }
+    out.write_all(metadata.as_bytes())?;
+    out.write_all(payload::all_texts().as_bytes())?;
+    //the arrays are converted in chunks to avoid a separate write call for each value
+    let mut buf = Vec::new();
+    for (_, vals) in u32_sections {
+        for chunk in vals.chunks(4096) {
+            buf.clear();
+            buf.extend(chunk.iter().flat_map(|val| val.to_le_bytes()));
+            out.write_all(&buf)?;
+        }
+    }
+    Ok(())
+}
+
+///Reads a file that was written by [write_payload()]. The file may come from a different build or
+///version of this crate, as long as its format version is supported.
+///
+///Fails with [ErrorKind::InvalidData] if the file is not a payload file, if it was written in a
+///newer format version than this crate can read, or if it is truncated.
+pub fn read_payload<R: Read>(mut input: R) -> Result<PayloadFile> {
+    let invalid = |msg: String| Error::new(ErrorKind::InvalidData, msg);
+    let read_exact = |input: &mut R, len: u64| -> Result<Vec<u8>> {
+        //NOTE: This does not allocate `len` bytes upfront, since a corrupted file could give any
+        //length.
+        let mut buf = Vec::new();
+        input.by_ref().take(len).read_to_end(&mut buf)?;
+        if (buf.len() as u64) < len {
+            return Err(invalid("payload file is truncated".into()));
+        }
+        Ok(buf)
+    };
+    let read_u32 = |input: &mut R| -> Result<u32> {
+        let buf = read_exact(input, 4)?;
+        Ok(u32::from_le_bytes([buf[0], buf[1], buf[2], buf[3]]))
+    };
+
+    if read_exact(&mut input, MAGIC.len() as u64)? != MAGIC {
+        return Err(invalid("not a payload file".into()));
+    }
+    let version = read_u32(&mut input)?;
+    if version > PAYLOAD_FILE_VERSION {
+        return Err(invalid(format!(
+            "payload file has format version {}, but this version of jmdict only supports versions up to {}",
+            version, PAYLOAD_FILE_VERSION
+        )));
+    }
+
+    let section_count = read_u32(&mut input)?;
+    let mut section_headers = Vec::new();
+    for _ in 0..section_count {
+        let name_len = read_exact(&mut input, 1)?[0];
+        let name = String::from_utf8(read_exact(&mut input, name_len.into())?)
+            .map_err(|_| invalid("invalid section name in payload file".into()))?;
+        let mut len = [0; 8];
+        len.copy_from_slice(&read_exact(&mut input, 8)?);
+        section_headers.push((name, u64::from_le_bytes(len)));
+    }
+    let mut sections = Vec::with_capacity(section_headers.len());
+    for (name, len) in section_headers {
+        let contents = read_exact(&mut input, len)?;
+        let is_text = name == "metadata" || name == "strings";
+        if is_text && std::str::from_utf8(&contents).is_err() {
+            return Err(invalid(format!("section {} is not valid UTF-8", name)));
+        }
+        if !is_text && contents.len() % 4 != 0 {
+            return Err(invalid(format!("section {} is not an array of u32", name)));
+        }
+        sections.push((name, contents));
+    }
+
+    let file = PayloadFile { sections };
+    for name in &["metadata", "strings"] {
+        if file.section(name).is_none() {
+            return Err(invalid(format!("payload file has no {} section", name)));
+        }
+    }
+    if file.metadata("build_fingerprint").is_none() {
+        return Err(invalid("payload file has no build fingerprint".into()));
+    }
+    Ok(file)
+}
+
+///The contents of a file that was written by [write_payload()]. See there for the meaning of the
+///sections.
+#[derive(Clone, Debug)]
+pub struct PayloadFile {
+    sections: Vec<(String, Vec<u8>)>,
+}
+
+impl PayloadFile {
+    ///Returns the value for the given key in the `metadata` section.
+    pub fn metadata(&self, key: &str) -> Option<&str> {
+        let metadata = std::str::from_utf8(self.section("metadata")?).ok()?;
+        metadata.lines().find_map(|line| {
+            let (k, v) = line.split_once('=')?;
+            if k == key {
+                Some(v)
+            } else {
+                None
+            }
+        })
+    }
+
+    ///Returns the [BUILD_FINGERPRINT] of the build that wrote this file.
+    pub fn build_fingerprint(&self) -> &str {
+        self.metadata("build_fingerprint").unwrap()
+    }
+
+    ///Whether this file contains the same database as the one compiled into this binary.
+    pub fn is_from_this_build(&self) -> bool {
+        self.build_fingerprint() == BUILD_FINGERPRINT
+    }
+
+    ///Returns the names of all sections in this file, in the order in which they appear.
+    pub fn section_names(&self) -> impl Iterator<Item = &str> {
+        self.sections.iter().map(|(name, _)| name.as_str())
+    }
+
+    ///Returns the raw contents of the section with the given name.
+    pub fn section(&self, name: &str) -> Option<&[u8]> {
+        let (_, contents) = self.sections.iter().find(|(n, _)| n == name)?;
+        Some(contents)
+    }
+
+    ///Returns the contents of the `strings` section.
+    pub fn strings(&self) -> &str {
+        std::str::from_utf8(self.section("strings").unwrap()).unwrap()
+    }
+
+    ///Returns the contents of the section with the given name as an array of u32. Returns None if
+    ///there is no such section, or if it is not an array of u32.
+    pub fn u32s(&self, name: &str) -> Option<Vec<u32>> {
+        if name == "metadata" || name == "strings" {
+            return None;
+        }
+        let contents = self.section(name)?;
+        Some(
+            contents
+                .chunks_exact(4)
+                .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                .collect(),
+        )
+    }
+}
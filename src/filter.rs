@@ -0,0 +1,112 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains [ContentFilter], which hides parts of the database at runtime.
+
+use crate::*;
+
+///Hides archaic senses and uncommon forms at runtime.
+///
+///The `scope-archaic` and `scope-uncommon` features decide at build time whether this content is
+///included in the database at all. When an application needs both views of the database (e.g. a
+///"learner mode" that hides this content, and a "scholar mode" that shows it), it can enable these
+///features and use a ContentFilter to hide the content again where appropriate:
+///
+///```
+///use jmdict::ContentFilter;
+///
+///let learner_mode = ContentFilter {
+///    hide_archaic: true,
+///    hide_uncommon: true,
+///};
+///for entry in learner_mode.entries(jmdict::entries()).take(10) {
+///    let readings: Vec<_> = learner_mode.reading_elements(&entry).map(|r| r.text).collect();
+///    assert!(!readings.is_empty());
+///}
+///```
+///
+///The default filter does not hide anything. Hiding content that is not included in the database
+///has no effect, so e.g. `hide_archaic` is meaningless unless `scope-archaic` is enabled.
+///
+///The filter works on the level of individual elements and senses. The [Entry] itself is not
+///changed, so methods like [Entry::senses()] still yield all senses. Use the methods of the filter
+///instead, e.g. [ContentFilter::senses()]. Searches can be filtered through
+///[SearchOptions::content_filter].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ContentFilter {
+    ///If true, senses with the [archaic](SenseInfo::Archaism) label are hidden. This hides the
+    ///same senses that are not included in the database when the `scope-archaic` feature is
+    ///disabled.
+    pub hide_archaic: bool,
+    ///If true, kanji elements and reading elements without any [priority](Priority) are hidden.
+    ///This hides the same elements that are not included in the database when the
+    ///`scope-uncommon` feature is disabled.
+    ///
+    ///This has no effect when the `exclude-priority` feature is enabled, since the priorities are
+    ///not available at runtime in that case.
+    pub hide_uncommon: bool,
+}
+
+impl ContentFilter {
+    ///Whether the given entry is shown by this filter. Like at build time, entries are hidden if
+    ///none of their reading elements or none of their senses are shown.
+    pub fn accepts_entry(&self, entry: &Entry) -> bool {
+        self.reading_elements(entry).next().is_some() && self.senses(entry).next().is_some()
+    }
+
+    ///Whether the given kanji element is shown by this filter.
+    pub fn accepts_kanji_element(&self, k: &KanjiElement) -> bool {
+        !self.hides_priority(k.priority)
+    }
+
+    ///Whether the given reading element is shown by this filter.
+    pub fn accepts_reading_element(&self, r: &ReadingElement) -> bool {
+        !self.hides_priority(r.priority)
+    }
+
+    ///Whether the given sense is shown by this filter.
+    pub fn accepts_sense(&self, s: &Sense) -> bool {
+        !(self.hide_archaic && s.infos().any(|i| i == SenseInfo::Archaism))
+    }
+
+    fn hides_priority(&self, priority: Priority) -> bool {
+        self.hide_uncommon && !cfg!(feature = "exclude-priority") && priority == Priority::default()
+    }
+
+    ///Like [entries.filter()](Iterator::filter), but only yields entries that are shown by this
+    ///filter.
+    pub fn entries<I: IntoIterator<Item = Entry>>(
+        &self,
+        entries: I,
+    ) -> impl Iterator<Item = Entry> {
+        let filter = *self;
+        entries.into_iter().filter(move |e| filter.accepts_entry(e))
+    }
+
+    ///Like [entry.kanji_elements()](Entry::kanji_elements), but only yields kanji elements that
+    ///are shown by this filter.
+    pub fn kanji_elements(&self, entry: &Entry) -> impl Iterator<Item = KanjiElement> {
+        let filter = *self;
+        entry
+            .kanji_elements()
+            .filter(move |k| filter.accepts_kanji_element(k))
+    }
+
+    ///Like [entry.reading_elements()](Entry::reading_elements), but only yields reading elements
+    ///that are shown by this filter.
+    pub fn reading_elements(&self, entry: &Entry) -> impl Iterator<Item = ReadingElement> {
+        let filter = *self;
+        entry
+            .reading_elements()
+            .filter(move |r| filter.accepts_reading_element(r))
+    }
+
+    ///Like [entry.senses()](Entry::senses), but only yields senses that are shown by this filter.
+    pub fn senses(&self, entry: &Entry) -> impl Iterator<Item = Sense> {
+        let filter = *self;
+        entry.senses().filter(move |s| filter.accepts_sense(s))
+    }
+}
@@ -0,0 +1,298 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains the JSON serialization behind [Entry::to_json()]. It does not rely on any
+//! external crates, but produces the same structure as the `Serialize` implementations that are
+//! enabled by the `serde` feature.
+
+use crate::*;
+
+pub(crate) trait WriteJson {
+    fn write_json(&self, out: &mut String);
+}
+
+fn write_object(out: &mut String, fields: &[(&str, &dyn WriteJson)]) {
+    out.push('{');
+    for (idx, (key, value)) in fields.iter().enumerate() {
+        if idx > 0 {
+            out.push(',');
+        }
+        key.write_json(out);
+        out.push(':');
+        value.write_json(out);
+    }
+    out.push('}');
+}
+
+fn write_array<T: WriteJson>(out: &mut String, items: impl Iterator<Item = T>) {
+    out.push('[');
+    for (idx, item) in items.enumerate() {
+        if idx > 0 {
+            out.push(',');
+        }
+        item.write_json(out);
+    }
+    out.push(']');
+}
+
+impl WriteJson for &str {
+    fn write_json(&self, out: &mut String) {
+        out.push('"');
+        for c in self.chars() {
+            match c {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => out.push(c),
+            }
+        }
+        out.push('"');
+    }
+}
+
+impl WriteJson for u32 {
+    fn write_json(&self, out: &mut String) {
+        out.push_str(&self.to_string());
+    }
+}
+
+impl WriteJson for u16 {
+    fn write_json(&self, out: &mut String) {
+        out.push_str(&self.to_string());
+    }
+}
+
+impl WriteJson for bool {
+    fn write_json(&self, out: &mut String) {
+        out.push_str(if *self { "true" } else { "false" });
+    }
+}
+
+impl<T: WriteJson> WriteJson for Option<T> {
+    fn write_json(&self, out: &mut String) {
+        match self {
+            Some(val) => val.write_json(out),
+            None => out.push_str("null"),
+        }
+    }
+}
+
+//enums are represented by their JMdict codes, as returned by `Enum::code()`
+macro_rules! impl_for_enums {
+    ($($enum: ty),*) => {
+        $(
+            impl WriteJson for $enum {
+                fn write_json(&self, out: &mut String) {
+                    self.code().write_json(out);
+                }
+            }
+        )*
+    };
+}
+
+impl_for_enums!(
+    Dialect,
+    GlossGender,
+    GlossLanguage,
+    GlossType,
+    KanjiInfo,
+    PartOfSpeech,
+    ReadingInfo,
+    SenseInfo,
+    SenseTopic,
+    SourceLanguage
+);
+
+impl WriteJson for PriorityInCorpus {
+    fn write_json(&self, out: &mut String) {
+        let name = match self {
+            PriorityInCorpus::Primary => "Primary",
+            PriorityInCorpus::Secondary => "Secondary",
+            PriorityInCorpus::Absent => "Absent",
+        };
+        name.write_json(out);
+    }
+}
+
+macro_rules! impl_for_iterators {
+    ($($iter: ty),*) => {
+        $(
+            impl WriteJson for $iter {
+                fn write_json(&self, out: &mut String) {
+                    write_array(out, *self);
+                }
+            }
+        )*
+    };
+}
+
+impl_for_iterators!(
+    CrossReferences,
+    Dialects,
+    ExampleTranslations,
+    Examples,
+    Glosses,
+    KanjiElements,
+    KanjiInfos,
+    LoanwordSources,
+    PartsOfSpeech,
+    ReadingElements,
+    ReadingInfos,
+    SenseInfos,
+    SenseTopics,
+    Senses,
+    Strings
+);
+
+impl WriteJson for Entry {
+    fn write_json(&self, out: &mut String) {
+        write_object(
+            out,
+            &[
+                ("number", &self.number),
+                ("kanji_elements", &self.kanji_elements()),
+                ("reading_elements", &self.reading_elements()),
+                ("senses", &self.senses()),
+            ],
+        );
+    }
+}
+
+impl WriteJson for KanjiElement {
+    fn write_json(&self, out: &mut String) {
+        write_object(
+            out,
+            &[
+                ("text", &self.text),
+                ("priority", &self.priority),
+                ("infos", &self.infos()),
+            ],
+        );
+    }
+}
+
+impl WriteJson for ReadingElement {
+    fn write_json(&self, out: &mut String) {
+        write_object(
+            out,
+            &[
+                ("text", &self.text),
+                ("priority", &self.priority),
+                ("no_kanji", &self.no_kanji),
+                ("infos", &self.infos()),
+                ("restricted_to", &self.restricted_to()),
+            ],
+        );
+    }
+}
+
+impl WriteJson for Priority {
+    fn write_json(&self, out: &mut String) {
+        write_object(
+            out,
+            &[
+                ("news", &self.news),
+                ("ichimango", &self.ichimango),
+                ("loanwords", &self.loanwords),
+                ("additional", &self.additional),
+                ("frequency_bucket", &self.frequency_bucket),
+            ],
+        );
+    }
+}
+
+impl WriteJson for Sense {
+    fn write_json(&self, out: &mut String) {
+        write_object(
+            out,
+            &[
+                (
+                    "applicable_kanji_elements",
+                    &self.applicable_kanji_elements(),
+                ),
+                (
+                    "applicable_reading_elements",
+                    &self.applicable_reading_elements(),
+                ),
+                ("parts_of_speech", &self.parts_of_speech()),
+                ("cross_references", &self.cross_references()),
+                ("antonyms", &self.antonyms()),
+                ("topics", &self.topics()),
+                ("infos", &self.infos()),
+                ("freetext_infos", &self.freetext_infos()),
+                ("loanword_sources", &self.loanword_sources()),
+                ("dialects", &self.dialects()),
+                ("glosses", &self.glosses()),
+                ("examples", &self.examples()),
+            ],
+        );
+    }
+}
+
+impl WriteJson for CrossReference {
+    fn write_json(&self, out: &mut String) {
+        write_object(
+            out,
+            &[
+                ("text", &self.text),
+                ("headword", &self.headword),
+                ("reading", &self.reading),
+                ("sense_number", &self.sense_number),
+            ],
+        );
+    }
+}
+
+impl WriteJson for LoanwordSource {
+    fn write_json(&self, out: &mut String) {
+        write_object(
+            out,
+            &[
+                ("text", &self.text),
+                ("language", &self.language),
+                ("is_partial", &self.is_partial),
+                ("is_wasei", &self.is_wasei),
+            ],
+        );
+    }
+}
+
+impl WriteJson for Gloss {
+    fn write_json(&self, out: &mut String) {
+        write_object(
+            out,
+            &[
+                ("language", &self.language),
+                ("text", &self.text),
+                ("gender", &self.gender),
+                ("gloss_type", &self.gloss_type),
+            ],
+        );
+    }
+}
+
+impl WriteJson for Example {
+    fn write_json(&self, out: &mut String) {
+        write_object(
+            out,
+            &[
+                ("source_id", &self.source_id),
+                ("text", &self.text),
+                ("sentence", &self.sentence),
+                ("translations", &self.translations()),
+            ],
+        );
+    }
+}
+
+impl WriteJson for ExampleTranslation {
+    fn write_json(&self, out: &mut String) {
+        write_object(out, &[("language", &self.language), ("text", &self.text)]);
+    }
+}
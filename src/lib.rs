@@ -16,7 +16,8 @@
 //! The database files compiled into the crate are licensed from the Electronic Dictionary Research
 //! and Development Group under Creative Commons licenses. Applications linking this crate directly
 //! oder indirectly must display appropriate copyright notices to users. Please refer to the
-//! [EDRDG's license statement](https://www.edrdg.org/edrdg/licence.html) for details.
+//! [EDRDG's license statement](https://www.edrdg.org/edrdg/licence.html) for details. The
+//! [database_info() function](database_info) reports which JMdict snapshot is compiled in.
 //!
 //! # Basic usage
 //!
@@ -54,6 +55,40 @@
 //!   [PartOfSpeech] enum will not include variants that are only relevant for archaic vocabulary,
 //!   such as obsolete conjugation patterns. (The [AllPartOfSpeech] enum always contains all
 //!   variants.)
+//! * When both views are needed in the same binary, enable `scope-uncommon` and `scope-archaic` and
+//!   hide the respective content at runtime where appropriate, using a [ContentFilter].
+//! * The `exclude-names` feature drops senses that describe proper names (see
+//!   [SenseInfo::is_name_type()]), for applications that use a separate names dictionary.
+//! * The `exclude-sensitive` feature drops senses that are marked as X-rated, vulgar, derogatory
+//!   or sensitive (see [SenseInfo::is_sensitive_content()]), e.g. for educational apps aimed at
+//!   children. Entries without any remaining senses are dropped entirely.
+//! * The `exclude-priority` feature omits the priority of kanji elements and reading elements
+//!   from the embedded database, which saves 4 bytes per element. All priorities are then equal
+//!   to `Priority::default()`, so methods that depend on them (e.g. [Priority::is_common()] and
+//!   `random_common_entry()`) become meaningless. Entry selection at build time, e.g. through
+//!   `RUST_JMDICT_MAX_ENTRIES`, still takes priorities into account.
+//! * The `tolerant-parsing` feature allows building with a JMdict that is newer than this crate.
+//!   Values that cannot be represented (e.g. a loanword source language that [SourceLanguage] does
+//!   not know yet) are then skipped with a build warning, instead of failing the build. The same
+//!   goes for example sentences in a format that this crate does not understand. Unknown
+//!   entity values (e.g. a new part of speech) do not need this feature since they are
+//!   represented by the `Unknown` variant of their enum.
+//!
+//! Independently of these features, the `RUST_JMDICT_MAX_ENTRIES` environment variable can be set
+//! at build time to only include the given number of most common entries (as ranked by
+//! [Priority::score()]). This is useful for very small databases on memory-constrained targets.
+//! Similarly, `RUST_JMDICT_TOPICS` can be set to a comma-separated list of [SenseTopic] codes (e.g.
+//! `med,pharm,anat`) to only include senses with at least one of these topics, and
+//! `RUST_JMDICT_PARTS_OF_SPEECH` can be set to a list of [PartOfSpeech] codes (e.g. `v*` for all
+//! verbs) to only include entries with at least one of these parts of speech.
+//! For more complex selections, `RUST_JMDICT_FILTER` can point to a JSON file with filter rules,
+//! as described in the README.
+//!
+//! The JMdict does not say which entries appear on which level of the JLPT. If
+//! `RUST_JMDICT_JLPT_LIST` points to a list of JLPT levels as described in the README, the level
+//! of each entry can be queried with [Entry::jlpt_level()]. Additionally,
+//! `RUST_JMDICT_JLPT_LEVEL` can be set to e.g. `N3` to only include entries on levels N5 through
+//! N3.
 //!
 //! ### Target languages
 //!
@@ -75,6 +110,57 @@
 //! languages. For example, in the default configuration, `GlossLanguage::English` will be the only
 //! variant. (The [AllGlossLanguage] enum always contains all variants.)
 //!
+//! When multiple target languages are enabled, [with_languages()] can be used to restrict the
+//! database to some of them at runtime, e.g. to the language that the user has selected.
+//!
+//! ### Optional integrations
+//!
+//! * The `rand` feature adds [random_entry()] and [random_common_entry()] for sampling entries
+//!   using the [rand crate](https://docs.rs/rand/).
+//! * The `rayon` feature allows iterating over [entries()] in parallel using the
+//!   [rayon crate](https://docs.rs/rayon/), e.g. `jmdict::entries().into_par_iter()`.
+//! * The `serde` feature implements `Serialize` from the [serde crate](https://docs.rs/serde/)
+//!   for [Entry] and all types contained within it, e.g. for converting entries into JSON. Enums
+//!   also implement `Deserialize`. Enum values are represented by their JMdict codes (e.g. `"v5k"`
+//!   for [PartOfSpeech::GodanKuVerb]), so that they round-trip with other JMdict tooling.
+//! * The `diacritic-folding` feature makes [search()] ignore diacritics on Latin letters in
+//!   glosses (e.g. "cafe" matches "Café") using the
+//!   [unicode-normalization crate](https://docs.rs/unicode-normalization/). Without it, glosses
+//!   are still matched case-insensitively.
+//! * The `stemming` feature makes [search()] match morphological variants of words in glosses (e.g.
+//!   "running" matches "to run"). Currently, only English glosses are stemmed (see [Stemmer]).
+//! * The `bigram-index` feature adds `entries_with_headword_containing()` for finding entries by
+//!   a substring of their kanji elements or reading elements. This is backed by an index of all
+//!   pairs of consecutive characters, which makes the embedded database about 14% larger.
+//! * The `radicals` feature adds `kanji_by_components()` for finding kanji by their visual
+//!   components, as in the multi-radical lookup of many dictionary apps. The resulting kanji can
+//!   be used with [entries_containing_kanji()] to find vocabulary. The components are taken from a
+//!   RADKFILE that has to be supplied at build time via the `RUST_JMDICT_RADKFILE` environment
+//!   variable (see README).
+//! * The `stroke-order` feature adds `stroke_order()`, which returns the strokes of a kanji or kana
+//!   as SVG paths, e.g. for stroke order diagrams or handwriting practice. The strokes are taken
+//!   from a KanjiVG file that has to be supplied at build time via the `RUST_JMDICT_KANJIVG`
+//!   environment variable (see README).
+//! * The `builtin-download` feature makes the build script download the JMdict contents with a
+//!   built-in HTTP client instead of `curl`. Proxies are configured through the `HTTPS_PROXY` and
+//!   `NO_PROXY` environment variables as usual.
+//! * The `decode-xz` and `decode-zstd` features allow the build script to read JMdict contents
+//!   that are compressed with XZ or Zstandard, respectively (see README). GZip is always
+//!   supported. Both decoders are written in pure Rust.
+//! * The `compact-payload` feature stores the embedded database in a compressed encoding, which
+//!   reduces its size by about 45%. In exchange, each part of the database is decompressed into
+//!   heap memory when it is first accessed, which takes a few milliseconds.
+//! * The `compressed-payload` feature stores the embedded database compressed with Zstandard,
+//!   which reduces its size by about 65% (or more in combination with `compact-payload`). As
+//!   above, each part of the database is decompressed into heap memory when it is first accessed.
+//!   Decompression is done by the [ruzstd crate](https://docs.rs/ruzstd/), which is written in
+//!   pure Rust and therefore also works for WASM targets.
+//! * The `order-by-frequency` feature makes [entries()] yield the most common entries first, as
+//!   determined by the priority of their kanji elements and reading elements, instead of ordering
+//!   entries by sequence number. For example, `jmdict::entries().take(5000)` then yields the 5000
+//!   most useful words. Lookups and searches return their results in the same order. The ordering
+//!   in use is reported by [database_info()].
+//!
 //! ### Crippled builds: `db-minimal`
 //!
 //! When the `db-minimal` feature is enabled, only a severly reduced portion of the JMdict will
@@ -88,25 +174,208 @@
 //! disabled entirely. The crate is compiled as usual, but `entries()` will be an empty list.
 //! This is useful for documentation builds like for `docs.rs`, where `--all-features` is given.
 
+#![forbid(unsafe_code)]
+
 pub use jmdict_enums::{
-    AllGlossLanguage, AllPartOfSpeech, Dialect, DisabledVariant, Enum, GlossLanguage, GlossType,
-    KanjiInfo, PartOfSpeech, Priority, PriorityInCorpus, ReadingInfo, SenseInfo, SenseTopic,
+    enum_kind, enum_kinds, AllGlossLanguage, AllPartOfSpeech, Dialect, DisabledVariant, Enum,
+    EnumKind, EnumSet, EnumSetIter, GlossGender, GlossLanguage, GlossLanguageCodeError, GlossType,
+    KanjiInfo, ParseEnumError, ParsePriorityError, PartOfSpeech, Priority, PriorityInCorpus,
+    PriorityScore, ReadingInfo, SenseInfo, SenseTopic, SourceLanguage, VariantInfo,
+};
+mod dictionary;
+pub use dictionary::{Dictionary, EmbeddedDictionary, MergedDictionary};
+mod export;
+pub use export::{read_payload, write_payload, PayloadFile, PAYLOAD_FILE_VERSION};
+mod filter;
+pub use filter::ContentFilter;
+mod json;
+mod lookup;
+#[cfg(feature = "bigram-index")]
+pub use lookup::entries_with_headword_containing;
+pub use lookup::{
+    all_readings, complete, entries_containing_kanji, entries_sharing_form,
+    entries_with_loanword_source, entries_with_stem, expressions, kanji_inventory,
+    loanword_source_languages, proverbs, segment, yojijukugo, AllReadings, FormGroup,
+    FormGroupMember, KanjiInventory, LoanwordSourceLanguages, Segments,
 };
+#[cfg(feature = "rayon")]
+mod parallel;
+#[cfg(feature = "rayon")]
+pub use parallel::ParEntries;
 mod payload;
 use payload::*;
+#[cfg(feature = "radicals")]
+mod radicals;
+#[cfg(feature = "radicals")]
+pub use radicals::{components_of, kanji_by_components, radicals, Radical};
+#[cfg(feature = "rand")]
+mod random;
+#[cfg(feature = "rand")]
+pub use random::{random_common_entry, random_entry};
+mod search;
+pub use search::{
+    search, search_with_options, MatchedElement, SearchHit, SearchHits, SearchOptions,
+};
+#[cfg(feature = "serde")]
+mod serialize;
+mod stats;
+#[cfg(feature = "stroke-order")]
+mod strokes;
+pub use stats::{gloss_count, ENTRY_COUNT, GLOSS_COUNT, SENSE_COUNT};
+#[cfg(feature = "stroke-order")]
+pub use strokes::{stroke_order, Strokes};
+#[cfg(feature = "stemming")]
+mod stemming;
+#[cfg(feature = "stemming")]
+pub use stemming::{stemmer_for, EnglishStemmer, Stemmer};
+mod variants;
+mod verbs;
+mod view;
+pub use view::{with_languages, DictionaryView, EntryView, SenseView};
+mod xref;
+pub use xref::CrossReference;
 
+#[cfg(test)]
+mod test_build_filter;
 #[cfg(test)]
 mod test_consistency;
 #[cfg(test)]
+mod test_dictionary;
+#[cfg(test)]
+mod test_entrypack;
+#[cfg(test)]
+mod test_export;
+#[cfg(test)]
 mod test_feature_matrix;
 #[cfg(test)]
+mod test_filter;
+#[cfg(test)]
+mod test_fingerprint;
+#[cfg(test)]
+mod test_indexes;
+#[cfg(test)]
+mod test_json;
+#[cfg(test)]
 mod test_ordering;
+#[cfg(test)]
+mod test_radicals;
+#[cfg(test)]
+mod test_search;
+#[cfg(all(test, feature = "serde"))]
+mod test_serialize;
+#[cfg(test)]
+mod test_strokes;
+#[cfg(test)]
+mod test_variants;
+#[cfg(test)]
+mod test_verbs;
+#[cfg(test)]
+mod test_view;
+#[cfg(test)]
+mod test_xref;
 
-///Returns an iterator over all entries in the database.
+///Returns an iterator over all entries in the database. Entries are ordered as described by
+///[DatabaseInfo::entry_order].
 pub fn entries() -> Entries {
     Entries::new()
 }
 
+///Returns information about the JMdict snapshot that was compiled into this binary. Applications
+///can use this to display the provenance of the dictionary data to their users, as required by
+///the [EDRDG's license statement](https://www.edrdg.org/edrdg/licence.html).
+///
+///```
+///let info = jmdict::database_info();
+///if let Some(date) = info.creation_date {
+///    println!("dictionary data from JMdict as of {}", date);
+///}
+///```
+pub fn database_info() -> DatabaseInfo {
+    let creation_date = env!("JMDICT_CREATION_DATE");
+    DatabaseInfo {
+        creation_date: if creation_date.is_empty() {
+            None
+        } else {
+            Some(creation_date)
+        },
+        entry_order: if cfg!(feature = "order-by-frequency") {
+            EntryOrder::Frequency
+        } else {
+            EntryOrder::SequenceNumber
+        },
+    }
+}
+
+///A hash over all data that `build.rs` generated for embedding into this binary, in hex encoding.
+///The build is reproducible: Two binaries built from the same JMdict snapshot with the same crate
+///version, features and build-time environment variables embed byte-for-byte identical data, and
+///thus have the same fingerprint. The fingerprint does not depend on the host or target platform.
+///
+///```
+///assert_eq!(jmdict::BUILD_FINGERPRINT.len(), 64);
+///```
+pub const BUILD_FINGERPRINT: &str = env!("JMDICT_BUILD_FINGERPRINT");
+
+///Implementation details of the `jmdict-macros` crate. This is not part of the public API.
+#[doc(hidden)]
+pub mod __private {
+    use crate::*;
+
+    ///Returns the indexes (as in [entries()]) of all entries that have a kanji element or reading
+    ///element with exactly the given text.
+    pub fn entry_indexes_with_headword(text: &str) -> impl Iterator<Item = usize> {
+        payload::entry_indexes_with_headword(text)
+            .iter()
+            .map(|&idx| idx as usize)
+    }
+
+    ///Returns the entry at the given index (as in [entries()]). Panics if the index is out of
+    ///range.
+    pub fn entry_at(index: usize) -> Entry {
+        payload::get_entry(index)
+    }
+
+    ///Checks whether the given string equals [BUILD_FINGERPRINT]. This is a `const fn` so that
+    ///macro expansions can check it at compile time.
+    pub const fn is_build_fingerprint(fingerprint: &str) -> bool {
+        let (lhs, rhs) = (fingerprint.as_bytes(), BUILD_FINGERPRINT.as_bytes());
+        if lhs.len() != rhs.len() {
+            return false;
+        }
+        let mut idx = 0;
+        while idx < lhs.len() {
+            if lhs[idx] != rhs[idx] {
+                return false;
+            }
+            idx += 1;
+        }
+        true
+    }
+}
+
+///Information about the JMdict snapshot that was compiled into this binary. See [database_info()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DatabaseInfo {
+    ///The creation date of the JMdict snapshot in the format "YYYY-MM-DD". This is `None` when
+    ///the date could not be determined, e.g. when the `db-empty` feature is enabled.
+    pub creation_date: Option<&'static str>,
+    ///The order in which [entries()] yields entries.
+    pub entry_order: EntryOrder,
+}
+
+///The order of entries in the database. See [DatabaseInfo::entry_order].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EntryOrder {
+    ///Entries are ordered by ascending sequence number (see [Entry::number]). This is the default.
+    SequenceNumber,
+    ///Entries are ordered by descending priority score (see [Priority::score()]) of their most
+    ///common kanji element or reading element, and by ascending sequence number among entries with
+    ///the same score. This is used when the `order-by-frequency` feature is enabled. (When the
+    ///`exclude-priority` feature is enabled as well, the order is still determined by the
+    ///priorities in the JMdict even though they are not reported by this crate.)
+    Frequency,
+}
+
 ///An entry in the JMdict dictionary.
 ///
 ///Each entry has zero or more [kanji elements](KanjiElement), one or more
@@ -119,8 +388,10 @@ pub struct Entry {
     ///The sequence number for this Entry as it appears in the JMdict. Numbers start around 1000000
     ///and typically increment in steps of 5 or 10. (It's like BASIC line numbers, if you're old
     ///enough to understand that reference.) The [Entries] iterator guarantees entries to appear
-    ///ordered by sequence number.
+    ///ordered by sequence number, unless the `order-by-frequency` feature is enabled (see
+    ///[EntryOrder]).
     pub number: u32,
+    jlpt_level: Option<u8>,
     kanji_elements_iter: KanjiElements,
     reading_elements_iter: ReadingElements,
     senses_iter: Senses,
@@ -138,6 +409,200 @@ impl Entry {
     pub fn senses(&self) -> Senses {
         self.senses_iter
     }
+
+    ///Returns the JLPT level of this entry (5 for N5, 4 for N4, etc.). The JMdict does not contain
+    ///this information, so this is only available if a JLPT level list was supplied at build time
+    ///via the `RUST_JMDICT_JLPT_LIST` environment variable. Otherwise, this always returns None.
+    pub fn jlpt_level(&self) -> Option<u8> {
+        self.jlpt_level
+    }
+
+    ///Returns all valid combinations of a kanji element with one of its reading elements, i.e.
+    ///the forms in which this entry can be written with furigana. This honors both
+    ///[ReadingElement::restricted_to] and [ReadingElement::no_kanji].
+    ///
+    ///Entries without kanji elements do not yield any forms.
+    ///
+    ///```
+    ///let entry = jmdict::entries()
+    ///    .find(|e| e.kanji_elements().any(|k| k.text == "じゃが芋"))
+    ///    .unwrap();
+    ///let forms: Vec<_> = entry.forms().map(|(k, r)| (k.text, r.text)).collect();
+    ///assert_eq!(forms, vec![("じゃが芋", "じゃがいも"), ("ジャガ芋", "ジャガいも")]);
+    ///```
+    pub fn forms(&self) -> impl Iterator<Item = (KanjiElement, ReadingElement)> {
+        let reading_elements = self.reading_elements();
+        self.kanji_elements().flat_map(move |k| {
+            reading_elements
+                .filter(move |r| {
+                    let mut restr = r.restricted_to();
+                    !r.no_kanji && (restr.len() == 0 || restr.any(|text| text == k.text))
+                })
+                .map(move |r| (k, r))
+        })
+    }
+
+    ///Returns those senses of this entry that apply to the given kanji element, i.e. those whose
+    ///[applicable_kanji_elements()](Sense::applicable_kanji_elements) are either empty or contain
+    ///the given kanji element.
+    pub fn senses_for_kanji_element(&self, k: &KanjiElement) -> impl Iterator<Item = Sense> {
+        let text = k.text;
+        self.senses().filter(move |s| {
+            let mut stagk = s.applicable_kanji_elements();
+            stagk.len() == 0 || stagk.any(|t| t == text)
+        })
+    }
+
+    ///Returns those senses of this entry that apply to the given reading element, i.e. those
+    ///whose [applicable_reading_elements()](Sense::applicable_reading_elements) are either empty
+    ///or contain the given reading element.
+    ///
+    ///```
+    ///# #[cfg(feature = "translations-eng")] {
+    ///let entry = jmdict::entries()
+    ///    .find(|e| e.kanji_elements().any(|k| k.text == "此方"))
+    ///    .unwrap();
+    ///let glosses_for = |reading| {
+    ///    let r = entry.reading_elements().find(|r| r.text == reading).unwrap();
+    ///    let senses = entry.senses_for_reading_element(&r);
+    ///    senses.flat_map(|s| s.glosses()).map(|g| g.text).collect::<Vec<_>>()
+    ///};
+    ///assert!(glosses_for("こちら").contains(&"this person"));
+    ///assert!(!glosses_for("こっち").contains(&"this person"));
+    ///# }
+    ///```
+    pub fn senses_for_reading_element(&self, r: &ReadingElement) -> impl Iterator<Item = Sense> {
+        let text = r.text;
+        self.senses().filter(move |s| {
+            let mut stagr = s.applicable_reading_elements();
+            stagr.len() == 0 || stagr.any(|t| t == text)
+        })
+    }
+
+    ///If this entry is a transitive verb, finds the corresponding intransitive verb, and vice
+    ///versa. For example, the transitive verb 上げる ("to raise") pairs with the intransitive verb
+    ///上がる ("to rise").
+    ///
+    ///Pairs that are linked through [cross-references](Sense::cross_references) are preferred.
+    ///Otherwise, a verb is considered to be a partner if it shares the kanji and their reading,
+    ///and only differs in okurigana. If there are multiple candidates, common verbs are preferred
+    ///over uncommon or archaic ones. Returns None if this entry is not a verb marked as transitive
+    ///or intransitive, or if no partner can be found.
+    ///
+    ///```
+    ///# #[cfg(not(feature = "db-minimal"))] {
+    ///let entry = jmdict::entries()
+    ///    .find(|e| e.kanji_elements().any(|k| k.text == "上げる"))
+    ///    .unwrap();
+    ///let pair = entry.transitivity_pair().unwrap();
+    ///assert!(pair.kanji_elements().any(|k| k.text == "上がる"));
+    ///# }
+    ///```
+    pub fn transitivity_pair(&self) -> Option<Entry> {
+        verbs::transitivity_pair(self)
+    }
+
+    ///Serializes this entry into a JSON document, e.g. for sending it to a web frontend.
+    ///
+    ///The document is an object with the keys `number`, `kanji_elements`, `reading_elements` and
+    ///`senses`. Keys are named like the respective public fields or accessor methods in this crate
+    ///(e.g. [Sense::parts_of_speech()] becomes `"parts_of_speech"`). Enum values are represented by
+    ///their JMdict codes (see [Enum::code()]), e.g. `"v1"` for [PartOfSpeech::IchidanVerb]. Absent
+    ///optional values are represented as `null`.
+    ///
+    ///This structure is part of the stable API. When the `serde` feature is enabled, `Serialize`
+    ///produces the same structure.
+    ///
+    ///```
+    ///let entry = jmdict::entries().next().unwrap();
+    ///let json = entry.to_json();
+    ///assert!(json.starts_with(&format!("{{\"number\":{},", entry.number)));
+    ///```
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        json::WriteJson::write_json(self, &mut out);
+        out
+    }
+
+    ///Returns all glosses in the given language across all senses of this entry, in the order in
+    ///which they appear in the JMdict.
+    ///
+    ///```
+    ///# #[cfg(feature = "translations-eng")] {
+    ///use jmdict::GlossLanguage;
+    ///let entry = jmdict::entries()
+    ///    .find(|e| e.kanji_elements().any(|k| k.text == "如何にも"))
+    ///    .unwrap();
+    ///let glosses: Vec<_> = entry.glosses_in(GlossLanguage::English).map(|g| g.text).collect();
+    ///assert!(glosses.contains(&"indeed") && glosses.contains(&"very"));
+    ///# }
+    ///```
+    pub fn glosses_in(&self, language: GlossLanguage) -> impl Iterator<Item = Gloss> {
+        self.senses().flat_map(move |s| s.glosses_in(language))
+    }
+
+    ///Whether the first sense of this entry is usually written using kana alone, even if the entry
+    ///has kanji elements.
+    ///
+    ///In the JMdict, this is recorded separately for each sense (see [Sense::is_usually_kana]).
+    ///Since the senses of an entry are ordered by how common they are, only the first sense counts
+    ///here, so e.g. 一杯 is not usually written as いっぱい even though its later senses (e.g. "a
+    ///lot") are. Use `entry.senses().any(|s| s.is_usually_kana())` to check if any sense is
+    ///usually written using kana alone.
+    pub fn is_usually_kana(&self) -> bool {
+        matches!(self.senses().next(), Some(s) if s.is_usually_kana())
+    }
+
+    ///Picks the form of this entry that is most suitable for display as a headword.
+    ///
+    ///If this entry [is usually written using kana alone](Entry::is_usually_kana), or if it does
+    ///not have any suitable kanji elements, this is the text of the first reading element.
+    ///Otherwise, this is the text of the first common kanji element, or of the first kanji element
+    ///if none is common. Kanji elements that are marked as rare, outdated, irregular or
+    ///search-only are never chosen, and neither are search-only reading elements unless there is
+    ///no other reading element.
+    ///
+    ///```
+    ///let find = |text| {
+    ///    let mut es = jmdict::entries();
+    ///    es.find(|e| e.kanji_elements().any(|k| k.text == text)).unwrap()
+    ///};
+    ///assert_eq!(find("彼処").main_form(), "あそこ"); //usually written in kana
+    ///assert_eq!(find("明白").main_form(), "明白");
+    ///```
+    pub fn main_form(&self) -> &'static str {
+        if !self.is_usually_kana() {
+            let candidates = self.kanji_elements().filter(|k| {
+                !k.infos().any(|i| {
+                    use KanjiInfo::*;
+                    matches!(
+                        i,
+                        IrregularKanjiUsage | OutdatedKanji | RareKanjiForm | SearchOnlyKanjiForm
+                    )
+                })
+            });
+            let first_common = candidates.clone().find(|k| k.priority.is_common());
+            if let Some(k) = first_common.or_else(|| candidates.clone().next()) {
+                return k.text;
+            }
+        }
+        //every entry has at least one reading element
+        let mut readings = self.reading_elements();
+        let first = readings.clone().next().unwrap();
+        readings.find(|r| !r.is_search_only()).unwrap_or(first).text
+    }
+
+    ///Like [kanji_elements()](Entry::kanji_elements), but skips kanji elements that
+    ///[are search-only](KanjiElement::is_search_only). This is intended for display code.
+    pub fn kanji_elements_for_display(&self) -> impl Iterator<Item = KanjiElement> {
+        self.kanji_elements().filter(|k| !k.is_search_only())
+    }
+
+    ///Like [reading_elements()](Entry::reading_elements), but skips reading elements that
+    ///[are search-only](ReadingElement::is_search_only). This is intended for display code.
+    pub fn reading_elements_for_display(&self) -> impl Iterator<Item = ReadingElement> {
+        self.reading_elements().filter(|r| !r.is_search_only())
+    }
 }
 
 ///A representation of a dictionary entry using kanji or other non-kana scripts.
@@ -155,6 +620,14 @@ impl KanjiElement {
     pub fn infos(&self) -> KanjiInfos {
         self.info_iter
     }
+
+    ///Returns whether this kanji element is
+    ///[marked as search-only](KanjiInfo::SearchOnlyKanjiForm). Such forms are usually
+    ///misspellings or otherwise irregular, so they should be considered when searching, but not
+    ///displayed to the user.
+    pub fn is_search_only(&self) -> bool {
+        self.infos().any(|i| i == KanjiInfo::SearchOnlyKanjiForm)
+    }
 }
 
 ///A representation of a dictionary entry using only kana.
@@ -166,13 +639,33 @@ impl KanjiElement {
 pub struct ReadingElement {
     pub text: &'static str,
     pub priority: Priority,
+    ///If true, this reading cannot truly be regarded as a reading of any of the [KanjiElements] in
+    ///this [Entry]. For instance, the entry for "お化け" has the reading "おばけ" for its kanji
+    ///elements, but also lists the katakana spelling "オバケ", which is not a reading of "お化け".
+    pub no_kanji: bool,
     info_iter: ReadingInfos,
+    restr_iter: Strings,
 }
 
 impl ReadingElement {
     pub fn infos(&self) -> ReadingInfos {
         self.info_iter
     }
+
+    ///If not empty, this reading only applies to these [KanjiElements] out of all the
+    ///[KanjiElements] in this [Entry]. For instance, the entry for "そよ風" and "微風" has the
+    ///reading "そよかぜ" for both kanji elements, but the reading "びふう" only applies to "微風".
+    pub fn restricted_to(&self) -> Strings {
+        self.restr_iter
+    }
+
+    ///Returns whether this reading element is
+    ///[marked as search-only](ReadingInfo::SearchOnlyKanaForm). Such forms are usually
+    ///misspellings or otherwise irregular, so they should be considered when searching, but not
+    ///displayed to the user.
+    pub fn is_search_only(&self) -> bool {
+        self.infos().any(|i| i == ReadingInfo::SearchOnlyKanaForm)
+    }
 }
 
 ///The translational equivalent of a Japanese word or phrase.
@@ -188,17 +681,20 @@ impl ReadingElement {
 ///a separate sense. (And in fact, 折角 has even more senses.)
 #[derive(Clone, Copy, Debug)]
 pub struct Sense {
+    //position within the entry in the original JMdict (see `RawSense::number` in jmdict-traverse)
+    pub(crate) number: u32,
     stagk_iter: Strings,
     stagr_iter: Strings,
     pos_iter: PartsOfSpeech,
-    cross_refs_iter: Strings,
-    antonyms_iter: Strings,
+    cross_refs_iter: CrossReferences,
+    antonyms_iter: CrossReferences,
     topics_iter: SenseTopics,
     info_iter: SenseInfos,
     freetext_info_iter: Strings,
     loanword_sources_iter: LoanwordSources,
     dialects_iter: Dialects,
     glosses_iter: Glosses,
+    examples_iter: Examples,
 }
 
 impl Sense {
@@ -218,20 +714,15 @@ impl Sense {
         self.pos_iter
     }
 
-    ///If not empty, contains the text of [KanjiElements] or [ReadingElements] of other [Entries]
-    ///with a similar meaning or sense. In some cases, a [KanjiElement]'s text will be followed by
-    ///a [Reading Element]'s text and/or a sense number to provide a precise target for the
-    ///cross-reference. Where this happens, a katakana middle dot (`・`, U+30FB) is placed between
-    ///the components of the cross-reference.
-    ///
-    ///TODO: Provide a structured type for these kinds of references.
-    pub fn cross_references(&self) -> Strings {
+    ///If not empty, contains references to [KanjiElements] or [ReadingElements] of other
+    ///[Entries] with a similar meaning or sense.
+    pub fn cross_references(&self) -> CrossReferences {
         self.cross_refs_iter
     }
 
-    ///If not empty, contains the text of [KanjiElements] or [ReadingElements] of other [Entries]
-    ///which are antonyms of this sense.
-    pub fn antonyms(&self) -> Strings {
+    ///If not empty, contains references to [KanjiElements] or [ReadingElements] of other
+    ///[Entries] which are antonyms of this sense.
+    pub fn antonyms(&self) -> CrossReferences {
         self.antonyms_iter
     }
 
@@ -263,6 +754,27 @@ impl Sense {
     pub fn glosses(&self) -> Glosses {
         self.glosses_iter
     }
+
+    ///Whether this sense of the [Entry] is usually written using kana alone. This is a shorthand
+    ///for checking if [infos()](Sense::infos) contains
+    ///[SenseInfo::UsuallyWrittenUsingKanaAlone].
+    pub fn is_usually_kana(&self) -> bool {
+        self.infos()
+            .any(|i| i == SenseInfo::UsuallyWrittenUsingKanaAlone)
+    }
+
+    ///Returns only those [glosses](Sense::glosses) that are in the given language.
+    pub fn glosses_in(&self, language: GlossLanguage) -> impl Iterator<Item = Gloss> {
+        self.glosses_iter.filter(move |g| g.language == language)
+    }
+
+    ///If not empty, contains example sentences that show this sense of the [Entry] in use.
+    ///
+    ///Example sentences only appear in recent versions of the JMdict, so this is always empty when
+    ///the crate is built with an older entrypack.
+    pub fn examples(&self) -> Examples {
+        self.examples_iter
+    }
 }
 
 ///A source word in other language which a particular [Sense] of an [Entry] has been borrowed from.
@@ -275,11 +787,13 @@ impl Sense {
 ///
 ///Within an [Entry], glosses appear in the [Sense].
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct LoanwordSource {
     pub text: &'static str,
-    ///The [ISO 639-2/B code](https://en.wikipedia.org/wiki/List_of_ISO_639-2_codes) for the
-    ///language from which the word was borrowed, e.g. "ger" for German or "chi" for Chinese.
-    pub language: &'static str,
+    ///The language from which the word was borrowed. Use `language.code()` to obtain the
+    ///[ISO 639-2/B code](https://en.wikipedia.org/wiki/List_of_ISO_639-2_codes) that appears in
+    ///the JMdict, e.g. "ger" for German or "chi" for Chinese.
+    pub language: SourceLanguage,
     ///Whether this source applies only to part of the loanword. Note that this flag is not always
     ///present in the JMdict when it should be.
     pub is_partial: bool,
@@ -291,23 +805,56 @@ pub struct LoanwordSource {
 ///
 ///Within an [Entry], glosses appear in the [Sense].
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Gloss {
     pub language: GlossLanguage,
     pub text: &'static str,
+    ///The grammatical gender of the gloss in the target language (e.g. for German nouns), if the
+    ///JMdict specifies one.
+    pub gender: GlossGender,
     pub gloss_type: GlossType,
 }
 
+///An example sentence for a particular [Sense] of an [Entry], as sourced from the
+///[Tatoeba project](https://tatoeba.org/).
+#[derive(Clone, Copy, Debug)]
+pub struct Example {
+    ///The ID of this sentence in the Tatoeba project.
+    pub source_id: u32,
+    ///The form of the [Entry] as it appears in the sentence (which may be conjugated).
+    pub text: &'static str,
+    ///The example sentence in Japanese.
+    pub sentence: &'static str,
+    translations_iter: ExampleTranslations,
+}
+
+impl Example {
+    ///Translations of the example sentence. Only translations into languages enabled through the
+    ///`translations-*` feature flags are included.
+    pub fn translations(&self) -> ExampleTranslations {
+        self.translations_iter
+    }
+}
+
+///A translation of an [Example] sentence.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ExampleTranslation {
+    pub language: GlossLanguage,
+    pub text: &'static str,
+}
+
 ///We cannot do `pub type KanjiElements = Range<KanjiElement, N>` etc. because Range<T, N> is
 ///private to the crate, so instead we declare a bunch of iterator types that wrap Range<T, N>.
 macro_rules! wrap_iterator {
-    ($val: ty, $size: literal, $iter: ident) => {
+    (@impl $val: ty, $range: ty, $iter: ident) => {
         ///An iterator providing fast access to objects in the database. Instances of this iterator
         ///can be copied cheaply.
         #[derive(Clone, Copy, Debug)]
-        pub struct $iter(Range<$val, $size>);
+        pub struct $iter($range);
 
-        impl From<Range<$val, $size>> for $iter {
-            fn from(r: Range<$val, $size>) -> $iter {
+        impl From<$range> for $iter {
+            fn from(r: $range) -> $iter {
                 $iter(r)
             }
         }
@@ -329,21 +876,39 @@ macro_rules! wrap_iterator {
                 self.0.len()
             }
         }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for $iter {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_seq(*self)
+            }
+        }
+    };
+    //arrays of enum values that may be inlined into their parent record
+    (inline $val: ty, $iter: ident) => {
+        wrap_iterator!(@impl $val, EnumRange<$val>, $iter);
+    };
+    ($val: ty, $size: expr, $iter: ident) => {
+        wrap_iterator!(@impl $val, Range<$val, { $size }>, $iter);
     };
 }
 
-wrap_iterator!(KanjiElement, 5, KanjiElements);
-wrap_iterator!(KanjiInfo, 1, KanjiInfos);
-wrap_iterator!(ReadingElement, 5, ReadingElements);
-wrap_iterator!(ReadingInfo, 1, ReadingInfos);
+wrap_iterator!(KanjiElement, payload::ELEMENT_SIZE, KanjiElements);
+wrap_iterator!(inline KanjiInfo, KanjiInfos);
+wrap_iterator!(ReadingElement, payload::ELEMENT_SIZE, ReadingElements);
+wrap_iterator!(inline ReadingInfo, ReadingInfos);
 wrap_iterator!(Sense, 5, Senses);
 wrap_iterator!(&'static str, 2, Strings);
+wrap_iterator!(CrossReference, 2, CrossReferences);
 wrap_iterator!(PartOfSpeech, 1, PartsOfSpeech);
 wrap_iterator!(SenseTopic, 1, SenseTopics);
 wrap_iterator!(SenseInfo, 1, SenseInfos);
-wrap_iterator!(LoanwordSource, 4, LoanwordSources);
+wrap_iterator!(LoanwordSource, 3, LoanwordSources);
 wrap_iterator!(Dialect, 1, Dialects);
 wrap_iterator!(Gloss, 2, Glosses);
+wrap_iterator!(Example, 7, Examples);
+wrap_iterator!(ExampleTranslation, 2, ExampleTranslations);
+wrap_iterator!(Entry, 1, IndexedEntries);
 
 ///An iterator providing fast access to objects in the database. Instances of this iterator
 ///can be copied cheaply.
@@ -362,6 +927,28 @@ impl Entries {
             end: entry_count(),
         }
     }
+
+    ///Returns the entry at the given position among the entries that this iterator has not
+    ///yielded yet, without consuming the iterator. Returns None if `index >= self.len()`.
+    ///
+    ///Since the database allows random access, this takes constant time.
+    ///
+    ///```
+    ///let entries = jmdict::entries();
+    ///let middle = entries.len() / 2;
+    ///assert_eq!(
+    ///    entries.get(middle).map(|e| e.number),
+    ///    entries.skip(middle).next().map(|e| e.number),
+    ///);
+    ///assert!(entries.get(entries.len()).is_none());
+    ///```
+    pub fn get(&self, index: usize) -> Option<Entry> {
+        if index < self.len() {
+            Some(get_entry(self.start + index))
+        } else {
+            None
+        }
+    }
 }
 
 impl std::iter::Iterator for Entries {
@@ -381,6 +968,37 @@ impl std::iter::Iterator for Entries {
         let count = self.end - self.start;
         (count, Some(count))
     }
+
+    //Since entries can be decoded independently of each other, we can skip over entries in O(1)
+    //instead of decoding them one by one. This also makes `skip()` efficient.
+    fn nth(&mut self, n: usize) -> Option<Self::Item> {
+        self.start = self.start.saturating_add(n).min(self.end);
+        self.next()
+    }
+
+    fn count(self) -> usize {
+        self.end - self.start
+    }
+
+    fn last(mut self) -> Option<Self::Item> {
+        self.next_back()
+    }
+}
+
+impl std::iter::DoubleEndedIterator for Entries {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.start < self.end {
+            self.end -= 1;
+            Some(get_entry(self.end))
+        } else {
+            None
+        }
+    }
+
+    fn nth_back(&mut self, n: usize) -> Option<Self::Item> {
+        self.end = self.end.saturating_sub(n).max(self.start);
+        self.next_back()
+    }
 }
 
 impl std::iter::ExactSizeIterator for Entries {
@@ -388,3 +1006,5 @@ impl std::iter::ExactSizeIterator for Entries {
         self.end - self.start
     }
 }
+
+impl std::iter::FusedIterator for Entries {}
@@ -0,0 +1,493 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains the parts of the public API that are backed by the indexes which `build.rs`
+//! generates alongside the actual database payload.
+
+use crate::*;
+use std::borrow::Cow;
+
+///Returns an iterator over all entries that use the given kanji in at least one of their
+///[kanji elements](KanjiElement). Entries appear in the same order as in
+///[entries()].
+///
+///This is much faster than scanning through all entries since it is backed by an index that is
+///built at compile time.
+///
+///```
+///let kanji = '曜';
+///for entry in jmdict::entries_containing_kanji(kanji) {
+///    assert!(entry.kanji_elements().any(|k| k.text.contains(kanji)));
+///}
+///```
+pub fn entries_containing_kanji(kanji: char) -> IndexedEntries {
+    payload::entries_containing_kanji(kanji).into()
+}
+
+///Returns an iterator over all distinct kanji that appear in at least one
+///[kanji element](KanjiElement) in the database, in order of their Unicode codepoints. Each kanji
+///comes with the entries using it, same as in [entries_containing_kanji()]. Use `entries.len()` to
+///obtain the number of entries using that kanji without decoding them.
+///
+///This is backed by the same index as [entries_containing_kanji()], so it does not need to scan
+///the whole database.
+///
+///```
+///for (kanji, entries) in jmdict::kanji_inventory() {
+///    assert!(entries.len() > 0);
+///    println!("{} appears in {} entries", kanji, entries.len());
+///}
+///```
+pub fn kanji_inventory() -> KanjiInventory {
+    KanjiInventory(payload::all_kanji())
+}
+
+///An iterator over the result of [kanji_inventory()].
+#[derive(Clone, Debug)]
+pub struct KanjiInventory(payload::KanjiIndexRecords);
+
+impl std::iter::Iterator for KanjiInventory {
+    type Item = (char, IndexedEntries);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (kanji, entries) = self.0.next()?;
+        Some((kanji, entries.into()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl std::iter::ExactSizeIterator for KanjiInventory {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+///Returns an iterator over all entries that have a [LoanwordSource] with the given language in at
+///least one of their senses. Entries appear in the same order as in [entries()].
+///This is backed by an index that is built at compile time.
+///
+///```
+///use jmdict::SourceLanguage;
+///for entry in jmdict::entries_with_loanword_source(SourceLanguage::German) {
+///    let sources = entry.senses().flat_map(|s| s.loanword_sources());
+///    assert!(sources.map(|ls| ls.language).any(|lang| lang == SourceLanguage::German));
+///}
+///```
+pub fn entries_with_loanword_source(language: SourceLanguage) -> IndexedEntries {
+    payload::entries_with_loanword_source(language).into()
+}
+
+///Returns an iterator over all languages that appear as [LoanwordSource::language] in at least
+///one entry in the database, in lexicographic order of their ISO 639-2 codes. Each language comes
+///with the entries having a loanword source in that language, same as in
+///[entries_with_loanword_source()]. Use `entries.len()` to obtain the number of entries without
+///decoding them.
+///
+///This is backed by the same index as [entries_with_loanword_source()], so it does not need to
+///scan the whole database.
+///
+///```
+///for (language, entries) in jmdict::loanword_source_languages() {
+///    println!("{} entries borrowed from {}", entries.len(), language.name());
+///}
+///```
+pub fn loanword_source_languages() -> LoanwordSourceLanguages {
+    LoanwordSourceLanguages(payload::all_loanword_languages())
+}
+
+///An iterator over the result of [loanword_source_languages()].
+#[derive(Clone, Debug)]
+pub struct LoanwordSourceLanguages(payload::TextIndexRecords);
+
+impl std::iter::Iterator for LoanwordSourceLanguages {
+    type Item = (SourceLanguage, IndexedEntries);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (code, entries) = self.0.next()?;
+        let language = SourceLanguage::from_code(code).unwrap();
+        Some((language, entries.into()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl std::iter::ExactSizeIterator for LoanwordSourceLanguages {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+///Returns all entries that have a [kanji element](KanjiElement) or
+///[reading element](ReadingElement) with the given text, as a [FormGroup] with one
+///[FormGroupMember] per entry that also contains the elements connecting it to that text. Returns
+///`None` if no entry has such an element.
+///
+///This is useful when the same form belongs to several entries. For example, 一日 appears in one
+///entry read いちにち ("one day") and another entry read ついたち ("first day of the month").
+///
+///```
+///if let Some(group) = jmdict::entries_sharing_form("一日") {
+///    for member in &group.members {
+///        assert_eq!(member.kanji_element.unwrap().text, group.form);
+///        let readings: Vec<_> = member.reading_elements.iter().map(|r| r.text).collect();
+///        println!("entry #{} is read {}", member.entry.number, readings.join("/"));
+///    }
+///}
+///```
+///
+///This is backed by an index that is built at compile time, so it does not need to scan the whole
+///database.
+pub fn entries_sharing_form(text: &str) -> Option<FormGroup> {
+    let members: Vec<_> = IndexedEntries::from(payload::entries_with_headword(text))
+        .map(|entry| {
+            let kanji_element = entry.kanji_elements().find(|k| k.text == text);
+            let reading_elements = match kanji_element {
+                Some(k) => entry
+                    .forms()
+                    .filter(|(k2, _)| k2.text == k.text)
+                    .map(|(_, r)| r)
+                    .collect(),
+                None => entry
+                    .reading_elements()
+                    .filter(|r| r.text == text)
+                    .collect(),
+            };
+            FormGroupMember {
+                entry,
+                kanji_element,
+                reading_elements,
+            }
+        })
+        .collect();
+
+    let first = members.first()?;
+    let form = match first.kanji_element {
+        Some(k) => k.text,
+        None => first.reading_elements[0].text,
+    };
+    Some(FormGroup { form, members })
+}
+
+///A group of entries sharing the same form, as returned by [entries_sharing_form()].
+#[derive(Clone, Debug)]
+pub struct FormGroup {
+    ///The text of the kanji elements or reading elements that is shared by all members.
+    pub form: &'static str,
+    ///The entries sharing this form, in the same order as in [entries()].
+    pub members: Vec<FormGroupMember>,
+}
+
+///A member of a [FormGroup].
+#[derive(Clone, Debug)]
+pub struct FormGroupMember {
+    ///The entry that has the shared form.
+    pub entry: Entry,
+    ///The kanji element with the shared form, or `None` if the shared form is a reading element
+    ///of this entry.
+    pub kanji_element: Option<KanjiElement>,
+    ///If the shared form is a kanji element, all reading elements that can be used with it (as
+    ///in [Entry::forms()]). Otherwise, the reading element with the shared form.
+    pub reading_elements: Vec<ReadingElement>,
+}
+
+///Returns an iterator over all entries that have at least one sense marked as an
+///[expression](PartOfSpeech::Expression). Entries appear in the same order as in
+///[entries()]. This is backed by a precomputed list, so it does not need to scan the whole
+///database.
+pub fn expressions() -> IndexedEntries {
+    payload::entries_with_tag("pos:exp").into()
+}
+
+///Returns an iterator over all entries that have at least one sense marked as a
+///[proverb](SenseInfo::Proverb). Entries appear in the same order as in
+///[entries()]. This is backed by a precomputed list, so it does not need to scan the whole
+///database.
+pub fn proverbs() -> IndexedEntries {
+    payload::entries_with_tag("misc:proverb").into()
+}
+
+///Returns an iterator over all entries that have at least one sense marked as a
+///[yojijukugo](SenseInfo::Yojijukugo) (four-character compound). Entries appear in the
+///same order as in [entries()]. This is backed by a precomputed list, so it does not need to scan
+///the whole database.
+///
+///```
+///for entry in jmdict::yojijukugo() {
+///    let mut infos = entry.senses().flat_map(|s| s.infos());
+///    assert!(infos.any(|i| i == jmdict::SenseInfo::Yojijukugo));
+///}
+///```
+pub fn yojijukugo() -> IndexedEntries {
+    payload::entries_with_tag("misc:yoji").into()
+}
+
+///Returns an iterator over all entries with an inflecting [kanji element](KanjiElement) or
+///[reading element](ReadingElement) (i.e. a verb or an i-adjective) whose stem is the given text.
+///Entries appear in the same order as in [entries()].
+///
+///The stem is the part of the text that stays the same in all regular conjugated forms, as
+///determined by the [PartOfSpeech] of the entry's senses. For example, the stem of "食べる"
+///(ichidan verb) is "食べ", the stem of "書く" (godan verb) is "書", the stem of "高い"
+///(i-adjective) is "高", and the stem of "勉強" (noun taking する) is "勉強" itself. Irregular
+///forms (like "こない" for "くる") are not covered.
+///
+///This is backed by an index that is built at compile time, so resolving a conjugated form only
+///takes one lookup per prefix of that form:
+///
+///```
+///let surface = "食べました";
+///let candidates: Vec<_> = surface
+///    .char_indices()
+///    .skip(1)
+///    .flat_map(|(idx, _)| jmdict::entries_with_stem(&surface[0..idx]))
+///    .collect();
+///# #[cfg(not(feature = "db-minimal"))]
+///assert!(candidates
+///    .iter()
+///    .any(|e| e.kanji_elements().any(|k| k.text == "食べる")));
+///```
+pub fn entries_with_stem(stem: &str) -> IndexedEntries {
+    payload::entries_with_stem(stem).into()
+}
+
+///Returns all entries that have a [kanji element](KanjiElement) or
+///[reading element](ReadingElement) whose text contains the given text. Entries appear in the
+///same order as in [entries()].
+///
+///This is only available with the `bigram-index` feature. It is backed by an index of all pairs of
+///consecutive characters in kanji elements and reading elements that is built at compile time, so
+///only those entries need to be checked that contain all pairs of consecutive characters from
+///`needle`. For needles with less than two characters, this falls back to a scan of all entries.
+///
+///```
+///for entry in jmdict::entries_with_headword_containing("曜日") {
+///    assert!(entry.kanji_elements().any(|k| k.text.contains("曜日")));
+///}
+///```
+#[cfg(feature = "bigram-index")]
+pub fn entries_with_headword_containing(needle: &str) -> Vec<Entry> {
+    let matches = |e: &Entry| {
+        e.kanji_elements().any(|k| k.text.contains(needle))
+            || e.reading_elements().any(|r| r.text.contains(needle))
+    };
+    let chars: Vec<char> = needle.chars().collect();
+    if chars.len() < 2 {
+        return entries().filter(matches).collect();
+    }
+
+    //intersect the posting lists of all bigrams, starting with the shortest one
+    let mut postings: Vec<&[u32]> = chars
+        .windows(2)
+        .map(|pair| payload::entry_indexes_with_bigram(pair[0], pair[1]))
+        .collect();
+    postings.sort_unstable_by_key(|p| p.len());
+    let (shortest, others) = postings.split_first().unwrap();
+    shortest
+        .iter()
+        .filter(|idx| others.iter().all(|p| p.binary_search(idx).is_ok()))
+        .map(|&idx| payload::get_entry(idx as usize))
+        //the bigrams may appear in different elements or in a different order, so we need to check
+        .filter(matches)
+        .collect()
+}
+
+///Returns an iterator over the texts of all [reading elements](ReadingElement) in the database.
+///Each text is yielded only once, in lexicographic order, along with all entries that have a
+///reading element with that text. Use `.len()` on the latter to get the number of entries.
+///
+///```
+///let mut previous = "";
+///for (reading, entries) in jmdict::all_readings() {
+///    assert!(previous < reading);
+///    assert!(entries.len() > 0);
+///    previous = reading;
+///}
+///```
+pub fn all_readings() -> AllReadings {
+    AllReadings(payload::all_readings())
+}
+
+///An iterator over the result of [all_readings()].
+#[derive(Clone, Debug)]
+pub struct AllReadings(payload::TextIndexRecords);
+
+impl std::iter::Iterator for AllReadings {
+    type Item = (&'static str, IndexedEntries);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (text, entries) = self.0.next()?;
+        Some((text, entries.into()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl std::iter::ExactSizeIterator for AllReadings {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+///Returns up to `k` texts of [kanji elements](KanjiElement) or [reading elements](ReadingElement)
+///that start with the given prefix. This is intended for search-as-you-type UIs.
+///
+///Results are ordered by descending frequency of use, as indicated by the [Priority] of the
+///respective elements. When the same text appears in multiple elements, the highest priority among
+///them is considered. Texts with equal priority are ordered by length, then lexicographically.
+///Traditional kanji forms (kyūjitai) in the prefix are replaced by their simplified forms
+///(shinjitai) before matching.
+///
+///```
+///let completions = jmdict::complete("にちよう", 3);
+///assert!(completions.len() <= 3);
+///assert!(completions.iter().all(|text| text.starts_with("にちよう")));
+///```
+pub fn complete(prefix: &str, k: usize) -> Vec<&'static str> {
+    let prefix = variants::normalize_variants(prefix);
+    let mut candidates: Vec<_> = payload::headwords_with_prefix(&prefix)
+        .map(|(text, entries)| (commonness_of_headword(text, entries.into()), text))
+        .collect();
+    candidates.sort_by(|(score1, text1), (score2, text2)| {
+        score2
+            .cmp(score1)
+            .then(text1.len().cmp(&text2.len()))
+            .then(text1.cmp(text2))
+    });
+    candidates
+        .into_iter()
+        .take(k)
+        .map(|(_, text)| text)
+        .collect()
+}
+
+///Returns the highest Priority::score() of all elements with the given text within the given
+///entries.
+pub(crate) fn commonness_of_headword(text: &str, entries: IndexedEntries) -> PriorityScore {
+    let mut result = PriorityScore::default();
+    for entry in entries {
+        let kanji_prios = entry
+            .kanji_elements()
+            .filter(|k| k.text == text)
+            .map(|k| k.priority);
+        let reading_prios = entry
+            .reading_elements()
+            .filter(|r| r.text == text)
+            .map(|r| r.priority);
+        for prio in kanji_prios.chain(reading_prios) {
+            result = result.max(prio.score());
+        }
+    }
+    result
+}
+
+///Splits a Japanese text into words by greedy longest-match against the texts of all
+///[kanji elements](KanjiElement) and [reading elements](ReadingElement) in the database.
+///
+///The returned iterator yields the byte range of each match within `text`, together with an
+///[Entry] that has a kanji element or reading element with exactly that text. When multiple
+///entries match the same span, one pair is yielded for each of them (in the same order as in
+///[entries()]), and all these pairs carry the same span. Parts of `text` that do not match any
+///entry (e.g. punctuation) are skipped.
+///
+///When a part of `text` uses traditional kanji forms (kyūjitai), it also matches entries using the
+///respective simplified forms (shinjitai), e.g. "舊式" matches "旧式". Matches that do not require
+///this substitution take precedence when they are at least as long.
+///
+///```
+///let text = "お參りに";
+///let segments: Vec<_> = jmdict::segment(text)
+///    .map(|(span, _entry)| &text[span])
+///    .collect();
+///assert!(segments.contains(&"お參り"));
+///```
+pub fn segment(text: &str) -> Segments<'_> {
+    Segments {
+        text,
+        normalized: variants::normalize_variants(text),
+        position: 0,
+        current: None,
+    }
+}
+
+///An iterator over the result of [segment()].
+#[derive(Clone, Debug)]
+pub struct Segments<'a> {
+    text: &'a str,
+    //same as `text`, but with kyūjitai replaced by shinjitai (this does not change byte offsets)
+    normalized: Cow<'a, str>,
+    position: usize,
+    current: Option<(std::ops::Range<usize>, IndexedEntries)>,
+}
+
+impl<'a> Segments<'a> {
+    ///Finds the longest headword at the start of `rest`, and returns its length in bytes.
+    fn longest_match(rest: &str) -> Option<usize> {
+        //only probe those lengths (in chars) where headwords with this first char exist
+        let lengths = payload::headword_lengths(rest.chars().next()?);
+        let ends: Vec<usize> = rest
+            .char_indices()
+            .take(lengths.max())
+            .map(|(idx, c)| idx + c.len_utf8())
+            .collect();
+        ends.into_iter()
+            .enumerate()
+            .rev()
+            .filter(|(idx, _)| lengths.contains(idx + 1))
+            .map(|(_, end)| end)
+            .find(|&end| payload::entries_with_headword(&rest[0..end]).len() > 0)
+    }
+}
+
+impl<'a> std::iter::Iterator for Segments<'a> {
+    type Item = (std::ops::Range<usize>, Entry);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            //yield all entries for the current match, if any
+            if let Some((span, entries)) = &mut self.current {
+                if let Some(entry) = entries.next() {
+                    return Some((span.clone(), entry));
+                }
+                self.current = None;
+            }
+
+            //find the next match
+            let rest = &self.text[self.position..];
+            let first_char = rest.chars().next()?;
+            let exact_match = Self::longest_match(rest).map(|len| (len, self.text));
+            let variant_match = match &self.normalized {
+                Cow::Borrowed(_) => None,
+                Cow::Owned(normalized) => Self::longest_match(&normalized[self.position..])
+                    .map(|len| (len, &normalized[..])),
+            };
+            let (len, haystack) = match (exact_match, variant_match) {
+                (Some(m1), Some(m2)) => {
+                    if m2.0 > m1.0 {
+                        m2
+                    } else {
+                        m1
+                    }
+                }
+                (Some(m), None) | (None, Some(m)) => m,
+                (None, None) => {
+                    self.position += first_char.len_utf8();
+                    continue;
+                }
+            };
+            let span = self.position..(self.position + len);
+            let entries = payload::entries_with_headword(&haystack[span.clone()]);
+            self.current = Some((span, entries.into()));
+            self.position += len;
+        }
+    }
+}
@@ -0,0 +1,88 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains the parts of the public API that are enabled by the `rayon` feature.
+
+use crate::*;
+use rayon::iter::plumbing::{bridge, Consumer, Producer, ProducerCallback, UnindexedConsumer};
+use rayon::iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+
+///A parallel iterator over entries in the database. This is obtained by calling `into_par_iter()`
+///on [Entries].
+///
+///```
+///use rayon::prelude::*;
+///let count = jmdict::entries()
+///    .into_par_iter()
+///    .filter(|e| e.kanji_elements().len() == 0)
+///    .count();
+///assert_eq!(count, jmdict::entries().filter(|e| e.kanji_elements().len() == 0).count());
+///```
+#[derive(Clone, Copy)]
+pub struct ParEntries {
+    entries: Entries,
+}
+
+impl IntoParallelIterator for Entries {
+    type Iter = ParEntries;
+    type Item = Entry;
+
+    fn into_par_iter(self) -> ParEntries {
+        ParEntries { entries: self }
+    }
+}
+
+impl ParallelIterator for ParEntries {
+    type Item = Entry;
+
+    fn drive_unindexed<C: UnindexedConsumer<Entry>>(self, consumer: C) -> C::Result {
+        bridge(self, consumer)
+    }
+
+    fn opt_len(&self) -> Option<usize> {
+        Some(self.entries.len())
+    }
+}
+
+impl IndexedParallelIterator for ParEntries {
+    fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn drive<C: Consumer<Entry>>(self, consumer: C) -> C::Result {
+        bridge(self, consumer)
+    }
+
+    fn with_producer<CB: ProducerCallback<Entry>>(self, callback: CB) -> CB::Output {
+        callback.callback(EntriesProducer(self.entries))
+    }
+}
+
+//Since entries can be decoded independently of each other, splitting the work is just a matter of
+//splitting the range of indexes into the entry offset table.
+struct EntriesProducer(Entries);
+
+impl Producer for EntriesProducer {
+    type Item = Entry;
+    type IntoIter = Entries;
+
+    fn into_iter(self) -> Entries {
+        self.0
+    }
+
+    fn split_at(self, index: usize) -> (Self, Self) {
+        let mid = self.0.start + index;
+        let left = Entries {
+            start: self.0.start,
+            end: mid,
+        };
+        let right = Entries {
+            start: mid,
+            end: self.0.end,
+        };
+        (EntriesProducer(left), EntriesProducer(right))
+    }
+}
@@ -43,7 +43,7 @@ impl<T: FromPayload<N>, const N: usize> std::iter::Iterator for Range<T, N> {
 
     fn next(&mut self) -> Option<Self::Item> {
         if self.start < self.end {
-            let data = &as_u32_slice(ALL_DATA)[self.start..(self.start + N)];
+            let data = &as_u32_slice(&ALL_DATA)[self.start..(self.start + N)];
             let item = T::get(data.try_into().unwrap());
             self.start += N;
             Some(item)
@@ -64,58 +64,152 @@ impl<T: FromPayload<N>, const N: usize> std::iter::ExactSizeIterator for Range<T
     }
 }
 
+///Like `Range<T, 1>`, but for arrays of enum values, which may also be stored inline in the record
+///of their parent object. See `encode_inline_enums()` in build.rs for the inline format.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum EnumRange<T: FromPayload<1>> {
+    Stored(Range<T, 1>),
+    Inline(u32, PhantomData<T>),
+}
+
+impl<T: FromPayload<1>> EnumRange<T> {
+    pub(crate) fn new(start: u32, end: u32) -> Self {
+        Self::Stored(Range::new(start, end))
+    }
+
+    pub(crate) fn inline(packed: u32) -> Self {
+        Self::Inline(packed & 0x3FFF, PhantomData)
+    }
+}
+
+impl<T: FromPayload<1>> std::iter::Iterator for EnumRange<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Self::Stored(r) => r.next(),
+            Self::Inline(packed, _) => {
+                let code = *packed & 0x7F;
+                if code == 0 {
+                    return None;
+                }
+                *packed >>= 7;
+                Some(T::get(&[code - 1]))
+            }
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.len();
+        (count, Some(count))
+    }
+}
+
+impl<T: FromPayload<1>> std::iter::ExactSizeIterator for EnumRange<T> {
+    fn len(&self) -> usize {
+        match self {
+            Self::Stored(r) => r.len(),
+            Self::Inline(packed, _) => match *packed {
+                0 => 0,
+                1..=0x7F => 1,
+                _ => 2,
+            },
+        }
+    }
+}
+
 ////////////////////////////////////////////////////////////////////////////////
 // concrete types
 
 pub(crate) fn entry_count() -> usize {
-    as_u32_slice(ALL_ENTRY_OFFSETS).len()
+    as_u32_slice(&ALL_ENTRY_OFFSETS).len()
 }
 
 pub(crate) fn get_entry(idx: usize) -> Entry {
-    let offset: usize = as_u32_slice(ALL_ENTRY_OFFSETS)[idx].try_into().unwrap();
-    let data = &as_u32_slice(ALL_DATA)[offset..(offset + 4)];
+    let offset: usize = as_u32_slice(&ALL_ENTRY_OFFSETS)[idx].try_into().unwrap();
+    let data = &as_u32_slice(&ALL_DATA)[offset..(offset + 4)];
 
     let (start, end) = (data[0], data[1]);
     let mid1 = start + (data[2] & 0x0000FFFF);
     let mid2 = start + ((data[2] & 0xFFFF0000) >> 16);
 
+    let jlpt_level = (data[3] >> 24) as u8;
     Entry {
-        number: data[3],
+        number: data[3] & 0x00FFFFFF,
+        jlpt_level: if jlpt_level == 0 {
+            None
+        } else {
+            Some(jlpt_level)
+        },
         kanji_elements_iter: Range::new(start, mid1).into(),
         reading_elements_iter: Range::new(mid1, mid2).into(),
         senses_iter: Range::new(mid2, end).into(),
     }
 }
 
-impl FromPayload<5> for KanjiElement {
-    fn get(data: &[u32; 5]) -> Self {
+impl FromPayload<1> for Entry {
+    fn get(data: &[u32; 1]) -> Self {
+        get_entry(data[0].try_into().unwrap())
+    }
+}
+
+///The number of u32 in the encoding of a kanji element or reading element. The priority is stored
+///last, and left out entirely with the `exclude-priority` feature.
+pub(crate) const ELEMENT_SIZE: usize = if cfg!(feature = "exclude-priority") {
+    4
+} else {
+    5
+};
+
+fn get_priority(data: &[u32]) -> Priority {
+    match data.get(4) {
+        Some(&val) => jmdict_enums::EnumPayload::from_u32(val),
+        None => Priority::default(),
+    }
+}
+
+impl FromPayload<ELEMENT_SIZE> for KanjiElement {
+    fn get(data: &[u32; ELEMENT_SIZE]) -> Self {
         Self {
-            priority: jmdict_enums::EnumPayload::from_u32(data[0]),
-            text: get_str(data[1], data[2]),
-            info_iter: Range::new(data[3], data[4]).into(),
+            priority: get_priority(data),
+            text: get_str(data[0], data[1]),
+            info_iter: if data[2] & 0x80000000 == 0 {
+                EnumRange::new(data[2], data[3]).into()
+            } else {
+                EnumRange::inline(data[2]).into()
+            },
         }
     }
 }
 
 impl FromPayload<1> for KanjiInfo {
     fn get(data: &[u32; 1]) -> Self {
-        jmdict_enums::EnumPayload::from_u32(data[0])
+        get_entity_enum(data[0])
     }
 }
 
-impl FromPayload<5> for ReadingElement {
-    fn get(data: &[u32; 5]) -> Self {
+impl FromPayload<ELEMENT_SIZE> for ReadingElement {
+    fn get(data: &[u32; ELEMENT_SIZE]) -> Self {
+        let start = data[2];
+        let end = start + ((data[3] & 0xFFFF0000) >> 16);
+        //if bit 15 is set, the infos are inlined in place of the offset of `restr_iter`
+        let (info_iter, mid) = match data[3] & 0x0000FFFF {
+            packed if packed & 0x8000 != 0 => (EnumRange::inline(packed), start),
+            len => (EnumRange::new(start, start + len), start + len),
+        };
         Self {
-            priority: jmdict_enums::EnumPayload::from_u32(data[0]),
-            text: get_str(data[1], data[2]),
-            info_iter: Range::new(data[3], data[4]).into(),
+            priority: get_priority(data),
+            text: get_str(data[0] & 0x0FFFFFFF, data[1]),
+            no_kanji: (data[0] & 0x80000000) == 0x80000000,
+            info_iter: info_iter.into(),
+            restr_iter: Range::new(mid, end).into(),
         }
     }
 }
 
 impl FromPayload<1> for ReadingInfo {
     fn get(data: &[u32; 1]) -> Self {
-        jmdict_enums::EnumPayload::from_u32(data[0])
+        get_entity_enum(data[0])
     }
 }
 
@@ -132,8 +226,10 @@ impl FromPayload<5> for Sense {
         let mid8 = start + ((data[3] & 0xFF000000) >> 24);
         let mid9 = start + (data[4] & 0x000000FF);
         let mid10 = start + ((data[4] & 0x0000FF00) >> 8);
+        let mid11 = start + ((data[4] & 0x00FF0000) >> 16);
 
         Self {
+            number: (data[4] & 0xFF000000) >> 24,
             stagk_iter: Range::new(start, mid1).into(),
             stagr_iter: Range::new(mid1, mid2).into(),
             pos_iter: Range::new(mid2, mid3).into(),
@@ -144,34 +240,35 @@ impl FromPayload<5> for Sense {
             freetext_info_iter: Range::new(mid7, mid8).into(),
             loanword_sources_iter: Range::new(mid8, mid9).into(),
             dialects_iter: Range::new(mid9, mid10).into(),
-            glosses_iter: Range::new(mid10, end).into(),
+            examples_iter: Range::new(mid10, mid11).into(),
+            glosses_iter: Range::new(mid11, end).into(),
         }
     }
 }
 
 impl FromPayload<1> for PartOfSpeech {
     fn get(data: &[u32; 1]) -> Self {
-        jmdict_enums::EnumPayload::from_u32(data[0])
+        get_entity_enum(data[0])
     }
 }
 
 impl FromPayload<1> for SenseTopic {
     fn get(data: &[u32; 1]) -> Self {
-        jmdict_enums::EnumPayload::from_u32(data[0])
+        get_entity_enum(data[0])
     }
 }
 
 impl FromPayload<1> for SenseInfo {
     fn get(data: &[u32; 1]) -> Self {
-        jmdict_enums::EnumPayload::from_u32(data[0])
+        get_entity_enum(data[0])
     }
 }
 
-impl FromPayload<4> for LoanwordSource {
-    fn get(data: &[u32; 4]) -> Self {
+impl FromPayload<3> for LoanwordSource {
+    fn get(data: &[u32; 3]) -> Self {
         Self {
             text: get_str(data[0] & 0x0FFFFFFF, data[1]),
-            language: get_str(data[2], data[3]),
+            language: jmdict_enums::EnumPayload::from_u32(data[2]),
             is_partial: (data[0] & 0x10000000) == 0x10000000,
             is_wasei: (data[0] & 0x20000000) == 0x20000000,
         }
@@ -180,53 +277,535 @@ impl FromPayload<4> for LoanwordSource {
 
 impl FromPayload<1> for Dialect {
     fn get(data: &[u32; 1]) -> Self {
-        jmdict_enums::EnumPayload::from_u32(data[0])
+        get_entity_enum(data[0])
     }
 }
 
 impl FromPayload<2> for Gloss {
     fn get(data: &[u32; 2]) -> Self {
         let lang_code = (data[0] & 0xF0000000) >> 28;
+        let gender_code = (data[1] & 0x0F000000) >> 24;
         let type_code = (data[1] & 0xF0000000) >> 28;
+        let start = data[0] & 0x0FFFFFFF;
+        let len = data[1] & 0x00FFFFFF;
         Gloss {
-            text: get_str(data[0] & 0x0FFFFFFF, data[1] & 0x0FFFFFFF),
+            text: get_str(start, start + len),
             language: jmdict_enums::EnumPayload::from_u32(lang_code),
+            gender: jmdict_enums::EnumPayload::from_u32(gender_code),
             gloss_type: jmdict_enums::EnumPayload::from_u32(type_code),
         }
     }
 }
 
+impl FromPayload<7> for Example {
+    fn get(data: &[u32; 7]) -> Self {
+        Example {
+            source_id: data[0],
+            text: get_str(data[1], data[2]),
+            sentence: get_str(data[3], data[4]),
+            translations_iter: Range::new(data[5], data[6]).into(),
+        }
+    }
+}
+
+impl FromPayload<2> for ExampleTranslation {
+    fn get(data: &[u32; 2]) -> Self {
+        let lang_code = (data[0] & 0xF0000000) >> 28;
+        ExampleTranslation {
+            text: get_str(data[0] & 0x0FFFFFFF, data[1]),
+            language: jmdict_enums::EnumPayload::from_u32(lang_code),
+        }
+    }
+}
+
+impl FromPayload<2> for CrossReference {
+    fn get(data: &[u32; 2]) -> Self {
+        CrossReference::parse(get_str(data[0], data[1]))
+    }
+}
+
 impl FromPayload<2> for &'static str {
     fn get(data: &[u32; 2]) -> Self {
         get_str(data[0], data[1])
     }
 }
 
+////////////////////////////////////////////////////////////////////////////////
+// indexes
+
+///Each index is a table of fixed-size records, sorted by some key. This function performs a binary
+///search over the records of such a table, with `cmp` comparing each record against the wanted key.
+fn find_record(
+    table: &'static [u32],
+    record_size: usize,
+    cmp: impl Fn(&[u32]) -> std::cmp::Ordering,
+) -> Option<&'static [u32]> {
+    let (mut lower, mut upper) = (0, table.len() / record_size);
+    while lower < upper {
+        let mid = lower + (upper - lower) / 2;
+        let record = &table[(mid * record_size)..((mid + 1) * record_size)];
+        match cmp(record) {
+            std::cmp::Ordering::Less => lower = mid + 1,
+            std::cmp::Ordering::Greater => upper = mid,
+            std::cmp::Ordering::Equal => return Some(record),
+        }
+    }
+    None
+}
+
+///Like find_record(), but returns the index of the first record that does not compare as `Less`.
+fn lower_bound(
+    table: &'static [u32],
+    record_size: usize,
+    cmp: impl Fn(&[u32]) -> std::cmp::Ordering,
+) -> usize {
+    let (mut lower, mut upper) = (0, table.len() / record_size);
+    while lower < upper {
+        let mid = lower + (upper - lower) / 2;
+        let record = &table[(mid * record_size)..((mid + 1) * record_size)];
+        if cmp(record) == std::cmp::Ordering::Less {
+            lower = mid + 1;
+        } else {
+            upper = mid;
+        }
+    }
+    lower
+}
+
+pub(crate) fn entries_containing_kanji(kanji: char) -> Range<Entry, 1> {
+    //records in the kanji index are `[kanji, start, end]`
+    let table = as_u32_slice(&KANJI_INDEX);
+    match find_record(table, 3, |r| r[0].cmp(&(kanji as u32))) {
+        Some(record) => Range::new(record[1], record[2]),
+        None => Range::new(0, 0),
+    }
+}
+
+pub(crate) fn entries_with_headword(text: &str) -> Range<Entry, 1> {
+    find_in_text_index(as_u32_slice(&HEADWORD_INDEX), text)
+}
+
+///Like `entries_with_headword()`, but returns the indexes of the entries (in ascending order).
+pub(crate) fn entry_indexes_with_headword(text: &str) -> &'static [u32] {
+    let range = entries_with_headword(text);
+    &as_u32_slice(&ALL_DATA)[range.start..range.end]
+}
+
+pub(crate) fn entries_with_loanword_source(language: SourceLanguage) -> Range<Entry, 1> {
+    find_in_text_index(as_u32_slice(&LOANWORD_INDEX), language.code())
+}
+
+pub(crate) fn entries_with_stem(stem: &str) -> Range<Entry, 1> {
+    find_in_text_index(as_u32_slice(&STEM_INDEX), stem)
+}
+
+///The `tag` must be one of those returned by `indexed_tags()` in build.rs.
+pub(crate) fn entries_with_tag(tag: &str) -> Range<Entry, 1> {
+    find_in_text_index(as_u32_slice(&TAG_INDEX), tag)
+}
+
+///Returns the indexes of all entries that have a kanji element or reading element containing the
+///given pair of consecutive characters (in ascending order).
+#[cfg(feature = "bigram-index")]
+pub(crate) fn entry_indexes_with_bigram(c1: char, c2: char) -> &'static [u32] {
+    //records in the bigram index are `[first_char, second_char, start, end]`
+    let table = as_u32_slice(&BIGRAM_INDEX);
+    let key = [c1 as u32, c2 as u32];
+    match find_record(table, 4, |r| r[0..2].cmp(&key)) {
+        Some(record) => &as_u32_slice(&ALL_DATA)[(record[2] as usize)..(record[3] as usize)],
+        None => &[],
+    }
+}
+
+///Returns all records of the radical index as `(radical, stroke_count, kanji)`, in the order of
+///the RADKFILE. The kanji are given as codepoints in ascending order.
+#[cfg(feature = "radicals")]
+pub(crate) fn all_radicals() -> impl Iterator<Item = (char, u8, &'static [u32])> {
+    //records in the radical index are `[radical, stroke_count, start, end]`
+    let data = as_u32_slice(&ALL_DATA);
+    as_u32_slice(&RADICAL_INDEX).chunks_exact(4).map(move |r| {
+        let radical = char::from_u32(r[0]).unwrap();
+        (radical, r[1] as u8, &data[(r[2] as usize)..(r[3] as usize)])
+    })
+}
+
+///Returns the references to the path data of all strokes of the given character (as
+///`[text_start, text_end]` for each stroke), or None if the character is not in the stroke index.
+#[cfg(feature = "stroke-order")]
+pub(crate) fn stroke_refs(c: char) -> Option<&'static [u32]> {
+    //records in the stroke index are `[char, start, end]`
+    let table = as_u32_slice(&STROKE_INDEX);
+    let record = find_record(table, 3, |r| r[0].cmp(&(c as u32)))?;
+    Some(&as_u32_slice(&ALL_DATA)[(record[1] as usize)..(record[2] as usize)])
+}
+
+///Returns the path data of a stroke, as referenced by `stroke_refs()`.
+#[cfg(feature = "stroke-order")]
+pub(crate) fn stroke_path(r: &[u32]) -> &'static str {
+    get_str(r[0], r[1])
+}
+
+///Returns all records of the kanji index (in order of the kanji's codepoints).
+pub(crate) fn all_kanji() -> KanjiIndexRecords {
+    KanjiIndexRecords(as_u32_slice(&KANJI_INDEX).chunks_exact(3))
+}
+
+///An iterator over all records in the kanji index.
+#[derive(Clone, Debug)]
+pub(crate) struct KanjiIndexRecords(std::slice::ChunksExact<'static, u32>);
+
+impl std::iter::Iterator for KanjiIndexRecords {
+    type Item = (char, Range<Entry, 1>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let r = self.0.next()?;
+        let kanji = char::from_u32(r[0]).unwrap();
+        Some((kanji, Range::new(r[1], r[2])))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl std::iter::ExactSizeIterator for KanjiIndexRecords {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+///Returns all records of the reading index (in lexicographic order of the reading texts).
+pub(crate) fn all_readings() -> TextIndexRecords {
+    TextIndexRecords(as_u32_slice(&READING_INDEX).chunks_exact(4))
+}
+
+///Returns all records of the loanword index (in lexicographic order of the language codes).
+pub(crate) fn all_loanword_languages() -> TextIndexRecords {
+    TextIndexRecords(as_u32_slice(&LOANWORD_INDEX).chunks_exact(4))
+}
+
+///An iterator over all records in an index keyed by strings.
+#[derive(Clone, Debug)]
+pub(crate) struct TextIndexRecords(std::slice::ChunksExact<'static, u32>);
+
+impl std::iter::Iterator for TextIndexRecords {
+    type Item = (&'static str, Range<Entry, 1>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let r = self.0.next()?;
+        Some((get_str(r[0], r[1]), Range::new(r[2], r[3])))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl std::iter::ExactSizeIterator for TextIndexRecords {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
+
+fn find_in_text_index(table: &'static [u32], text: &str) -> Range<Entry, 1> {
+    //records in indexes keyed by strings are `[text_start, text_end, start, end]`
+    match find_record(table, 4, |r| get_str(r[0], r[1]).cmp(text)) {
+        Some(record) => Range::new(record[2], record[3]),
+        None => Range::new(0, 0),
+    }
+}
+
+///Returns the lengths (in chars) of all headwords starting with the given character.
+pub(crate) fn headword_lengths(first_char: char) -> HeadwordLengths {
+    //records in the headword length index are `[first_char, length_mask, max_length]`
+    let table = as_u32_slice(&HEADWORD_LENGTH_INDEX);
+    match find_record(table, 3, |r| r[0].cmp(&(first_char as u32))) {
+        Some(record) => HeadwordLengths {
+            mask: record[1],
+            max_length: record[2],
+        },
+        None => HeadwordLengths {
+            mask: 0,
+            max_length: 0,
+        },
+    }
+}
+
+///A set of headword lengths, as returned by headword_lengths(). See
+///`encode_headword_length_index()` in build.rs for details on the encoding.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct HeadwordLengths {
+    mask: u32,
+    max_length: u32,
+}
+
+impl HeadwordLengths {
+    ///Returns the length of the longest headword.
+    pub(crate) fn max(&self) -> usize {
+        self.max_length as usize
+    }
+
+    ///Returns whether there may be a headword of this length. If this returns false, there
+    ///definitely is none.
+    pub(crate) fn contains(&self, len: usize) -> bool {
+        if len == 0 || len > self.max() {
+            return false;
+        }
+        let bit = len.min(32) - 1;
+        self.mask & (1 << bit) != 0
+    }
+}
+
+///Returns all headwords starting with the given prefix (in lexicographic order), along with the
+///entries using them.
+pub(crate) fn headwords_with_prefix(
+    prefix: &str,
+) -> impl Iterator<Item = (&'static str, Range<Entry, 1>)> + '_ {
+    let table = as_u32_slice(&HEADWORD_INDEX);
+    let idx = lower_bound(table, 4, |r| get_str(r[0], r[1]).cmp(prefix));
+    table[(idx * 4)..]
+        .chunks_exact(4)
+        .map(|r| (get_str(r[0], r[1]), Range::new(r[2], r[3])))
+        .take_while(move |(text, _)| text.starts_with(prefix))
+}
+
+////////////////////////////////////////////////////////////////////////////////
+// strings
+
+///Decodes a value of one of the enums that correspond to JMdict entities. Values of the `Unknown`
+///variant refer to the table of unknown codes (see `intern_unknown_code()` in build.rs).
+fn get_entity_enum<E: jmdict_enums::EnumPayload>(code: u32) -> E {
+    if code & 0x80000000 == 0 {
+        return E::from_u32(code);
+    }
+    let idx: usize = (code & 0x7FFFFFFF).try_into().unwrap();
+    let r = &as_u32_slice(&UNKNOWN_CODES)[(idx * 2)..(idx * 2 + 2)];
+    E::from_unknown_code(get_str(r[0], r[1]))
+}
+
 fn get_str(start: u32, end: u32) -> &'static str {
     let start = start.try_into().unwrap();
     let end = end.try_into().unwrap();
-    &ALL_TEXTS[start..end]
+    &all_texts()[start..end]
 }
 
 ////////////////////////////////////////////////////////////////////////////////
 // embedded data
 
 //NOTE: We would only need 4-byte alignment, but 16-byte is the smallest alignment interval that
-//the align_data crate offers.
+//the align_data crate offers. The alignment is required for viewing the bytes as a u32 slice
+//without copying: `bytemuck::cast_slice()` panics if the input is not sufficiently aligned.
+//
+//NOTE 2: as_u32_slice() cannot be made const because cast_slice() is not const, so we have to use
+//it on every read access to the respective arrays.
 //
-//NOTE 2: as_u32_slice() cannot be made const because from_raw_parts() is not const, so we have to
-//use it on every read access to the respective arrays.
+//NOTE 3: The build script writes all u32 arrays in little-endian byte order, regardless of the
+//byte order of the build host, so that cross-compilation works. On little-endian targets, the
+//embedded data can be used as-is. On big-endian targets, each array is converted once on first
+//access. The same happens on all targets with the `compact-payload` feature, where the arrays are
+//stored as varints, and with the `compressed-payload` feature, where all files are compressed.
 
 use align_data::{include_aligned, Align16};
 
-fn as_u32_slice(input: &'static [u8]) -> &'static [u32] {
-    unsafe {
-        let ptr = input.as_ptr() as *const u32;
-        std::slice::from_raw_parts(ptr, input.len() / 4)
+///An array of u32 embedded into the binary, as written by `write_u32s()` in build.rs.
+struct Payload {
+    bytes: &'static [u8],
+    #[cfg(any(
+        target_endian = "big",
+        feature = "compact-payload",
+        feature = "compressed-payload"
+    ))]
+    decoded: std::sync::OnceLock<Vec<u32>>,
+}
+
+impl Payload {
+    const fn new(bytes: &'static [u8]) -> Self {
+        Self {
+            bytes,
+            #[cfg(any(
+                target_endian = "big",
+                feature = "compact-payload",
+                feature = "compressed-payload"
+            ))]
+            decoded: std::sync::OnceLock::new(),
+        }
+    }
+}
+
+#[cfg(all(
+    target_endian = "little",
+    not(feature = "compact-payload"),
+    not(feature = "compressed-payload")
+))]
+fn as_u32_slice(input: &'static Payload) -> &'static [u32] {
+    bytemuck::cast_slice(input.bytes)
+}
+
+#[cfg(any(
+    target_endian = "big",
+    feature = "compact-payload",
+    feature = "compressed-payload"
+))]
+fn as_u32_slice(input: &'static Payload) -> &'static [u32] {
+    input.decoded.get_or_init(|| {
+        #[cfg(feature = "compressed-payload")]
+        let bytes = &decompress(input.bytes);
+        #[cfg(not(feature = "compressed-payload"))]
+        let bytes = input.bytes;
+        decode_u32s(bytes)
+    })
+}
+
+#[cfg(all(
+    not(feature = "compact-payload"),
+    any(target_endian = "big", feature = "compressed-payload")
+))]
+fn decode_u32s(bytes: &[u8]) -> Vec<u32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes(chunk.try_into().unwrap()))
+        .collect()
+}
+
+///Inverse of the varint encoding in `write_u32s()` in build.rs.
+#[cfg(feature = "compact-payload")]
+fn decode_u32s(bytes: &[u8]) -> Vec<u32> {
+    let mut iter = bytes.iter();
+    let mut read_varint = || {
+        let (mut val, mut shift) = (0u32, 0);
+        loop {
+            let byte = *iter.next()?;
+            val |= u32::from(byte & 0x7F) << shift;
+            if byte & 0x80 == 0 {
+                return Some(val);
+            }
+            shift += 7;
+        }
+    };
+
+    let stride = read_varint().unwrap() as usize;
+    let mut result: Vec<u32> = Vec::new();
+    while let Some(val) = read_varint() {
+        let val = if stride > 0 {
+            let base = if result.len() >= stride {
+                result[result.len() - stride]
+            } else {
+                0
+            };
+            let delta = (val >> 1) ^ 0u32.wrapping_sub(val & 1);
+            base.wrapping_add(delta)
+        } else {
+            val
+        };
+        result.push(val);
     }
+    result
+}
+
+///Inverse of `write_data_file()` in build.rs.
+#[cfg(feature = "compressed-payload")]
+fn decompress(bytes: &[u8]) -> Vec<u8> {
+    use std::io::Read;
+    let mut decoder = ruzstd::decoding::StreamingDecoder::new(bytes).unwrap();
+    let mut result = Vec::new();
+    decoder.read_to_end(&mut result).unwrap();
+    result
+}
+
+#[cfg(not(feature = "compressed-payload"))]
+pub(crate) fn all_texts() -> &'static str {
+    include_str!(concat!(env!("OUT_DIR"), "/strings.txt"))
+}
+
+#[cfg(feature = "compressed-payload")]
+pub(crate) fn all_texts() -> &'static str {
+    static DECODED: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    DECODED.get_or_init(|| {
+        let bytes = include_bytes!(concat!(env!("OUT_DIR"), "/strings.txt"));
+        String::from_utf8(decompress(bytes)).unwrap()
+    })
+}
+
+///Returns the name and decoded contents of each u32 array in the embedded data, for
+///[write_payload()]. The names are those of the respective files written by build.rs.
+pub(crate) fn u32_sections() -> Vec<(&'static str, &'static [u32])> {
+    #[allow(unused_mut)]
+    let mut sections = vec![
+        ("entry_offsets", as_u32_slice(&ALL_ENTRY_OFFSETS)),
+        ("payload", as_u32_slice(&ALL_DATA)),
+        ("kanji_index", as_u32_slice(&KANJI_INDEX)),
+        ("headword_index", as_u32_slice(&HEADWORD_INDEX)),
+        (
+            "headword_length_index",
+            as_u32_slice(&HEADWORD_LENGTH_INDEX),
+        ),
+        ("reading_index", as_u32_slice(&READING_INDEX)),
+        ("loanword_index", as_u32_slice(&LOANWORD_INDEX)),
+        ("stem_index", as_u32_slice(&STEM_INDEX)),
+        ("tag_index", as_u32_slice(&TAG_INDEX)),
+        ("unknown_codes", as_u32_slice(&UNKNOWN_CODES)),
+    ];
+    #[cfg(feature = "bigram-index")]
+    sections.push(("bigram_index", as_u32_slice(&BIGRAM_INDEX)));
+    #[cfg(feature = "radicals")]
+    sections.push(("radical_index", as_u32_slice(&RADICAL_INDEX)));
+    #[cfg(feature = "stroke-order")]
+    sections.push(("stroke_index", as_u32_slice(&STROKE_INDEX)));
+    sections
 }
 
-static ALL_ENTRY_OFFSETS: &[u8] =
-    include_aligned!(Align16, concat!(env!("OUT_DIR"), "/entry_offsets.dat"));
-static ALL_DATA: &[u8] = include_aligned!(Align16, concat!(env!("OUT_DIR"), "/payload.dat"));
-static ALL_TEXTS: &str = include_str!(concat!(env!("OUT_DIR"), "/strings.txt"));
+static ALL_ENTRY_OFFSETS: Payload = Payload::new(include_aligned!(
+    Align16,
+    concat!(env!("OUT_DIR"), "/entry_offsets.dat")
+));
+static ALL_DATA: Payload = Payload::new(include_aligned!(
+    Align16,
+    concat!(env!("OUT_DIR"), "/payload.dat")
+));
+static KANJI_INDEX: Payload = Payload::new(include_aligned!(
+    Align16,
+    concat!(env!("OUT_DIR"), "/kanji_index.dat")
+));
+static HEADWORD_INDEX: Payload = Payload::new(include_aligned!(
+    Align16,
+    concat!(env!("OUT_DIR"), "/headword_index.dat")
+));
+static HEADWORD_LENGTH_INDEX: Payload = Payload::new(include_aligned!(
+    Align16,
+    concat!(env!("OUT_DIR"), "/headword_length_index.dat")
+));
+static READING_INDEX: Payload = Payload::new(include_aligned!(
+    Align16,
+    concat!(env!("OUT_DIR"), "/reading_index.dat")
+));
+static LOANWORD_INDEX: Payload = Payload::new(include_aligned!(
+    Align16,
+    concat!(env!("OUT_DIR"), "/loanword_index.dat")
+));
+static STEM_INDEX: Payload = Payload::new(include_aligned!(
+    Align16,
+    concat!(env!("OUT_DIR"), "/stem_index.dat")
+));
+static TAG_INDEX: Payload = Payload::new(include_aligned!(
+    Align16,
+    concat!(env!("OUT_DIR"), "/tag_index.dat")
+));
+#[cfg(feature = "bigram-index")]
+static BIGRAM_INDEX: Payload = Payload::new(include_aligned!(
+    Align16,
+    concat!(env!("OUT_DIR"), "/bigram_index.dat")
+));
+#[cfg(feature = "radicals")]
+static RADICAL_INDEX: Payload = Payload::new(include_aligned!(
+    Align16,
+    concat!(env!("OUT_DIR"), "/radical_index.dat")
+));
+#[cfg(feature = "stroke-order")]
+static STROKE_INDEX: Payload = Payload::new(include_aligned!(
+    Align16,
+    concat!(env!("OUT_DIR"), "/stroke_index.dat")
+));
+static UNKNOWN_CODES: Payload = Payload::new(include_aligned!(
+    Align16,
+    concat!(env!("OUT_DIR"), "/unknown_codes.dat")
+));
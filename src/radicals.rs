@@ -0,0 +1,90 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains [kanji_by_components()] and related functions, which are backed by the
+//! RADKFILE that was supplied at build time.
+
+use crate::*;
+
+///A radical (or, more generally, a visual component of kanji) from the RADKFILE that was supplied
+///at build time. See [radicals()].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Radical {
+    ///The radical itself.
+    pub text: char,
+    ///The number of strokes in this radical.
+    pub stroke_count: u8,
+    kanji: &'static [u32],
+}
+
+impl Radical {
+    ///Returns all kanji that contain this radical, in order of their Unicode codepoints.
+    pub fn kanji(&self) -> impl Iterator<Item = char> {
+        self.kanji.iter().map(|&c| char::from_u32(c).unwrap())
+    }
+}
+
+///Returns all radicals in the order in which they appear in the RADKFILE, i.e. ordered by stroke
+///count. This is the usual order for showing them in a multi-radical lookup.
+///
+///The JMdict does not contain this information, so this is only available if a RADKFILE was
+///supplied at build time via the `RUST_JMDICT_RADKFILE` environment variable (see README).
+///Otherwise, this iterator is empty.
+pub fn radicals() -> impl Iterator<Item = Radical> {
+    payload::all_radicals().map(|(text, stroke_count, kanji)| Radical {
+        text,
+        stroke_count,
+        kanji,
+    })
+}
+
+///Returns all kanji that contain all of the given components, in order of their Unicode
+///codepoints. Returns an empty list if no components are given, or if any of them is not one of
+///the [radicals()].
+///
+///The kanji can then be used to find vocabulary with [entries_containing_kanji()]:
+///
+///```
+///for kanji in jmdict::kanji_by_components(&['日', '生']) {
+///    for entry in jmdict::entries_containing_kanji(kanji) {
+///        assert!(entry.kanji_elements().any(|k| k.text.contains(kanji)));
+///    }
+///}
+///```
+///
+///Note that the RADKFILE covers all kanji in JIS X 0208 and JIS X 0212, so some of the resulting
+///kanji may not appear in any entry.
+pub fn kanji_by_components(components: &[char]) -> Vec<char> {
+    let mut lists = Vec::with_capacity(components.len());
+    for &component in components {
+        match radicals().find(|r| r.text == component) {
+            Some(radical) => lists.push(radical.kanji),
+            None => return Vec::new(),
+        }
+    }
+    //start from the shortest list to keep the intersection cheap
+    lists.sort_by_key(|list| list.len());
+    let (first, rest) = match lists.split_first() {
+        Some(split) => split,
+        None => return Vec::new(),
+    };
+    first
+        .iter()
+        .filter(|c| rest.iter().all(|list| list.binary_search(c).is_ok()))
+        .map(|&c| char::from_u32(c).unwrap())
+        .collect()
+}
+
+///Returns all radicals that the given kanji contains, in the same order as in [radicals()].
+///
+///```
+///for radical in jmdict::components_of('明') {
+///    assert!(radical.kanji().any(|k| k == '明'));
+///}
+///```
+pub fn components_of(kanji: char) -> impl Iterator<Item = Radical> {
+    radicals().filter(move |r| r.kanji.binary_search(&(kanji as u32)).is_ok())
+}
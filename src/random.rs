@@ -0,0 +1,61 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains the parts of the public API that are enabled by the `rand` feature.
+
+use crate::*;
+use rand::Rng;
+
+///Picks an entry from the database uniformly at random. Returns `None` only if the database is
+///empty (e.g. with the `db-empty` feature).
+///
+///```
+///let mut rng = rand::thread_rng();
+///let entry = jmdict::random_entry(&mut rng).unwrap();
+///println!("word of the day: {}", entry.reading_elements().next().unwrap().text);
+///```
+pub fn random_entry(rng: &mut impl Rng) -> Option<Entry> {
+    let count = entry_count();
+    if count == 0 {
+        None
+    } else {
+        Some(get_entry(rng.gen_range(0..count)))
+    }
+}
+
+///Like [random_entry()], but only picks entries where at least one kanji element or reading element
+///is common vocabulary according to [Priority::is_common()]. Returns `None` if there is no such
+///entry in the database.
+///
+///The selection is uniform among common entries, except when common entries are extremely rare in
+///the database. In that case, the selection is only approximately uniform.
+pub fn random_common_entry(rng: &mut impl Rng) -> Option<Entry> {
+    let count = entry_count();
+    if count == 0 {
+        return None;
+    }
+
+    //rejection sampling gives a uniform distribution, and common entries are frequent enough even
+    //in the `full` configuration that we usually succeed after a few tries
+    for _ in 0..64 {
+        let entry = get_entry(rng.gen_range(0..count));
+        if is_common(&entry) {
+            return Some(entry);
+        }
+    }
+
+    //to guarantee termination, fall back to scanning from a random starting point
+    let start = rng.gen_range(0..count);
+    (start..count)
+        .chain(0..start)
+        .map(get_entry)
+        .find(is_common)
+}
+
+fn is_common(entry: &Entry) -> bool {
+    entry.kanji_elements().any(|k| k.priority.is_common())
+        || entry.reading_elements().any(|r| r.priority.is_common())
+}
@@ -0,0 +1,442 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains the full-text search API.
+
+use crate::*;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+
+///Searches the texts of all kanji elements, reading elements and glosses for the given query.
+///
+///Matches in glosses are case-insensitive, so e.g. "москва" matches "Москва". When the
+///`diacritic-folding` feature is enabled, they also ignore diacritics on Latin letters, so e.g.
+///"cafe" matches "Café". Matches in kanji elements and reading elements are exact, except that
+///traditional kanji forms (kyūjitai) match their simplified forms (shinjitai), so e.g. "舊" matches
+///"旧".
+///
+///When the `stemming` feature is enabled, glosses in languages that have a [Stemmer] also match
+///if they contain the same sequence of word stems as the query, so e.g. "running" matches "to run".
+///
+///Each element that contains the query yields one [SearchHit], which describes where exactly the
+///query was found. Hits are ordered by their entry, in the same order as in [entries()]. Within
+///an entry, hits in kanji elements come first, then hits in reading elements, then hits in glosses.
+///
+///This performs a full scan of the database, so it is considerably slower than the index-backed
+///lookups like [entries_containing_kanji()].
+///
+///```
+///# #[cfg(feature = "translations-eng")] {
+///let hit = jmdict::search("mother").next().unwrap();
+///assert_eq!(&hit.text()[hit.span.clone()], "mother");
+///# }
+///```
+pub fn search(query: &str) -> SearchHits<'_> {
+    search_with_options(query, SearchOptions::default())
+}
+
+///Like [search()], but allows to customize how the query is matched.
+///
+///```
+///# #[cfg(not(feature = "db-minimal"))] {
+///use jmdict::SearchOptions;
+///let opts = SearchOptions {
+///    fuzzy_katakana: true,
+///    ..SearchOptions::default()
+///};
+///let hit = jmdict::search_with_options("コンピューター", opts)
+///    .find(|h| h.text() == "コンピュータ")
+///    .unwrap();
+///assert_eq!(&hit.text()[hit.span.clone()], "コンピュータ");
+///# }
+///```
+pub fn search_with_options(query: &str, opts: SearchOptions) -> SearchHits<'_> {
+    SearchHits {
+        matcher: Matcher::new(query, opts),
+        entries: entries(),
+        pending: VecDeque::new(),
+    }
+}
+
+///Like [search_with_options()], but searches the given entries instead of the whole database. This
+///backs the default implementation of [Dictionary::search_with_options()].
+pub(crate) fn search_entries<'a>(
+    entries: impl Iterator<Item = Entry> + 'a,
+    query: &'a str,
+    opts: SearchOptions,
+) -> impl Iterator<Item = SearchHit> + 'a {
+    let matcher = Matcher::new(query, opts);
+    entries.flat_map(move |entry| {
+        let mut hits = VecDeque::new();
+        matcher.collect_hits(entry, &mut hits);
+        hits
+    })
+}
+
+///Options for [search_with_options()].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SearchOptions {
+    ///If true, katakana in kanji elements and reading elements are matched regardless of how
+    ///long vowels and the "v" sound are spelled. For instance, "コンピューター" matches
+    ///"コンピュータ", "メール" matches "メイル", and "ヴァイオリン" matches "バイオリン".
+    pub fuzzy_katakana: bool,
+    ///Entries, elements and senses that are hidden by this filter do not produce any hits.
+    pub content_filter: ContentFilter,
+}
+
+///Identifies the element of an [Entry] in which a [SearchHit] was found. All indexes are 0-based
+///and refer to the order in which the respective iterators yield the elements.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum MatchedElement {
+    ///The hit is in the text of `entry.kanji_elements().nth(index)`.
+    KanjiElement(usize),
+    ///The hit is in the text of `entry.reading_elements().nth(index)`.
+    ReadingElement(usize),
+    ///The hit is in the text of `entry.senses().nth(sense).unwrap().glosses().nth(gloss)`.
+    Gloss { sense: usize, gloss: usize },
+}
+
+///A single result of [search()].
+#[derive(Clone, Debug)]
+pub struct SearchHit {
+    ///The entry containing the hit.
+    pub entry: Entry,
+    ///Which element of the entry contains the hit.
+    pub element: MatchedElement,
+    ///The byte range of the hit within the text of the matched element (as returned by
+    ///[text()](SearchHit::text)), e.g. for highlighting the hit in a UI.
+    pub span: std::ops::Range<usize>,
+}
+
+impl SearchHit {
+    ///Returns the full text of the matched element.
+    pub fn text(&self) -> &'static str {
+        match self.element {
+            MatchedElement::KanjiElement(idx) => self.entry.kanji_elements().nth(idx).unwrap().text,
+            MatchedElement::ReadingElement(idx) => {
+                self.entry.reading_elements().nth(idx).unwrap().text
+            }
+            MatchedElement::Gloss { sense, gloss } => {
+                let sense = self.entry.senses().nth(sense).unwrap();
+                sense.glosses().nth(gloss).unwrap().text
+            }
+        }
+    }
+}
+
+///An iterator over the result of [search()].
+#[derive(Clone)]
+pub struct SearchHits<'q> {
+    matcher: Matcher<'q>,
+    entries: Entries,
+    pending: VecDeque<SearchHit>,
+}
+
+///The normalized forms of a search query, which are matched against each entry.
+#[derive(Clone)]
+struct Matcher<'q> {
+    query: &'q str,
+    opts: SearchOptions,
+    headword_query: Cow<'q, str>,
+    folded_query: String,
+    #[cfg(feature = "stemming")]
+    stemmed_queries: Vec<(GlossLanguage, Vec<String>)>,
+}
+
+impl<'q> Matcher<'q> {
+    fn new(query: &'q str, opts: SearchOptions) -> Self {
+        let mut headword_query = variants::normalize_variants(query);
+        if opts.fuzzy_katakana {
+            headword_query = Cow::Owned(fold_katakana(&headword_query).0);
+        }
+        Self {
+            query,
+            opts,
+            headword_query,
+            folded_query: fold(query).0,
+            #[cfg(feature = "stemming")]
+            stemmed_queries: GlossLanguage::all_variants()
+                .iter()
+                .filter_map(|&lang| {
+                    let stemmer = stemming::stemmer_for(lang)?;
+                    let (folded, _) = fold(query);
+                    let stems = words(&folded).map(|w| stemmer.stem(&folded[w])).collect();
+                    Some((lang, stems))
+                })
+                .collect(),
+        }
+    }
+
+    fn collect_hits(&self, entry: Entry, pending: &mut VecDeque<SearchHit>) {
+        let filter = self.opts.content_filter;
+        if self.query.is_empty() || !filter.accepts_entry(&entry) {
+            return;
+        }
+        let headword_query = &self.headword_query;
+        let opts = self.opts;
+        let folded_query = &self.folded_query;
+        #[cfg(feature = "stemming")]
+        let stemmed_queries = &self.stemmed_queries;
+        let mut push = |element, span: Option<std::ops::Range<usize>>| {
+            if let Some(span) = span {
+                pending.push_back(SearchHit {
+                    entry,
+                    element,
+                    span,
+                });
+            }
+        };
+
+        //NOTE: Indexes in MatchedElement refer to the unfiltered iterators of the entry.
+        for (idx, k) in entry.kanji_elements().enumerate() {
+            if !filter.accepts_kanji_element(&k) {
+                continue;
+            }
+            push(
+                MatchedElement::KanjiElement(idx),
+                find_in_headword(k.text, headword_query, opts),
+            );
+        }
+        for (idx, r) in entry.reading_elements().enumerate() {
+            if !filter.accepts_reading_element(&r) {
+                continue;
+            }
+            push(
+                MatchedElement::ReadingElement(idx),
+                find_in_headword(r.text, headword_query, opts),
+            );
+        }
+        for (sense_idx, sense) in entry.senses().enumerate() {
+            if !filter.accepts_sense(&sense) {
+                continue;
+            }
+            for (gloss_idx, gloss) in sense.glosses().enumerate() {
+                let element = MatchedElement::Gloss {
+                    sense: sense_idx,
+                    gloss: gloss_idx,
+                };
+                let span = find_in_gloss(gloss.text, folded_query);
+                #[cfg(feature = "stemming")]
+                let span = span.or_else(|| {
+                    let (lang, stems) =
+                        stemmed_queries.iter().find(|(l, _)| *l == gloss.language)?;
+                    find_stems_in_gloss(gloss.text, stems, stemming::stemmer_for(*lang)?)
+                });
+                push(element, span);
+            }
+        }
+    }
+}
+
+impl<'q> std::iter::Iterator for SearchHits<'q> {
+    type Item = SearchHit;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.matcher.query.is_empty() {
+            return None;
+        }
+        loop {
+            if let Some(hit) = self.pending.pop_front() {
+                return Some(hit);
+            }
+            let entry = self.entries.next()?;
+            self.matcher.collect_hits(entry, &mut self.pending);
+        }
+    }
+}
+
+///Returns the byte range of the first occurrence of `normalized_query` in the text of a kanji
+///element or reading element, after the text has been normalized in the same way as the query.
+fn find_in_headword(
+    text: &str,
+    normalized_query: &str,
+    opts: SearchOptions,
+) -> Option<std::ops::Range<usize>> {
+    //the query can become empty through normalization (e.g. "ー" with `fuzzy_katakana`), and
+    //an empty query does not match anything
+    if normalized_query.is_empty() {
+        return None;
+    }
+    //NOTE: normalize_variants() does not change byte offsets, so its result can be used directly
+    let text = variants::normalize_variants(text);
+    if opts.fuzzy_katakana {
+        let (folded, offsets) = fold_katakana(&text);
+        let start = folded.find(normalized_query)?;
+        let end = start + normalized_query.len();
+        return Some(offsets[start].0..offsets[end - 1].1);
+    }
+    let start = text.find(normalized_query)?;
+    Some(start..(start + normalized_query.len()))
+}
+
+///Returns the byte range of the first occurrence of `folded_query` in the text of a gloss, after
+///the text has been folded in the same way as the query. The result refers to the original text.
+fn find_in_gloss(text: &str, folded_query: &str) -> Option<std::ops::Range<usize>> {
+    if folded_query.is_empty() {
+        return None;
+    }
+    let (folded, offsets) = fold(text);
+    let start = folded.find(folded_query)?;
+    let end = start + folded_query.len();
+    //`offsets` maps each byte of `folded` to the original char that it came from
+    let original_start = offsets[start].0;
+    let original_end = offsets[end - 1].1;
+    Some(original_start..original_end)
+}
+
+///Folds case and (with the `diacritic-folding` feature) removes diacritics from Latin letters, for
+///case-insensitive and diacritic-insensitive matching of glosses. Alongside the folded text,
+///returns the byte range of the original char for each byte of the folded text.
+pub(crate) fn fold(text: &str) -> (String, Vec<(usize, usize)>) {
+    let mut folded = String::with_capacity(text.len());
+    let mut offsets = Vec::with_capacity(text.len());
+    for (idx, c) in text.char_indices() {
+        let original = (idx, idx + c.len_utf8());
+        let before = folded.len();
+        if is_latin(c) {
+            fold_latin(c, &mut folded);
+        } else {
+            //NOTE: Diacritics outside of the Latin script are not removed since they usually
+            //distinguish different letters (e.g. the dakuten in "か" vs. "が", or "и" vs. "й").
+            folded.extend(c.to_lowercase());
+        }
+        offsets.resize(offsets.len() + folded.len() - before, original);
+    }
+    (folded, offsets)
+}
+
+#[cfg(feature = "diacritic-folding")]
+fn fold_latin(c: char, folded: &mut String) {
+    use unicode_normalization::char::{decompose_canonical, is_combining_mark};
+    decompose_canonical(c, |d| {
+        if !is_combining_mark(d) {
+            folded.extend(d.to_lowercase());
+        }
+    });
+}
+
+#[cfg(not(feature = "diacritic-folding"))]
+fn fold_latin(c: char, folded: &mut String) {
+    folded.extend(c.to_lowercase());
+}
+
+///Returns the byte range of the first occurrence of the stemmed query in the text of a gloss.
+#[cfg(feature = "stemming")]
+fn find_stems_in_gloss(
+    text: &str,
+    stemmed_query: &[String],
+    stemmer: &dyn Stemmer,
+) -> Option<std::ops::Range<usize>> {
+    if stemmed_query.is_empty() {
+        return None;
+    }
+    let (folded, offsets) = fold(text);
+    let words: Vec<_> = words(&folded).collect();
+    let stems: Vec<_> = words
+        .iter()
+        .map(|w| stemmer.stem(&folded[w.clone()]))
+        .collect();
+    let idx = stems
+        .windows(stemmed_query.len())
+        .position(|window| window == stemmed_query)?;
+    let first = &words[idx];
+    let last = &words[idx + stemmed_query.len() - 1];
+    Some(offsets[first.start].0..offsets[last.end - 1].1)
+}
+
+///Splits a text into words, and returns the byte range of each word.
+#[cfg(feature = "stemming")]
+fn words(text: &str) -> impl Iterator<Item = std::ops::Range<usize>> + '_ {
+    let mut start = None;
+    text.char_indices()
+        .chain(std::iter::once((text.len(), ' ')))
+        .filter_map(move |(idx, c)| match (start, c.is_alphanumeric()) {
+            (None, true) => {
+                start = Some(idx);
+                None
+            }
+            (Some(s), false) => {
+                start = None;
+                Some(s..idx)
+            }
+            _ => None,
+        })
+}
+
+///Removes spelling differences in katakana for [SearchOptions::fuzzy_katakana]: Long vowels are
+///removed (no matter if spelled with "ー" or with an explicit vowel), and "ヴ" is replaced by the
+///respective kana from the "バ" row. Like fold(), this also returns the byte range of the original
+///char(s) for each byte of the result.
+pub(crate) fn fold_katakana(text: &str) -> (String, Vec<(usize, usize)>) {
+    let mut folded = String::with_capacity(text.len());
+    let mut offsets = Vec::with_capacity(text.len());
+    let mut prev_vowel = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((idx, c)) = chars.next() {
+        let mut original = (idx, idx + c.len_utf8());
+        let replacement = match c {
+            'ヴ' => {
+                let next = chars.peek().map(|&(_, c)| c);
+                let merged = match next {
+                    Some('ァ') => Some('バ'),
+                    Some('ィ') => Some('ビ'),
+                    Some('ェ') => Some('ベ'),
+                    Some('ォ') => Some('ボ'),
+                    _ => None,
+                };
+                if merged.is_some() {
+                    let (next_idx, next_char) = chars.next().unwrap();
+                    original.1 = next_idx + next_char.len_utf8();
+                }
+                Some(merged.unwrap_or('ブ'))
+            }
+            'ー' => None,
+            'ア' | 'イ' | 'ウ' | 'エ' | 'オ' => {
+                let is_long_vowel = matches!(
+                    (prev_vowel, c),
+                    (Some('a'), 'ア')
+                        | (Some('i'), 'イ')
+                        | (Some('u'), 'ウ')
+                        | (Some('e'), 'エ')
+                        | (Some('e'), 'イ')
+                        | (Some('o'), 'オ')
+                        | (Some('o'), 'ウ')
+                );
+                if is_long_vowel {
+                    None
+                } else {
+                    Some(c)
+                }
+            }
+            _ => Some(c),
+        };
+        if let Some(r) = replacement {
+            prev_vowel = katakana_vowel(r);
+            let before = folded.len();
+            folded.push(r);
+            offsets.resize(offsets.len() + folded.len() - before, original);
+        }
+    }
+    (folded, offsets)
+}
+
+///Returns the vowel that the given katakana ends in, if any.
+fn katakana_vowel(c: char) -> Option<char> {
+    static ROWS: &[(char, &str)] = &[
+        ('a', "アァカガサザタダナハバパマヤャラワヮヵ"),
+        ('i', "イィキギシジチヂニヒビピミリヰ"),
+        ('u', "ウゥクグスズツヅヌフブプムユュル"),
+        ('e', "エェケゲセゼテデネヘベペメレヱヶ"),
+        ('o', "オォコゴソゾトドノホボポモヨョロヲ"),
+    ];
+    ROWS.iter()
+        .find(|(_, kana)| kana.contains(c))
+        .map(|&(vowel, _)| vowel)
+}
+
+fn is_latin(c: char) -> bool {
+    matches!(c as u32, 0x0041..=0x024F | 0x1E00..=0x1EFF)
+}
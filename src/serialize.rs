@@ -0,0 +1,83 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains the parts of the public API that are enabled by the `serde` feature.
+//!
+//! Types that only have public fields derive `Serialize` directly. The types in here hold some of
+//! their contents in private iterator fields, so their implementations are written out by hand to
+//! use the same names as the respective accessor methods.
+
+use crate::*;
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+
+impl Serialize for Entry {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Entry", 4)?;
+        s.serialize_field("number", &self.number)?;
+        s.serialize_field("kanji_elements", &self.kanji_elements())?;
+        s.serialize_field("reading_elements", &self.reading_elements())?;
+        s.serialize_field("senses", &self.senses())?;
+        s.end()
+    }
+}
+
+impl Serialize for KanjiElement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("KanjiElement", 3)?;
+        s.serialize_field("text", self.text)?;
+        s.serialize_field("priority", &self.priority)?;
+        s.serialize_field("infos", &self.infos())?;
+        s.end()
+    }
+}
+
+impl Serialize for ReadingElement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("ReadingElement", 5)?;
+        s.serialize_field("text", self.text)?;
+        s.serialize_field("priority", &self.priority)?;
+        s.serialize_field("no_kanji", &self.no_kanji)?;
+        s.serialize_field("infos", &self.infos())?;
+        s.serialize_field("restricted_to", &self.restricted_to())?;
+        s.end()
+    }
+}
+
+impl Serialize for Sense {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Sense", 12)?;
+        s.serialize_field(
+            "applicable_kanji_elements",
+            &self.applicable_kanji_elements(),
+        )?;
+        s.serialize_field(
+            "applicable_reading_elements",
+            &self.applicable_reading_elements(),
+        )?;
+        s.serialize_field("parts_of_speech", &self.parts_of_speech())?;
+        s.serialize_field("cross_references", &self.cross_references())?;
+        s.serialize_field("antonyms", &self.antonyms())?;
+        s.serialize_field("topics", &self.topics())?;
+        s.serialize_field("infos", &self.infos())?;
+        s.serialize_field("freetext_infos", &self.freetext_infos())?;
+        s.serialize_field("loanword_sources", &self.loanword_sources())?;
+        s.serialize_field("dialects", &self.dialects())?;
+        s.serialize_field("glosses", &self.glosses())?;
+        s.serialize_field("examples", &self.examples())?;
+        s.end()
+    }
+}
+
+impl Serialize for Example {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("Example", 4)?;
+        s.serialize_field("source_id", &self.source_id)?;
+        s.serialize_field("text", self.text)?;
+        s.serialize_field("sentence", self.sentence)?;
+        s.serialize_field("translations", &self.translations())?;
+        s.end()
+    }
+}
@@ -0,0 +1,37 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains statistics about the database contents that `build.rs` computes at compile
+//! time, so that they are available without traversing the database.
+
+use crate::*;
+
+include!(concat!(env!("OUT_DIR"), "/stats.rs"));
+
+///The number of entries in the database. This is the same as `jmdict::entries().len()`, but
+///available as a constant.
+pub const ENTRY_COUNT: usize = COUNTS[0];
+
+///The total number of senses across all entries in the database.
+pub const SENSE_COUNT: usize = COUNTS[1];
+
+///The total number of glosses across all senses in the database. See [gloss_count()] for
+///counting glosses in a specific language.
+pub const GLOSS_COUNT: usize = COUNTS[2];
+
+///Returns the total number of glosses in the given language across all senses in the database.
+///This is backed by a table that is computed at compile time.
+///
+///```
+///use jmdict::GlossLanguage;
+///assert!(jmdict::gloss_count(GlossLanguage::English) <= jmdict::GLOSS_COUNT);
+///```
+pub fn gloss_count(language: GlossLanguage) -> usize {
+    GLOSS_COUNTS_BY_LANGUAGE
+        .iter()
+        .find(|(code, _)| *code == language.code())
+        .map_or(0, |(_, count)| *count)
+}
@@ -0,0 +1,93 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains the stemmers that [search()] uses for matching morphological variants in
+//! glosses. It is only compiled when the `stemming` feature is enabled.
+
+use crate::*;
+
+///A stemmer reduces words to a common stem, so that e.g. "running" and "runs" both match "run".
+///
+///The stem does not need to be an actual word. It only needs to be the same for all variants of a
+///word, and different for unrelated words as far as possible. Stemmers only ever see words that
+///have already been lowercased.
+pub trait Stemmer: Sync {
+    ///Reduces the given word to its stem.
+    fn stem(&self, word: &str) -> String;
+}
+
+///Returns the stemmer for glosses in the given language, if there is one.
+pub fn stemmer_for(language: GlossLanguage) -> Option<&'static dyn Stemmer> {
+    match language {
+        #[cfg(feature = "translations-eng")]
+        GlossLanguage::English => Some(&EnglishStemmer),
+        #[allow(unreachable_patterns)]
+        _ => None,
+    }
+}
+
+///A light stemmer for English that only removes the most common inflectional suffixes ("-s",
+///"-es", "-ies", "-ed", "-ing"). Irregular forms like "ran" or "mice" are not recognized.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct EnglishStemmer;
+
+impl Stemmer for EnglishStemmer {
+    fn stem(&self, word: &str) -> String {
+        //only ASCII words are stemmed, everything else is most likely not an English word
+        if word.len() <= 3 || !word.bytes().all(|b| b.is_ascii_lowercase()) {
+            return word.into();
+        }
+        let mut stem = word;
+
+        //plural, third person singular, and past tense of verbs ending in "-y"
+        for suffix in &["ies", "ied"] {
+            if let Some(s) = stem.strip_suffix(suffix) {
+                if s.len() >= 2 {
+                    return format!("{}y", s);
+                }
+            }
+        }
+        if stem.ends_with("sses") || ["xes", "ches", "shes"].iter().any(|s| stem.ends_with(s)) {
+            stem = &stem[0..(stem.len() - 2)];
+        } else if stem.ends_with('s')
+            && !stem.ends_with("ss")
+            && !stem.ends_with("us")
+            && !stem.ends_with("is")
+        {
+            stem = &stem[0..(stem.len() - 1)];
+        }
+
+        //past tense and progressive
+        let mut stripped = false;
+        for suffix in &["ing", "ed"] {
+            if let Some(s) = stem.strip_suffix(suffix) {
+                if s.len() >= 3 && s.bytes().any(is_vowel) {
+                    stem = s;
+                    stripped = true;
+                    break;
+                }
+            }
+        }
+        if stripped {
+            //"running" -> "runn" -> "run", but "falling" -> "fall"
+            let bytes = stem.as_bytes();
+            let last = bytes[bytes.len() - 1];
+            if last == bytes[bytes.len() - 2] && !is_vowel(last) && !b"lsz".contains(&last) {
+                stem = &stem[0..(stem.len() - 1)];
+            }
+        }
+
+        //"make" and "making" both become "mak"
+        if stem.len() > 3 {
+            stem = stem.strip_suffix('e').unwrap_or(stem);
+        }
+        stem.into()
+    }
+}
+
+fn is_vowel(b: u8) -> bool {
+    b"aeiouy".contains(&b)
+}
@@ -0,0 +1,56 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains [stroke_order()], which is backed by the KanjiVG file that was supplied at
+//! build time.
+
+use crate::*;
+
+///Returns the strokes of the given kanji (or kana) in stroke order, or None if the character is
+///not known.
+///
+///Each stroke is given as SVG path data, i.e. as the value of the `d` attribute of a `<path>`
+///element. The coordinates refer to a canvas of 109x109 units, as used throughout KanjiVG. For
+///example, the strokes can be rendered like this:
+///
+///```
+///if let Some(strokes) = jmdict::stroke_order('日') {
+///    let mut svg = String::from(r#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 109 109">"#);
+///    for path in strokes {
+///        svg.push_str(&format!(r#"<path d="{}" fill="none" stroke="black"/>"#, path));
+///    }
+///    svg.push_str("</svg>");
+///}
+///```
+///
+///The JMdict does not contain this information, so this is only available if a KanjiVG file was
+///supplied at build time via the `RUST_JMDICT_KANJIVG` environment variable (see README).
+///Otherwise, this always returns None.
+pub fn stroke_order(kanji: char) -> Option<Strokes> {
+    payload::stroke_refs(kanji).map(|refs| Strokes(refs.chunks_exact(2)))
+}
+
+///An iterator over the result of [stroke_order()].
+#[derive(Clone, Debug)]
+pub struct Strokes(std::slice::ChunksExact<'static, u32>);
+
+impl std::iter::Iterator for Strokes {
+    type Item = &'static str;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(payload::stroke_path)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl std::iter::ExactSizeIterator for Strokes {
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+}
@@ -0,0 +1,69 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use crate::Enum;
+use jmdict_traverse::{BuildFilter, Options, RawEntry, Visitor};
+
+///For each visited entry, collects the sequence number, the numbers of all senses, and whether any
+///of the senses is marked as a godan verb.
+#[derive(Default)]
+struct Collector(Vec<(u32, Vec<u32>, bool)>);
+
+impl Visitor for Collector {
+    fn process_entry(&mut self, entry: &RawEntry) {
+        let numbers = entry.sense.iter().map(|s| s.number).collect();
+        let is_verb = (entry.sense.iter())
+            .flat_map(|s| s.pos.iter())
+            .any(|p| p.code().starts_with("v5"));
+        self.0.push((entry.ent_seq, numbers, is_verb));
+    }
+}
+
+fn traverse(filter: Option<&str>) -> Vec<(u32, Vec<u32>, bool)> {
+    let mut opts = Options::default();
+    opts.is_db_minimal = true;
+    opts.filter = filter.map(|f| BuildFilter::parse(f).unwrap());
+    let mut c = Collector::default();
+    jmdict_traverse::process_dictionary(&mut c, opts);
+    c.0
+}
+
+#[test]
+fn test_build_filter_required_tags() {
+    let all = traverse(None);
+    let verbs = traverse(Some(r#"{"required_tags": ["v5*"]}"#));
+
+    //required tags select whole entries, so this also works in builds without English glosses,
+    //where none of the senses carry tags
+    assert!(!verbs.is_empty());
+    assert!(verbs.len() < all.len());
+    if cfg!(feature = "translations-eng") {
+        let expected: Vec<_> = all.iter().filter(|e| e.2).cloned().collect();
+        assert_eq!(verbs, expected);
+    } else {
+        for entry in &verbs {
+            assert!(all.contains(entry), "entry {} was changed", entry.0);
+        }
+    }
+
+    assert!(BuildFilter::parse(r#"{"required_tags": ["foo"]}"#).is_err());
+    assert!(BuildFilter::parse(r#"{"min_priority": "foo"}"#).is_err());
+    assert!(BuildFilter::parse(r#"{"unknown_field": 42}"#).is_err());
+}
+
+#[test]
+fn test_build_filter_excluded_tags() {
+    let all = traverse(None);
+    let non_verbs = traverse(Some(r#"{"excluded_tags": ["v5*"]}"#));
+
+    //excluded tags still apply to individual senses
+    assert!(non_verbs.iter().all(|e| !e.2));
+    for entry in &all {
+        if !entry.2 {
+            assert!(non_verbs.contains(entry), "entry {} was changed", entry.0);
+        }
+    }
+}
@@ -11,7 +11,7 @@ fn check_consistency() {
     //This test runs through the data files in the repository a second time and checks that
     //entries() contains exactly what we want. This test especially verifies that all indexes into
     //omniarrays are within bounds and point to the right stuff.
-    struct Visitor(crate::Entries);
+    struct Visitor(std::vec::IntoIter<crate::Entry>);
 
     impl jmdict_traverse::Visitor for Visitor {
         fn process_entry(&mut self, entry: &jmdict_traverse::RawEntry) {
@@ -22,13 +22,24 @@ fn check_consistency() {
         }
     }
 
-    let opts = jmdict_traverse::Options {
-        is_db_minimal: cfg!(feature = "db-minimal"),
-        with_uncommon: cfg!(feature = "scope-uncommon"),
-        with_archaic: cfg!(feature = "scope-archaic"),
-    };
+    let mut opts = jmdict_traverse::Options::default();
+    opts.is_db_minimal = cfg!(feature = "db-minimal");
+    opts.with_uncommon = cfg!(feature = "scope-uncommon");
+    opts.with_archaic = cfg!(feature = "scope-archaic");
+    opts.with_names = cfg!(not(feature = "exclude-names"));
+    opts.with_sensitive = cfg!(not(feature = "exclude-sensitive"));
+    opts.topics = jmdict_traverse::enum_list_from_env("RUST_JMDICT_TOPICS");
+    opts.parts_of_speech = jmdict_traverse::enum_list_from_env("RUST_JMDICT_PARTS_OF_SPEECH");
+    opts.filter = jmdict_traverse::BuildFilter::from_env();
+    opts.jlpt = jmdict_traverse::JlptList::from_env();
+    opts.is_tolerant = cfg!(feature = "tolerant-parsing");
 
-    let mut v = Visitor(crate::entries());
+    //the traversal visits entries by sequence number, so the same order is needed here
+    let mut entries: Vec<_> = crate::entries().collect();
+    if cfg!(feature = "order-by-frequency") {
+        entries.sort_by_key(|entry| entry.number);
+    }
+    let mut v = Visitor(entries.into_iter());
     jmdict_traverse::process_dictionary(&mut v, opts);
     assert!(v.0.next().is_none(), "not all entries were exhausted");
 }
@@ -37,10 +48,7 @@ trait Check<A> {
     fn check(&self, actual: &A);
 }
 
-fn check_vec<A, E: Check<A>>(
-    expected: &Vec<E>,
-    actual: impl Iterator<Item = A> + ExactSizeIterator,
-) {
+fn check_vec<A, E: Check<A>>(expected: &[E], actual: impl ExactSizeIterator<Item = A>) {
     assert_eq!(expected.len(), actual.len());
     for (expected, actual) in expected.iter().zip(actual) {
         expected.check(&actual);
@@ -56,6 +64,8 @@ impl<E: Debug + PartialEq<A>, A: Debug + PartialEq<E>> Check<A> for E {
 impl Check<crate::Entry> for jmdict_traverse::RawEntry<'_> {
     fn check(&self, actual: &crate::Entry) {
         let expected = self;
+        assert_eq!(expected.ent_seq, actual.number);
+        assert_eq!(expected.jlpt_level, actual.jlpt_level());
         check_vec(&expected.k_ele, actual.kanji_elements());
         check_vec(&expected.r_ele, actual.reading_elements());
         check_vec(&expected.sense, actual.senses());
@@ -66,6 +76,9 @@ impl Check<crate::KanjiElement> for jmdict_traverse::RawKanjiElement<'_> {
     fn check(&self, actual: &crate::KanjiElement) {
         let expected = self;
         assert_eq!(expected.keb, actual.text);
+        if cfg!(not(feature = "exclude-priority")) {
+            assert_eq!(expected.ke_pri, actual.priority);
+        }
         check_vec(&expected.ke_inf, actual.infos());
     }
 }
@@ -74,7 +87,12 @@ impl Check<crate::ReadingElement> for jmdict_traverse::RawReadingElement<'_> {
     fn check(&self, actual: &crate::ReadingElement) {
         let expected = self;
         assert_eq!(expected.reb, actual.text);
+        if cfg!(not(feature = "exclude-priority")) {
+            assert_eq!(expected.re_pri, actual.priority);
+        }
+        assert_eq!(expected.re_nokanji, actual.no_kanji);
         check_vec(&expected.re_inf, actual.infos());
+        check_vec(&expected.re_restr, actual.restricted_to());
     }
 }
 
@@ -84,14 +102,16 @@ impl Check<crate::Sense> for jmdict_traverse::RawSense<'_> {
         check_vec(&expected.stagk, actual.applicable_kanji_elements());
         check_vec(&expected.stagr, actual.applicable_reading_elements());
         check_vec(&expected.pos, actual.parts_of_speech());
-        check_vec(&expected.xref, actual.cross_references());
-        check_vec(&expected.ant, actual.antonyms());
+        assert_eq!(expected.number, actual.number);
+        check_vec(&expected.xref, actual.cross_references().map(|x| x.text));
+        check_vec(&expected.ant, actual.antonyms().map(|x| x.text));
         check_vec(&expected.field, actual.topics());
         check_vec(&expected.misc, actual.infos());
         check_vec(&expected.s_inf, actual.freetext_infos());
         check_vec(&expected.lsource, actual.loanword_sources());
         check_vec(&expected.dial, actual.dialects());
         check_vec(&expected.gloss, actual.glosses());
+        check_vec(&expected.example, actual.examples());
     }
 }
 
@@ -105,11 +125,30 @@ impl Check<crate::LoanwordSource> for jmdict_traverse::RawLSource<'_> {
     }
 }
 
+impl Check<crate::Example> for jmdict_traverse::RawExample<'_> {
+    fn check(&self, actual: &crate::Example) {
+        let expected = self;
+        assert_eq!(expected.source_id, actual.source_id);
+        assert_eq!(expected.text, actual.text);
+        assert_eq!(expected.sentence, actual.sentence);
+        check_vec(&expected.translations, actual.translations());
+    }
+}
+
+impl Check<crate::ExampleTranslation> for jmdict_traverse::RawExampleTranslation<'_> {
+    fn check(&self, actual: &crate::ExampleTranslation) {
+        let expected = self;
+        assert_eq!(expected.lang, actual.language);
+        assert_eq!(expected.text, actual.text);
+    }
+}
+
 impl Check<crate::Gloss> for jmdict_traverse::RawGloss<'_> {
     fn check(&self, actual: &crate::Gloss) {
         let expected = self;
         assert_eq!(expected.lang, actual.language);
         assert_eq!(expected.text, actual.text);
+        assert_eq!(expected.g_gend, actual.gender);
         assert_eq!(expected.g_type, actual.gloss_type);
     }
 }
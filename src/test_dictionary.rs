@@ -0,0 +1,110 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use crate::*;
+
+///Like EmbeddedDictionary, but uses the default implementations of all lookups.
+struct ScanningDictionary;
+
+impl Dictionary for ScanningDictionary {
+    fn entries(&self) -> Box<dyn Iterator<Item = Entry> + '_> {
+        Box::new(entries())
+    }
+}
+
+fn numbers<'a>(iter: Box<dyn Iterator<Item = Entry> + 'a>) -> Vec<u32> {
+    iter.map(|e| e.number).collect()
+}
+
+#[test]
+fn test_embedded_matches_default_lookups() {
+    let (embedded, scanning) = (EmbeddedDictionary, ScanningDictionary);
+    for text in &["一日", "いちにち", "お母さん", "存在しない"] {
+        assert_eq!(
+            numbers(embedded.entries_with_headword(text)),
+            numbers(scanning.entries_with_headword(text)),
+            "headword was {}",
+            text
+        );
+    }
+    for kanji in &['曜', '日', '母'] {
+        assert_eq!(
+            numbers(embedded.entries_containing_kanji(*kanji)),
+            numbers(scanning.entries_containing_kanji(*kanji)),
+            "kanji was {}",
+            kanji
+        );
+    }
+    for language in &[SourceLanguage::German, SourceLanguage::French] {
+        assert_eq!(
+            numbers(embedded.entries_with_loanword_source(*language)),
+            numbers(scanning.entries_with_loanword_source(*language)),
+            "language was {:?}",
+            language
+        );
+    }
+    assert_eq!(
+        numbers(embedded.expressions()),
+        numbers(scanning.expressions())
+    );
+    assert_eq!(numbers(embedded.proverbs()), numbers(scanning.proverbs()));
+    assert_eq!(
+        numbers(embedded.yojijukugo()),
+        numbers(scanning.yojijukugo())
+    );
+}
+
+#[test]
+fn test_fixture_dictionary() {
+    let fixture: Vec<Entry> = entries().step_by(7).take(20).collect();
+    assert_eq!(numbers(fixture.entries()), numbers(fixture[..].entries()));
+
+    //lookups only find entries from the fixture
+    for entry in &fixture {
+        let text = entry.reading_elements().next().unwrap().text;
+        let found = numbers(fixture.entries_with_headword(text));
+        assert!(found.contains(&entry.number));
+        assert!(found.iter().all(|n| fixture.iter().any(|e| e.number == *n)));
+    }
+}
+
+#[test]
+fn test_merged_dictionary() {
+    let all: Vec<Entry> = entries().collect();
+    let (odd, even): (Vec<Entry>, Vec<Entry>) = all.iter().partition(|e| e.number % 2 == 1);
+    let overlap: Vec<Entry> = all.iter().step_by(3).copied().collect();
+    let merged = MergedDictionary::new(&[&odd, &overlap, &even]);
+
+    //entries are ordered by sequence number, without duplicates
+    let mut expected: Vec<u32> = all.iter().map(|e| e.number).collect();
+    expected.sort_unstable();
+    assert_eq!(numbers(merged.entries()), expected);
+
+    //lookups and searches agree with the embedded database up to the order of results
+    let sorted = |mut v: Vec<u32>| {
+        v.sort_unstable();
+        v
+    };
+    for kanji in &['曜', '日', '母'] {
+        assert_eq!(
+            numbers(merged.entries_containing_kanji(*kanji)),
+            sorted(numbers(EmbeddedDictionary.entries_containing_kanji(*kanji))),
+        );
+    }
+    let hit_numbers = |hits: Box<dyn Iterator<Item = SearchHit> + '_>| -> Vec<u32> {
+        let mut result: Vec<u32> = hits.map(|h| h.entry.number).collect();
+        result.dedup();
+        result
+    };
+    for query in &["日", "にち", "day"] {
+        assert_eq!(
+            hit_numbers(merged.search(query)),
+            sorted(hit_numbers(Box::new(search(query)))),
+            "query was {}",
+            query
+        );
+    }
+}
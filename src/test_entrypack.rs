@@ -0,0 +1,266 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+///A heavily abridged JMdict XML file, with the same structure as the real one.
+const JMDICT_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE JMdict [
+<!ELEMENT JMdict (entry*)>
+<!-- Rev 1.08
+	Added the g_type attribute to gloss.
+-->
+<!-- Rev 1.09
+	Added the example element.
+-->
+<!ENTITY n "noun (common) (futsuumeishi)">
+<!ENTITY uk "word usually written using kana alone">
+]>
+<!-- JMdict created: 2021-07-19 -->
+<JMdict>
+<entry>
+<ent_seq>1000000</ent_seq>
+<r_ele>
+<reb>ヽ</reb>
+</r_ele>
+<sense>
+<pos>&n;</pos>
+<gloss>repetition mark in katakana</gloss>
+</sense>
+</entry>
+<entry>
+<ent_seq>1000010</ent_seq>
+<r_ele>
+<reb>ヾ</reb>
+</r_ele>
+<sense>
+<misc>&uk;</misc>
+<gloss>voiced repetition mark in katakana</gloss>
+</sense>
+</entry>
+</JMdict>
+"#;
+
+///Runs `check_entrypack()` on a file with the given contents.
+fn check_contents(name: &str, contents: &str) -> Result<jmdict_traverse::HeaderObj, String> {
+    let path = temp_file(name, contents.as_bytes());
+    let result = jmdict_traverse::check_entrypack(&path);
+    std::fs::remove_file(&path).unwrap();
+    result
+}
+
+#[test]
+fn test_entrypack_header() {
+    let mut buf = Vec::new();
+    jmdict_traverse::write_entrypack(JMDICT_XML.as_bytes(), &mut buf).unwrap();
+    let contents = String::from_utf8(buf).unwrap();
+    let lines: Vec<&str> = contents.lines().collect();
+    assert_eq!(lines.len(), 3);
+    assert!(lines[1].starts_with(r#"{"n":1000000,"#));
+
+    let header = check_contents("entrypack-v2.json", &contents).unwrap();
+    assert_eq!(header.entrypack, jmdict_traverse::ENTRYPACK_VERSION);
+    assert_eq!(header.creation_date.as_deref(), Some("2021-07-19"));
+    assert_eq!(header.revision.as_deref(), Some("1.09"));
+    assert_eq!(header.entry_count, 2);
+    assert_eq!(header.entities.len(), 2);
+    assert_eq!(
+        header.entities.get("uk").map(String::as_str),
+        Some("word usually written using kana alone")
+    );
+
+    //a truncated file is detected
+    let truncated = format!("{}\n{}\n", lines[0], lines[1]);
+    let err = check_contents("entrypack-truncated.json", &truncated).unwrap_err();
+    assert!(
+        err.contains("announces 2 entries, but 1 were found"),
+        "{}",
+        err
+    );
+
+    //so is a modified entry
+    let modified = contents.replace("repetition mark", "iteration mark");
+    let err = check_contents("entrypack-modified.json", &modified).unwrap_err();
+    assert!(err.contains("checksum"), "{}", err);
+
+    //entrypacks in format version 1 do not have a header
+    let v1 = lines[1..].join("\n");
+    let err = check_contents("entrypack-v1.json", &v1).unwrap_err();
+    assert!(err.contains("format version 1"), "{}", err);
+}
+
+#[test]
+fn test_entrypack_malformed_xml() {
+    let convert = |xml: &str| {
+        let mut buf = Vec::new();
+        let result = jmdict_traverse::write_entrypack(xml.as_bytes(), &mut buf);
+        (result, buf)
+    };
+    let (result, buf) = convert(JMDICT_XML);
+    assert!(result.is_ok());
+    assert!(!buf.is_empty());
+
+    let cases = [
+        //invalid XML
+        JMDICT_XML.replace("</reb>", "</keb>"),
+        //missing required element
+        JMDICT_XML.replace("<ent_seq>1000010</ent_seq>", ""),
+        //invalid character reference
+        JMDICT_XML.replace("repetition mark", "repetition &#xFFFFFFFF; mark"),
+    ];
+    for xml in &cases {
+        let (result, buf) = convert(xml);
+        let err = result.unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidData, "{}", err);
+        assert!(buf.is_empty());
+    }
+}
+
+///Writes the given contents into a temporary file, and returns its path.
+fn temp_file(name: &str, contents: &[u8]) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("{}-{}", std::process::id(), name));
+    std::fs::write(&path, contents).unwrap();
+    path
+}
+
+#[test]
+fn test_entrypack_delta() {
+    let build = |xml: &str| {
+        let mut buf = Vec::new();
+        jmdict_traverse::write_entrypack(xml.as_bytes(), &mut buf).unwrap();
+        buf
+    };
+    let old = build(JMDICT_XML);
+    //change the first entry, remove the second one, and add a third one
+    let new_xml = JMDICT_XML
+        .replace(
+            "repetition mark in katakana<",
+            "iteration mark in katakana<",
+        )
+        .replace("<ent_seq>1000010</ent_seq>", "<ent_seq>1000020</ent_seq>");
+    let new = build(&new_xml);
+    let old_path = temp_file("delta-old.json", &old);
+    let new_path = temp_file("delta-new.json", &new);
+
+    let mut delta = Vec::new();
+    let stats = jmdict_traverse::write_entrypack_delta(&old_path, &new_path, &mut delta).unwrap();
+    assert_eq!(
+        stats,
+        jmdict_traverse::DeltaStats {
+            added: 1,
+            changed: 1,
+            removed: 1,
+        }
+    );
+    let delta_str = std::str::from_utf8(&delta).unwrap();
+    assert_eq!(delta_str.lines().count(), 4);
+    assert!(
+        delta_str.contains("\n{\"removed\":1000010}\n"),
+        "{}",
+        delta_str
+    );
+
+    //applying the delta reproduces the new entrypack exactly
+    let delta_path = temp_file("delta.json", &delta);
+    let mut result = Vec::new();
+    let header =
+        jmdict_traverse::apply_entrypack_delta(&old_path, &delta_path, &mut result).unwrap();
+    assert_eq!(header.entry_count, 2);
+    assert_eq!(result, new);
+
+    //the delta does not apply to any other entrypack
+    let err = jmdict_traverse::apply_entrypack_delta(&new_path, &delta_path, &mut Vec::new())
+        .unwrap_err();
+    assert!(err.contains("does not apply"), "{}", err);
+
+    //between identical entrypacks, the delta only consists of the header
+    let mut delta = Vec::new();
+    let stats = jmdict_traverse::write_entrypack_delta(&old_path, &old_path, &mut delta).unwrap();
+    assert_eq!(stats, jmdict_traverse::DeltaStats::default());
+    assert_eq!(std::str::from_utf8(&delta).unwrap().lines().count(), 1);
+
+    for path in &[old_path, new_path, delta_path] {
+        std::fs::remove_file(path).unwrap();
+    }
+}
+
+///The contents of the HELLO_* data files after decoding.
+fn hello_decoded() -> String {
+    "hello world\n".repeat(20000)
+}
+
+///[hello_decoded()], compressed with `xz`. The decoded size is larger than the chunks in which the
+///XZ decoder passes data back.
+const HELLO_XZ: &[u8] = &[
+    0xFD, 0x37, 0x7A, 0x58, 0x5A, 0x00, 0x00, 0x04, 0xE6, 0xD6, 0xB4, 0x46, 0x04, 0xC0, 0x7D, 0x80,
+    0xD3, 0x0E, 0x21, 0x01, 0x16, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x06, 0xE2, 0x6D, 0x35,
+    0xE3, 0xA9, 0x7F, 0x00, 0x75, 0x5D, 0x00, 0x34, 0x19, 0x49, 0xEE, 0x8D, 0xE9, 0x17, 0x89, 0x3A,
+    0x33, 0x5F, 0xFC, 0xB4, 0x04, 0xB1, 0xCA, 0x03, 0x3C, 0x4C, 0x8F, 0xED, 0xAA, 0xED, 0x18, 0x72,
+    0xFE, 0x26, 0x48, 0x77, 0x2E, 0xB0, 0xD5, 0x46, 0xF4, 0x90, 0xEF, 0x70, 0xBA, 0x10, 0x22, 0x6C,
+    0x6F, 0x23, 0x69, 0xF6, 0x5A, 0xBB, 0x39, 0xFF, 0xE6, 0xD6, 0xDA, 0x85, 0x2D, 0xFD, 0x63, 0x47,
+    0x4E, 0x09, 0x55, 0xFD, 0x22, 0xB0, 0xB5, 0x75, 0x3B, 0xC3, 0x48, 0x9C, 0xE6, 0x6F, 0x95, 0xD8,
+    0x7F, 0x0B, 0x40, 0xA1, 0x33, 0xA0, 0x72, 0xFE, 0x0A, 0x8C, 0x48, 0x36, 0x6E, 0x31, 0x9D, 0x3B,
+    0x06, 0x33, 0x34, 0xE6, 0x49, 0x6E, 0x41, 0x45, 0xE4, 0x1B, 0x30, 0xE0, 0xA7, 0xBE, 0x39, 0x8F,
+    0x24, 0xE1, 0x70, 0x9E, 0xA7, 0x23, 0x54, 0x08, 0xD0, 0x56, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+    0x8D, 0x60, 0x93, 0xB8, 0xFD, 0x7B, 0x1F, 0x63, 0x00, 0x01, 0x99, 0x01, 0x80, 0xD3, 0x0E, 0x00,
+    0x3A, 0xAA, 0x49, 0x38, 0xB1, 0xC4, 0x67, 0xFB, 0x02, 0x00, 0x00, 0x00, 0x00, 0x04, 0x59, 0x5A,
+];
+
+///[hello_decoded()], compressed with `zstd`.
+const HELLO_ZST: &[u8] = &[
+    0x28, 0xB5, 0x2F, 0xFD, 0xA4, 0x80, 0xA9, 0x03, 0x00, 0xA4, 0x00, 0x00, 0x60, 0x68, 0x65, 0x6C,
+    0x6C, 0x6F, 0x20, 0x77, 0x6F, 0x72, 0x6C, 0x64, 0x0A, 0x01, 0x00, 0xF1, 0xFF, 0xCF, 0x4B, 0x12,
+    0x4D, 0x00, 0x00, 0x08, 0x72, 0x01, 0x00, 0x7C, 0xA9, 0x39, 0x10, 0x02, 0xFE, 0x9D, 0x5F, 0xD2,
+];
+
+///Decodes the given file with [jmdict_traverse::open_data_file()]. Panics from inside that
+///function are caught, so that the temporary file gets cleaned up in any case.
+fn decode_data_file(name: &str, contents: &[u8]) -> std::thread::Result<String> {
+    use std::io::Read;
+    let path = temp_file(name, contents);
+    let result = std::panic::catch_unwind(|| {
+        let mut decoded = String::new();
+        jmdict_traverse::open_data_file(&path)
+            .read_to_string(&mut decoded)
+            .unwrap();
+        decoded
+    });
+    std::fs::remove_file(&path).unwrap();
+    result
+}
+
+///Checks that the given HELLO_* data file is decoded correctly if `enabled` is true, i.e. if the
+///respective decoder feature is enabled. Otherwise, [jmdict_traverse::open_data_file()] panics,
+///unless the decoder got enabled anyway through feature unification (e.g. because jmdict-entrypack
+///is built alongside this crate).
+fn check_decoding(name: &str, contents: &[u8], enabled: bool) {
+    match decode_data_file(name, contents) {
+        Ok(decoded) => assert_eq!(decoded, hello_decoded()),
+        Err(_) => assert!(!enabled, "could not decode {}", name),
+    }
+}
+
+#[test]
+fn test_open_data_file() {
+    let expected = hello_decoded();
+    assert_eq!(
+        decode_data_file("hello.txt", expected.as_bytes()).unwrap(),
+        expected
+    );
+    check_decoding("hello.txt.xz", HELLO_XZ, cfg!(feature = "decode-xz"));
+    check_decoding("hello.txt.zst", HELLO_ZST, cfg!(feature = "decode-zstd"));
+}
+
+#[cfg(feature = "decode-xz")]
+#[test]
+fn test_open_data_file_xz() {
+    //corrupted XZ streams are reported as read errors instead of being truncated silently
+    use std::io::Read;
+    let mut corrupted = HELLO_XZ.to_vec();
+    corrupted[60] ^= 0xFF;
+    let path = temp_file("corrupted.txt.xz", &corrupted);
+    let result = jmdict_traverse::open_data_file(&path).read_to_end(&mut Vec::new());
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+}
@@ -0,0 +1,52 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use crate::*;
+
+#[test]
+fn test_payload_roundtrip() {
+    let mut buf = Vec::new();
+    write_payload(&mut buf).unwrap();
+    let file = read_payload(&buf[..]).unwrap();
+
+    assert!(file.is_from_this_build());
+    assert_eq!(
+        file.metadata("jmdict_version"),
+        Some(env!("CARGO_PKG_VERSION"))
+    );
+    let entry_count = entries().len().to_string();
+    assert_eq!(file.metadata("entry_count"), Some(entry_count.as_str()));
+    assert_eq!(
+        file.metadata("creation_date"),
+        database_info().creation_date
+    );
+    assert_eq!(file.strings(), payload::all_texts());
+
+    //all arrays are exported in decoded form, regardless of the payload features
+    let u32_sections = payload::u32_sections();
+    let names: Vec<&str> = file.section_names().collect();
+    assert_eq!(names.len(), u32_sections.len() + 2);
+    for (name, vals) in u32_sections {
+        assert_eq!(file.u32s(name).as_deref(), Some(vals), "section {}", name);
+    }
+    assert_eq!(file.u32s("metadata"), None);
+    assert_eq!(file.u32s("does_not_exist"), None);
+}
+
+#[test]
+fn test_payload_errors() {
+    let mut buf = Vec::new();
+    write_payload(&mut buf).unwrap();
+    let err_of = |bytes: &[u8]| read_payload(bytes).unwrap_err().to_string();
+
+    assert_eq!(err_of(b"not a payload file"), "not a payload file");
+    assert_eq!(err_of(&buf[..buf.len() - 1]), "payload file is truncated");
+    assert_eq!(err_of(&buf[..4]), "payload file is truncated");
+
+    let mut newer = buf.clone();
+    newer[8..12].copy_from_slice(&(PAYLOAD_FILE_VERSION + 1).to_le_bytes());
+    assert!(err_of(&newer).contains("format version"));
+}
@@ -52,11 +52,24 @@ fn test_gloss_availability() {
         if *selected {
             assert!(glosses.contains(gloss), "glosses were {:?}", glosses);
         }
+
+        //the same information is available without looking at actual glosses
+        let lang = AllGlossLanguage::from_code(lang_code).unwrap();
+        assert_eq!(*selected, lang.is_enabled());
+        match GlossLanguage::try_from_code(lang_code) {
+            Ok(l) => assert!(*selected && AllGlossLanguage::from(l) == lang),
+            Err(e) => assert!(!*selected && e == GlossLanguageCodeError::Disabled(lang)),
+        }
     }
+    assert_eq!(
+        GlossLanguage::try_from_code("xyz"),
+        Err(GlossLanguageCodeError::UnknownCode)
+    );
 }
 
 ///Spot checks for correct decoding of priorities.
 #[test]
+#[cfg(not(feature = "exclude-priority"))]
 fn test_priorities() {
     //Tests may be skipped if the test entry is not available, since entry
     //availability depends on the selection of target languages.
@@ -66,10 +79,12 @@ fn test_priorities() {
             Priority {
                 ichimango: PriorityInCorpus::Primary,
                 news: PriorityInCorpus::Secondary,
-                frequency_bucket: 36,
+                frequency_bucket: Some(36),
                 ..Default::default()
             }
         );
+        assert_eq!(ke.priority.to_string(), "ichi1, news2, nf36");
+        assert_eq!("ichi1, news2, nf36".parse(), Ok(ke.priority));
     }
 
     if let Some((_, _, re)) = find_by_keb_reb("あの方", "あのかた") {
@@ -97,6 +112,25 @@ fn test_priorities() {
     }
 }
 
+///Checks that all priorities survive a round trip through their textual representation.
+#[test]
+#[cfg(not(feature = "exclude-priority"))]
+fn test_priority_round_trip() {
+    for entry in entries() {
+        let priorities = entry
+            .kanji_elements()
+            .map(|k| k.priority)
+            .chain(entry.reading_elements().map(|r| r.priority));
+        for prio in priorities {
+            assert_eq!(Priority::from_markers(prio.markers()), Ok(prio));
+            assert_eq!(prio.to_string().parse(), Ok(prio));
+        }
+    }
+
+    let err = "ichi1, nf00".parse::<Priority>().unwrap_err();
+    assert_eq!(err.marker(), "nf00");
+}
+
 ///Spot checks for correct decoding of enums.
 #[test]
 fn test_enums() {
@@ -119,7 +153,7 @@ fn test_enums() {
     } else {
         ("発条", "ばね", "GikunOrJukujikun")
     };
-    if keb != "" {
+    if !keb.is_empty() {
         if let Some((_, _, re)) = find_by_keb_reb(keb, reb) {
             assert_eq!(enum2str(re.infos()), expected_infos);
         }
@@ -187,13 +221,19 @@ fn test_strings() {
 
         //check for xref
         let sense = find_sense("彼の", "the");
-        assert_eq!(strs2str(sense.cross_references()), "どの,この・1,その・1");
+        assert_eq!(
+            strs2str(sense.cross_references().map(|x| x.text)),
+            "どの,この・1,その・1"
+        );
 
         //check for ant (`db-minimal` has absolutely none of those)
         #[cfg(not(feature = "db-minimal"))]
         {
             let sense = find_sense("アンダー", "under");
-            assert_eq!(strs2str(sense.antonyms()), "オーバー・2");
+            assert_eq!(strs2str(sense.antonyms().map(|x| x.text)), "オーバー・2");
+            let (entry, sense) = sense.antonyms().next().unwrap().resolve_sense().unwrap();
+            assert!(entry.reading_elements().any(|r| r.text == "オーバー"));
+            assert!(sense.glosses().any(|g| g.text == "going over"));
         }
 
         //check for s_inf
@@ -205,6 +245,34 @@ fn test_strings() {
     }
 }
 
+///Checks that [Entry::is_usually_kana()] follows the first sense when the senses disagree.
+#[test]
+fn test_usually_kana() {
+    for entry in entries() {
+        let first_sense = entry.senses().next().is_some_and(|s| s.is_usually_kana());
+        assert_eq!(entry.is_usually_kana(), first_sense);
+    }
+
+    //Which senses are included depends on the target languages, so the spot checks are
+    //feature-gated like in `test_strings()`.
+    #[cfg(feature = "translations-eng")]
+    {
+        //屁 is usually written as おなら in its first sense ("wind"), but not in its second sense
+        let (entry, _) = find_by_keb("屁").unwrap();
+        assert!(entry.senses().any(|s| !s.is_usually_kana()));
+        assert!(entry.is_usually_kana());
+
+        //一杯 is usually written as いっぱい in its later senses (e.g. "a lot"), but not in its first
+        //sense ("cupful")
+        #[cfg(not(feature = "db-minimal"))]
+        {
+            let (entry, _) = find_by_keb("一杯").unwrap();
+            assert!(entry.senses().any(|s| s.is_usually_kana()));
+            assert!(!entry.is_usually_kana());
+        }
+    }
+}
+
 ///Spot checks for correct encoding of loanword sources.
 #[test]
 fn test_loanword_sources() {
@@ -217,7 +285,7 @@ fn test_loanword_sources() {
             &sense.loanword_sources().collect::<Vec<_>>(),
             &[LoanwordSource {
                 text: "eye mate",
-                language: "eng",
+                language: SourceLanguage::English,
                 is_partial: false,
                 is_wasei: true,
             }]
@@ -232,13 +300,13 @@ fn test_loanword_sources() {
                 &[
                     LoanwordSource {
                         text: "sub",
-                        language: "eng",
+                        language: SourceLanguage::English,
                         is_partial: true,
                         is_wasei: true,
                     },
                     LoanwordSource {
                         text: "Sack",
-                        language: "ger",
+                        language: SourceLanguage::German,
                         is_partial: true,
                         is_wasei: true,
                     }
@@ -263,6 +331,7 @@ fn find_by_keb(keb: &'static str) -> Option<(Entry, KanjiElement)> {
     Some((e, e.kanji_elements().find(|k| k.text == keb).unwrap()))
 }
 
+#[cfg(not(feature = "exclude-priority"))]
 fn find_by_reb(reb: &'static str) -> Option<(Entry, ReadingElement)> {
     let e = entries().find(|e| e.reading_elements().any(|r| r.text == reb))?;
     Some((e, e.reading_elements().find(|r| r.text == reb).unwrap()))
@@ -290,3 +359,292 @@ fn find_sense(jp_text: &'static str, gloss: &'static str) -> Sense {
         .find(|s| s.glosses().any(|g| g.text == gloss))
         .unwrap()
 }
+
+///Checks that the creation date of the JMdict snapshot is reported.
+#[test]
+fn test_database_info() {
+    let info = database_info();
+    if cfg!(feature = "db-empty") {
+        assert_eq!(info.creation_date, None);
+        return;
+    }
+    let date = info.creation_date.unwrap();
+    let parts: Vec<_> = date.split('-').map(|p| p.len()).collect();
+    assert_eq!(parts, vec![4, 2, 2], "unexpected date format: {:?}", date);
+    assert!(date.chars().all(|c| c.is_ascii_digit() || c == '-'));
+}
+
+///Checks that descriptions are available for enum variants, regardless of which variants are
+///enabled.
+#[test]
+fn test_enum_descriptions() {
+    assert_eq!(
+        KanjiInfo::IrregularKanjiUsage.description(),
+        "word containing irregular kanji usage"
+    );
+    for &pos in AllPartOfSpeech::all_variants() {
+        assert!(!pos.description().is_empty(), "{:?}", pos);
+        if let Ok(pos) = std::convert::TryInto::<PartOfSpeech>::try_into(pos) {
+            assert_eq!(pos.description(), AllPartOfSpeech::from(pos).description());
+        }
+    }
+}
+
+///Checks that language names are available for all gloss languages, regardless of which target
+///languages are enabled.
+#[test]
+fn test_gloss_language_names() {
+    let lang = AllGlossLanguage::German;
+    assert_eq!(
+        (lang.english_name(), lang.native_name(), lang.iso639_1()),
+        ("German", "Deutsch", "de")
+    );
+    for &lang in AllGlossLanguage::all_variants() {
+        assert_eq!(lang.iso639_1().len(), 2, "{:?}", lang);
+        assert_eq!(lang.english_name(), lang.constant_name());
+    }
+    for &lang in GlossLanguage::all_variants() {
+        assert_eq!(
+            lang.native_name(),
+            AllGlossLanguage::from(lang).native_name()
+        );
+    }
+}
+
+///Checks that all enums fit into an EnumSet, regardless of which variants are enabled.
+#[test]
+fn test_enum_set_capacity() {
+    macro_rules! check {
+        ($($enum: ty),*) => {
+            $(
+                let all = EnumSet::<$enum>::all();
+                assert_eq!(all.len(), <$enum>::all_variants().len());
+                for value in all {
+                    assert!(<$enum>::all_variants().contains(&value));
+                }
+            )*
+        };
+    }
+    check!(
+        AllPartOfSpeech,
+        AllGlossLanguage,
+        Dialect,
+        KanjiInfo,
+        ReadingInfo,
+        SenseInfo,
+        SenseTopic,
+        SourceLanguage
+    );
+}
+
+///Checks that `to_index()` and `from_index()` enumerate the variants densely.
+#[test]
+fn test_enum_indexes() {
+    macro_rules! check {
+        ($($enum: ty),*) => {
+            $(
+                assert_eq!(<$enum>::VARIANT_COUNT, <$enum>::all_variants().len());
+                for (idx, value) in <$enum>::all_variants().iter().enumerate() {
+                    assert_eq!(value.to_index(), Some(idx));
+                    assert_eq!(<$enum>::from_index(idx), Some(*value));
+                }
+                assert_eq!(<$enum>::from_index(<$enum>::VARIANT_COUNT), None);
+            )*
+        };
+    }
+    check!(
+        AllPartOfSpeech,
+        PartOfSpeech,
+        AllGlossLanguage,
+        GlossLanguage,
+        Dialect,
+        KanjiInfo,
+        ReadingInfo,
+        SenseInfo,
+        SenseTopic,
+        SourceLanguage
+    );
+    assert_eq!(KanjiInfo::Unknown("foo").to_index(), None);
+
+    let mut counts = [0; AllPartOfSpeech::VARIANT_COUNT];
+    counts[AllPartOfSpeech::CommonNoun.to_index().unwrap()] += 1;
+    assert_eq!(counts.iter().sum::<usize>(), 1);
+}
+
+///Checks that enums can be parsed from both their codes and their constant names.
+#[test]
+fn test_enum_from_str() {
+    for &pos in AllPartOfSpeech::all_variants() {
+        assert_eq!(pos.code().parse(), Ok(pos));
+        assert_eq!(pos.constant_name().parse(), Ok(pos));
+    }
+    for &lang in GlossLanguage::all_variants() {
+        assert_eq!(lang.code().parse(), Ok(lang));
+    }
+
+    let err = "Unknown".parse::<KanjiInfo>().unwrap_err();
+    assert_eq!(err.enum_name(), "KanjiInfo");
+    assert_eq!(err.input(), "Unknown");
+    assert_eq!(
+        err.to_string(),
+        "unknown or disabled KanjiInfo: \"Unknown\""
+    );
+}
+
+///Checks that the enum registry agrees with the respective enum types.
+#[test]
+fn test_enum_kinds() {
+    let names: Vec<_> = enum_kinds().iter().map(|k| k.name()).collect();
+    let mut sorted = names.clone();
+    sorted.sort();
+    assert_eq!(names, sorted);
+
+    let kind = enum_kind("PartOfSpeech").unwrap();
+    assert_eq!(kind.variants().len(), PartOfSpeech::VARIANT_COUNT);
+    for (info, pos) in kind.variants().iter().zip(PartOfSpeech::all_variants()) {
+        assert_eq!(info.code, pos.code());
+        assert_eq!(info.constant_name, pos.constant_name());
+        assert_eq!(info.description, Some(pos.description()));
+    }
+
+    let kind = enum_kind("GlossType").unwrap();
+    assert_eq!(
+        kind.variant("lit").unwrap().constant_name,
+        "LiteralTranslation"
+    );
+    assert_eq!(kind.variant("lit").unwrap().description, None);
+    assert_eq!(kind.variant("xyz"), None);
+    assert!(enum_kind("AllPartOfSpeech").is_none());
+}
+
+///Checks that enums are ordered in the same way as their variants are declared.
+#[test]
+fn test_enum_ordering() {
+    let mut sorted = AllPartOfSpeech::all_variants().to_vec();
+    sorted.sort();
+    assert_eq!(sorted, AllPartOfSpeech::all_variants());
+    assert!(KanjiInfo::IrregularKanjiUsage < KanjiInfo::RareKanjiForm);
+}
+
+///Checks that labels fall back to the English descriptions.
+#[test]
+fn test_enum_labels() {
+    let pos = PartOfSpeech::GodanKuVerb;
+    assert_eq!(pos.label("en"), "Godan verb with 'ku' ending");
+    for &pos in AllPartOfSpeech::all_variants() {
+        assert!(!pos.label("xx").is_empty(), "{:?}", pos);
+    }
+}
+
+///Checks the behavior of the `Unknown` variants that are used for entities that this crate does
+///not know about.
+#[test]
+fn test_unknown_variants() {
+    use std::convert::TryFrom;
+    let pos = AllPartOfSpeech::Unknown("v9z");
+    assert_eq!(pos.code(), "v9z");
+    assert_eq!(pos.description(), "v9z");
+    assert_eq!(pos.to_string(), "Unknown(v9z)");
+    assert_eq!(
+        PartOfSpeech::try_from(pos),
+        Ok(PartOfSpeech::Unknown("v9z"))
+    );
+    assert_eq!(AllPartOfSpeech::from(PartOfSpeech::Unknown("v9z")), pos);
+    assert_eq!(AllPartOfSpeech::from_code("v9z"), None);
+
+    let mut set = EnumSet::new();
+    assert!(!set.insert(SenseInfo::Unknown("foo")));
+    assert!(set.is_empty());
+    assert!(!set.contains(SenseInfo::Unknown("foo")));
+}
+
+///Checks that search-only forms are recognized and skipped by display helpers.
+#[test]
+fn test_search_only_forms() {
+    assert_eq!(
+        KanjiInfo::from_code("sK"),
+        Some(KanjiInfo::SearchOnlyKanjiForm)
+    );
+    assert_eq!(
+        ReadingInfo::from_code("sk"),
+        Some(ReadingInfo::SearchOnlyKanaForm)
+    );
+    for entry in entries() {
+        assert!(entry
+            .kanji_elements_for_display()
+            .all(|k| !k.is_search_only()));
+        assert!(entry
+            .reading_elements_for_display()
+            .all(|r| !r.is_search_only()));
+        let main_form = entry.main_form();
+        assert!(!entry
+            .kanji_elements()
+            .any(|k| k.text == main_form && k.is_search_only()));
+    }
+}
+
+///Checks that the enums cover exactly the entities that are listed in the entity list of the
+///JMdict (as copied into jmdict-enums/data/entities.json).
+#[test]
+fn test_enums_match_entities() {
+    let entities: serde_json::Value =
+        serde_json::from_str(include_str!("../jmdict-enums/data/entities.json")).unwrap();
+    macro_rules! check {
+        ($($group: literal => $enum: ty),*) => {
+            $(
+                let mut expected: Vec<&str> = entities[$group]
+                    .as_object()
+                    .unwrap()
+                    .keys()
+                    .map(|k| k.as_str())
+                    .collect();
+                expected.sort_unstable();
+                let mut actual: Vec<&str> = <$enum>::all_variants().iter().map(|v| v.code()).collect();
+                actual.sort_unstable();
+                assert_eq!(expected, actual, "entity group was {}", $group);
+            )*
+        };
+    }
+    check!(
+        "dial" => Dialect,
+        "field" => SenseTopic,
+        "ke_inf" => KanjiInfo,
+        "misc" => SenseInfo,
+        "pos" => AllPartOfSpeech,
+        "re_inf" => ReadingInfo
+    );
+}
+
+///Checks that senses for proper names are only present if not excluded.
+#[test]
+fn test_name_senses() {
+    let has_names = entries()
+        .flat_map(|e| e.senses())
+        .any(|s| s.infos().any(|i| i.is_name_type()));
+    if cfg!(feature = "exclude-names") {
+        assert!(!has_names);
+    } else if cfg!(not(feature = "db-minimal")) {
+        assert!(has_names);
+    }
+}
+
+///Checks that senses with sensitive content are only present if not excluded.
+#[test]
+fn test_sensitive_senses() {
+    let has_sensitive = entries()
+        .flat_map(|e| e.senses())
+        .any(|s| s.infos().any(|i| i.is_sensitive_content()));
+    assert_eq!(has_sensitive, cfg!(not(feature = "exclude-sensitive")));
+}
+
+///Checks that priorities are only present if not excluded.
+#[test]
+fn test_priority_data() {
+    let has_priority = entries().any(|e| {
+        e.kanji_elements()
+            .map(|k| k.priority)
+            .chain(e.reading_elements().map(|r| r.priority))
+            .any(|p| p != Priority::default())
+    });
+    assert_eq!(has_priority, cfg!(not(feature = "exclude-priority")));
+}
@@ -0,0 +1,114 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use crate::*;
+
+fn texts<T>(iter: impl Iterator<Item = T>, text: impl Fn(&T) -> &'static str) -> Vec<&'static str> {
+    iter.map(|x| text(&x)).collect()
+}
+
+fn first_gloss(s: &Sense) -> &'static str {
+    s.glosses().next().map_or("", |g| g.text)
+}
+
+#[test]
+fn test_content_filter() {
+    let filters = [
+        ContentFilter::default(),
+        ContentFilter {
+            hide_archaic: true,
+            ..ContentFilter::default()
+        },
+        ContentFilter {
+            hide_uncommon: true,
+            ..ContentFilter::default()
+        },
+    ];
+    //hiding content that is not in the database does not change anything
+    let is_noop = [
+        true,
+        !cfg!(feature = "scope-archaic"),
+        !cfg!(feature = "scope-uncommon") || cfg!(feature = "exclude-priority"),
+    ];
+    let mut hidden_entry_counts = [0; 3];
+
+    for entry in entries() {
+        for ((filter, is_noop), hidden_count) in filters
+            .iter()
+            .zip(is_noop.iter())
+            .zip(hidden_entry_counts.iter_mut())
+        {
+            if !filter.accepts_entry(&entry) {
+                *hidden_count += 1;
+                continue;
+            }
+            let ks = texts(filter.kanji_elements(&entry), |k| k.text);
+            let rs = texts(filter.reading_elements(&entry), |r| r.text);
+            let ss = texts(filter.senses(&entry), first_gloss);
+            if *is_noop {
+                assert_eq!(ks, texts(entry.kanji_elements(), |k| k.text));
+                assert_eq!(rs, texts(entry.reading_elements(), |r| r.text));
+                assert_eq!(ss, texts(entry.senses(), first_gloss));
+            }
+            assert!(!rs.is_empty() && !ss.is_empty());
+            if filter.hide_archaic {
+                for s in filter.senses(&entry) {
+                    assert!(!s.infos().any(|i| i == SenseInfo::Archaism));
+                }
+            }
+            if filter.hide_uncommon && !*is_noop {
+                for k in filter.kanji_elements(&entry) {
+                    assert_ne!(k.priority, Priority::default());
+                }
+                for r in filter.reading_elements(&entry) {
+                    assert_ne!(r.priority, Priority::default());
+                }
+            }
+        }
+    }
+    for (hidden_count, is_noop) in hidden_entry_counts.iter().zip(is_noop.iter()) {
+        if *is_noop {
+            assert_eq!(*hidden_count, 0);
+        }
+    }
+    assert_eq!(
+        filters[1].entries(entries()).count(),
+        entries().count() - hidden_entry_counts[1]
+    );
+}
+
+#[test]
+fn test_search_with_content_filter() {
+    let content_filter = ContentFilter {
+        hide_archaic: true,
+        hide_uncommon: true,
+    };
+    let opts = SearchOptions {
+        content_filter,
+        ..SearchOptions::default()
+    };
+    for query in &["日", "にち", "day", "of"] {
+        let unfiltered: Vec<_> = search(query).map(|h| (h.entry.number, h.element)).collect();
+        for hit in search_with_options(query, opts) {
+            assert!(unfiltered.contains(&(hit.entry.number, hit.element)));
+            assert!(content_filter.accepts_entry(&hit.entry));
+            match hit.element {
+                MatchedElement::KanjiElement(idx) => {
+                    let k = hit.entry.kanji_elements().nth(idx).unwrap();
+                    assert!(content_filter.accepts_kanji_element(&k));
+                }
+                MatchedElement::ReadingElement(idx) => {
+                    let r = hit.entry.reading_elements().nth(idx).unwrap();
+                    assert!(content_filter.accepts_reading_element(&r));
+                }
+                MatchedElement::Gloss { sense, .. } => {
+                    let s = hit.entry.senses().nth(sense).unwrap();
+                    assert!(content_filter.accepts_sense(&s));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,37 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use sha2::{Digest, Sha256};
+
+///Recomputes the fingerprint from the files that were embedded into this build (with the same
+///algorithm as `compute_fingerprint()` in build.rs), and checks that it matches.
+#[test]
+fn test_build_fingerprint() {
+    let out_dir = std::path::Path::new(env!("OUT_DIR"));
+    let mut file_names: Vec<String> = std::fs::read_dir(out_dir)
+        .unwrap()
+        .map(|dir_entry| dir_entry.unwrap().file_name().into_string().unwrap())
+        //this is written for `RUST_JMDICT_SIZE_REPORT` and not embedded
+        .filter(|name| name != "size_report.json")
+        .collect();
+    file_names.sort();
+    assert!(file_names.iter().any(|name| name == "payload.dat"));
+
+    let mut hasher = Sha256::new();
+    for name in &file_names {
+        let contents = std::fs::read(out_dir.join(name)).unwrap();
+        hasher.update(name.as_bytes());
+        hasher.update([0]);
+        hasher.update((contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+    let expected: String = hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    assert_eq!(crate::BUILD_FINGERPRINT, expected);
+}
@@ -0,0 +1,347 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use crate::*;
+
+//NOTE: These tests compare the results of index lookups against full scans of the database, so
+//they work regardless of which entries were selected through feature flags.
+
+#[test]
+fn test_entries_containing_kanji() {
+    for kanji in &['曜', '日', '母', '龘'] {
+        let expected: Vec<u32> = entries()
+            .filter(|e| e.kanji_elements().any(|k| k.text.contains(*kanji)))
+            .map(|e| e.number)
+            .collect();
+        let actual: Vec<u32> = entries_containing_kanji(*kanji).map(|e| e.number).collect();
+        assert_eq!(expected, actual, "kanji was {}", kanji);
+    }
+
+    //non-kanji characters are not indexed
+    assert_eq!(entries_containing_kanji('の').count(), 0);
+}
+
+#[test]
+fn test_kanji_inventory() {
+    let mut expected = std::collections::BTreeMap::<char, usize>::new();
+    for entry in entries() {
+        let mut chars: Vec<char> = entry
+            .kanji_elements()
+            .flat_map(|k| k.text.chars())
+            .collect();
+        chars.sort_unstable();
+        chars.dedup();
+        for c in chars {
+            if entries_containing_kanji(c).next().is_some() {
+                *expected.entry(c).or_default() += 1;
+            }
+        }
+    }
+    let actual: std::collections::BTreeMap<char, usize> = kanji_inventory()
+        .map(|(kanji, entries)| (kanji, entries.len()))
+        .collect();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_loanword_source_languages() {
+    let mut expected = std::collections::BTreeMap::<&str, usize>::new();
+    for entry in entries() {
+        let sources = entry.senses().flat_map(|s| s.loanword_sources());
+        let mut codes: Vec<&str> = sources.map(|ls| ls.language.code()).collect();
+        codes.sort_unstable();
+        codes.dedup();
+        for code in codes {
+            *expected.entry(code).or_default() += 1;
+        }
+    }
+    let actual: std::collections::BTreeMap<&str, usize> = loanword_source_languages()
+        .map(|(language, entries)| (language.code(), entries.len()))
+        .collect();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_entries_with_loanword_source() {
+    use SourceLanguage::*;
+    for language in &[German, English, French, Swahili] {
+        let expected: Vec<u32> = entries()
+            .filter(|e| {
+                let mut sources = e.senses().flat_map(|s| s.loanword_sources());
+                sources.any(|ls| ls.language == *language)
+            })
+            .map(|e| e.number)
+            .collect();
+        let actual: Vec<u32> = entries_with_loanword_source(*language)
+            .map(|e| e.number)
+            .collect();
+        assert_eq!(expected, actual, "language was {}", language);
+    }
+}
+
+#[test]
+fn test_entries_with_stem() {
+    //check that all ichidan verbs, godan verbs with "-ku" and i-adjectives can be found by their stem
+    use PartOfSpeech::*;
+    let cases = [(IchidanVerb, "る"), (GodanKuVerb, "く"), (Adjective, "い")];
+    for (pos, ending) in cases.iter() {
+        for e in entries().filter(|e| e.senses().any(|s| s.parts_of_speech().any(|p| p == *pos))) {
+            let kebs = e.kanji_elements().map(|k| k.text);
+            let rebs = e.reading_elements().map(|r| r.text);
+            for text in kebs.chain(rebs) {
+                if let Some(stem) = text.strip_suffix(ending).filter(|s| !s.is_empty()) {
+                    assert!(
+                        entries_with_stem(stem).any(|e2| e2.number == e.number),
+                        "stem {:?} of {:?} not found",
+                        stem,
+                        text
+                    );
+                }
+            }
+        }
+    }
+
+    //all results must actually start with the stem
+    for stem in &["食べ", "書", "高", "見"] {
+        for e in entries_with_stem(stem) {
+            let kebs = e.kanji_elements().map(|k| k.text);
+            let rebs = e.reading_elements().map(|r| r.text);
+            assert!(kebs.chain(rebs).any(|text| text.starts_with(stem)));
+        }
+    }
+}
+
+#[test]
+fn test_all_readings() {
+    let mut expected = std::collections::BTreeMap::<&str, Vec<u32>>::new();
+    for e in entries() {
+        for r in e.reading_elements() {
+            let numbers = expected.entry(r.text).or_default();
+            if numbers.last() != Some(&e.number) {
+                numbers.push(e.number);
+            }
+        }
+    }
+    let actual: std::collections::BTreeMap<&str, Vec<u32>> = all_readings()
+        .map(|(text, entries)| (text, entries.map(|e| e.number).collect()))
+        .collect();
+    assert_eq!(all_readings().len(), expected.len());
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_segment() {
+    let headwords: std::collections::HashSet<&str> = entries()
+        .flat_map(|e| {
+            let kebs = e.kanji_elements().map(|k| k.text);
+            kebs.chain(e.reading_elements().map(|r| r.text))
+        })
+        .collect();
+
+    let text = "お母さんは日曜日に映画を見に行きます。";
+
+    //compute the expected segmentation by brute force
+    let mut expected = Vec::new();
+    let mut position = 0;
+    while position < text.len() {
+        let rest = &text[position..];
+        let longest = rest
+            .char_indices()
+            .map(|(idx, c)| idx + c.len_utf8())
+            .rev()
+            .find(|len| headwords.contains(&rest[0..*len]));
+        match longest {
+            Some(len) => {
+                expected.push(position..(position + len));
+                position += len;
+            }
+            None => position += rest.chars().next().unwrap().len_utf8(),
+        }
+    }
+
+    let mut actual = Vec::new();
+    for (span, entry) in segment(text) {
+        let form = &text[span.clone()];
+        assert!(
+            entry.kanji_elements().any(|k| k.text == form)
+                || entry.reading_elements().any(|r| r.text == form),
+            "entry {} does not match {:?}",
+            entry.number,
+            form
+        );
+        if actual.last() != Some(&span) {
+            actual.push(span);
+        }
+    }
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_headword_lengths() {
+    let mut expected = std::collections::BTreeMap::<char, Vec<usize>>::new();
+    for entry in entries() {
+        let kebs = entry.kanji_elements().map(|k| k.text);
+        for text in kebs.chain(entry.reading_elements().map(|r| r.text)) {
+            let first_char = text.chars().next().unwrap();
+            expected
+                .entry(first_char)
+                .or_default()
+                .push(text.chars().count());
+        }
+    }
+
+    for (first_char, lengths) in expected {
+        let actual = payload::headword_lengths(first_char);
+        assert_eq!(actual.max(), *lengths.iter().max().unwrap());
+        for len in 1..=actual.max() {
+            //lengths of 32 chars and more share a bit, so they cannot be checked exactly
+            if len < 32 {
+                assert_eq!(actual.contains(len), lengths.contains(&len));
+            } else if lengths.contains(&len) {
+                assert!(actual.contains(len));
+            }
+        }
+    }
+
+    //characters that do not start any headword are not indexed
+    assert_eq!(payload::headword_lengths('\u{0}').max(), 0);
+}
+
+#[test]
+fn test_complete() {
+    for prefix in &["にち", "お", "ア", "日"] {
+        let k = 10;
+        let actual = complete(prefix, k);
+        assert!(actual.len() <= k);
+
+        //compute the expected result by brute force
+        let mut candidates: Vec<&str> = entries()
+            .flat_map(|e| {
+                let kebs = e.kanji_elements().map(|k| k.text);
+                kebs.chain(e.reading_elements().map(|r| r.text))
+            })
+            .filter(|text| text.starts_with(prefix))
+            .collect();
+        candidates.sort_unstable();
+        candidates.dedup();
+        assert_eq!(actual.len(), candidates.len().min(k));
+
+        //all results must be at least as common as all omitted candidates
+        let score = |text: &str| {
+            let entries = payload::entries_with_headword(text).into();
+            lookup::commonness_of_headword(text, entries)
+        };
+        let worst_score = actual.iter().map(|t| score(t)).min().unwrap_or_default();
+        for text in &candidates {
+            if !actual.contains(text) {
+                assert!(score(text) <= worst_score, "{} was omitted", text);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_tagged_entries() {
+    let has_pos = |e: &Entry, pos| {
+        e.senses()
+            .flat_map(|s| s.parts_of_speech())
+            .any(|p| p == pos)
+    };
+    let has_info = |e: &Entry, info| e.senses().flat_map(|s| s.infos()).any(|i| i == info);
+    let numbers = |iter: IndexedEntries| iter.map(|e| e.number).collect::<Vec<_>>();
+
+    let expected: Vec<u32> = entries()
+        .filter(|e| has_pos(e, PartOfSpeech::Expression))
+        .map(|e| e.number)
+        .collect();
+    assert_eq!(numbers(expressions()), expected);
+
+    let expected: Vec<u32> = entries()
+        .filter(|e| has_info(e, SenseInfo::Proverb))
+        .map(|e| e.number)
+        .collect();
+    assert_eq!(numbers(proverbs()), expected);
+
+    let expected: Vec<u32> = entries()
+        .filter(|e| has_info(e, SenseInfo::Yojijukugo))
+        .map(|e| e.number)
+        .collect();
+    assert_eq!(numbers(yojijukugo()), expected);
+}
+
+#[test]
+fn test_stats() {
+    assert_eq!(ENTRY_COUNT, entries().count());
+    assert_eq!(SENSE_COUNT, entries().flat_map(|e| e.senses()).count());
+    let glosses = || entries().flat_map(|e| e.senses()).flat_map(|s| s.glosses());
+    assert_eq!(GLOSS_COUNT, glosses().count());
+    for &language in GlossLanguage::all_variants() {
+        let expected = glosses().filter(|g| g.language == language).count();
+        assert_eq!(
+            gloss_count(language),
+            expected,
+            "language was {:?}",
+            language
+        );
+    }
+}
+
+#[test]
+fn test_entries_sharing_form() {
+    for text in &[
+        "一日",
+        "日",
+        "あそこ",
+        "お母さん",
+        "おかあさん",
+        "存在しない",
+    ] {
+        let expected: Vec<u32> = entries()
+            .filter(|e| {
+                e.kanji_elements().any(|k| k.text == *text)
+                    || e.reading_elements().any(|r| r.text == *text)
+            })
+            .map(|e| e.number)
+            .collect();
+        let group = match entries_sharing_form(text) {
+            Some(group) => group,
+            None => {
+                assert!(expected.is_empty(), "form was {}", text);
+                continue;
+            }
+        };
+        assert_eq!(group.form, *text);
+        let actual: Vec<u32> = group.members.iter().map(|m| m.entry.number).collect();
+        assert_eq!(expected, actual, "form was {}", text);
+
+        for member in &group.members {
+            assert!(!member.reading_elements.is_empty(), "form was {}", text);
+            match member.kanji_element {
+                Some(k) => assert_eq!(k.text, *text),
+                None => assert!(member.reading_elements.iter().all(|r| r.text == *text)),
+            }
+        }
+    }
+}
+
+#[test]
+#[cfg(feature = "bigram-index")]
+fn test_entries_with_headword_containing() {
+    for needle in &["", "お", "曜日", "かあさ", "日本語", "ーア", "xyz"] {
+        let expected: Vec<u32> = entries()
+            .filter(|e| {
+                e.kanji_elements().any(|k| k.text.contains(needle))
+                    || e.reading_elements().any(|r| r.text.contains(needle))
+            })
+            .map(|e| e.number)
+            .collect();
+        let actual: Vec<u32> = entries_with_headword_containing(needle)
+            .into_iter()
+            .map(|e| e.number)
+            .collect();
+        assert_eq!(expected, actual, "needle was {:?}", needle);
+    }
+}
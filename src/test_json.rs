@@ -0,0 +1,47 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use crate::*;
+
+#[test]
+fn test_entry_to_json() {
+    for entry in entries().take(100) {
+        let value: serde_json::Value = serde_json::from_str(&entry.to_json()).unwrap();
+        assert_eq!(value["number"], entry.number);
+
+        let rebs = value["reading_elements"].as_array().unwrap();
+        assert_eq!(rebs.len(), entry.reading_elements().len());
+        for (r, v) in entry.reading_elements().zip(rebs) {
+            assert_eq!(v["text"], r.text);
+            let bucket = v["priority"]["frequency_bucket"].as_u64();
+            assert_eq!(bucket, r.priority.frequency_bucket.map(u64::from));
+        }
+
+        let senses = value["senses"].as_array().unwrap();
+        assert_eq!(senses.len(), entry.senses().len());
+        for (s, v) in entry.senses().zip(senses) {
+            let pos: Vec<_> = s.parts_of_speech().map(|p| p.code()).collect();
+            assert_eq!(v["parts_of_speech"], serde_json::json!(pos));
+            let glosses: Vec<_> = s.glosses().map(|g| g.text).collect();
+            let actual: Vec<_> = v["glosses"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .map(|g| &g["text"])
+                .collect();
+            assert_eq!(actual, glosses);
+        }
+
+        //with the `serde` feature, both representations must be identical
+        #[cfg(feature = "serde")]
+        assert_eq!(value, serde_json::to_value(entry).unwrap());
+    }
+
+    //strings must be escaped correctly
+    let mut out = String::new();
+    json::WriteJson::write_json(&"a\"b\\c\nd\u{1}", &mut out);
+    assert_eq!(out, r#""a\"b\\c\nd\u0001""#);
+}
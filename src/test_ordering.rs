@@ -4,13 +4,74 @@
 * Refer to the file "LICENSE" for details.
 *******************************************************************************/
 
-use crate::entries;
+use crate::{database_info, entries, EntryOrder};
 
+#[cfg(not(feature = "order-by-frequency"))]
 #[test]
 fn test_entry_order() {
+    assert_eq!(database_info().entry_order, EntryOrder::SequenceNumber);
     let mut prev = 0;
     for entry in entries() {
         assert!(entry.number > prev, "{} comes after {}", entry.number, prev);
         prev = entry.number;
     }
 }
+
+#[cfg(feature = "order-by-frequency")]
+#[test]
+fn test_entry_order() {
+    assert_eq!(database_info().entry_order, EntryOrder::Frequency);
+    if cfg!(feature = "exclude-priority") {
+        //the priorities that the order is based on are not available at runtime
+        return;
+    }
+    let keys: Vec<_> = entries()
+        .map(|entry| {
+            let kanji_scores = entry.kanji_elements().map(|k| k.priority.score());
+            let reading_scores = entry.reading_elements().map(|r| r.priority.score());
+            let score = kanji_scores.chain(reading_scores).max().unwrap_or_default();
+            (std::cmp::Reverse(score), entry.number)
+        })
+        .collect();
+    for pair in keys.windows(2) {
+        assert!(pair[0] < pair[1], "{:?} comes after {:?}", pair[1], pair[0]);
+    }
+}
+
+#[test]
+fn test_entries_double_ended() {
+    let numbers: Vec<u32> = entries().map(|e| e.number).collect();
+    let len = numbers.len();
+
+    let mut reversed: Vec<u32> = entries().rev().map(|e| e.number).collect();
+    reversed.reverse();
+    assert_eq!(reversed, numbers);
+
+    //random access via nth() and nth_back(), including out-of-range indexes
+    for &n in &[
+        0,
+        1,
+        len / 2,
+        len.saturating_sub(1),
+        len,
+        len + 1,
+        usize::MAX,
+    ] {
+        let expected_back = len.checked_sub(n).and_then(|idx| idx.checked_sub(1));
+        assert_eq!(entries().nth(n).map(|e| e.number), numbers.get(n).copied());
+        assert_eq!(
+            entries().nth_back(n).map(|e| e.number),
+            expected_back.map(|idx| numbers[idx])
+        );
+    }
+
+    //both ends can be consumed from the same iterator without overlap
+    let mut iter = entries();
+    let mut expected = numbers.iter().copied();
+    while iter.len() > 0 {
+        assert_eq!(iter.next().map(|e| e.number), expected.next());
+        assert_eq!(iter.next_back().map(|e| e.number), expected.next_back());
+        assert_eq!(iter.len(), expected.len());
+    }
+    assert_eq!(entries().last().map(|e| e.number), numbers.last().copied());
+}
@@ -0,0 +1,65 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use jmdict_traverse::RadkFile;
+
+#[test]
+fn test_radkfile_parsing() {
+    let contents = "
+# comments and empty lines are ignored
+
+$ 一 1
+七丁万三
+上下 不
+$ 日 4 js01
+明早旦明
+$ 口 3
+";
+    let radk = RadkFile::parse(contents).unwrap();
+    let actual: Vec<_> = radk
+        .radicals
+        .iter()
+        .map(|r| (r.text, r.stroke_count, r.kanji.iter().collect::<String>()))
+        .collect();
+    let expected = vec![
+        ('一', 1, "丁七万三上下不".to_string()),
+        ('日', 4, "旦早明".to_string()),
+        ('口', 3, String::new()),
+    ];
+    assert_eq!(actual, expected);
+
+    assert!(RadkFile::parse("七丁万三\n$ 一 1\n").is_err());
+    assert!(RadkFile::parse("$ 一\n").is_err());
+    assert!(RadkFile::parse("$ 一二 1\n").is_err());
+}
+
+#[cfg(feature = "radicals")]
+#[test]
+fn test_radicals() {
+    use crate::*;
+
+    //the embedded radicals must be the same as in the RADKFILE given at build time
+    let expected = RadkFile::from_env().map_or_else(Vec::new, |radk| radk.radicals);
+    let actual: Vec<_> = radicals().collect();
+    assert_eq!(actual.len(), expected.len());
+    for (a, e) in actual.iter().zip(expected.iter()) {
+        assert_eq!(a.text, e.text);
+        assert_eq!(a.stroke_count, e.stroke_count);
+        assert_eq!(a.kanji().collect::<Vec<_>>(), e.kanji);
+    }
+
+    assert_eq!(kanji_by_components(&[]), Vec::<char>::new());
+    for radical in actual.iter().take(20) {
+        let kanji = kanji_by_components(&[radical.text]);
+        assert_eq!(kanji, radical.kanji().collect::<Vec<_>>());
+        for &k in kanji.iter().take(20) {
+            assert!(components_of(k).any(|r| r == *radical));
+            //every kanji is found by the combination of all its components
+            let components: Vec<char> = components_of(k).map(|r| r.text).collect();
+            assert!(kanji_by_components(&components).contains(&k));
+        }
+    }
+}
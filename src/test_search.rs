@@ -0,0 +1,144 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use crate::search::fold;
+use crate::*;
+
+#[test]
+fn test_search_hits() {
+    for query in &["母", "かあ", "mother"] {
+        let hits: Vec<SearchHit> = search(query).collect();
+
+        //every hit must point to an occurrence of the query (modulo folding in glosses)
+        for hit in &hits {
+            let matched = &hit.text()[hit.span.clone()];
+            match hit.element {
+                MatchedElement::Gloss { .. } => assert_eq!(fold(matched).0, fold(query).0),
+                _ => assert_eq!(matched, *query),
+            }
+        }
+
+        //every element containing the query must be reported exactly once
+        let expected_count: usize = entries()
+            .map(|e| {
+                let kebs = e.kanji_elements().filter(|k| k.text.contains(query));
+                let rebs = e.reading_elements().filter(|r| r.text.contains(query));
+                let glosses = e
+                    .senses()
+                    .flat_map(|s| s.glosses())
+                    .filter(|g| fold(g.text).0.contains(&fold(query).0));
+                kebs.count() + rebs.count() + glosses.count()
+            })
+            .sum();
+        assert_eq!(hits.len(), expected_count, "query was {}", query);
+    }
+
+    assert_eq!(search("").count(), 0);
+}
+
+#[test]
+fn test_search_folding() {
+    //queries are folded the same way as glosses
+    let count = search("mother").count();
+    assert_eq!(search("MOTHER").count(), count);
+    if cfg!(feature = "diacritic-folding") {
+        assert_eq!(search("Mothér").count(), count);
+    }
+
+    //every gloss hit must fold to the same text as the query, and spans must refer to the original
+    //text (which may have a different length than the folded text)
+    for query in &["cafe", "naive", "e"] {
+        for hit in search(query) {
+            let matched = &hit.text()[hit.span.clone()];
+            assert_eq!(fold(matched).0, *query);
+        }
+    }
+
+    if cfg!(feature = "diacritic-folding") {
+        assert_eq!(fold("Sérénade").0, "serenade");
+    } else {
+        assert_eq!(fold("Sérénade").0, "sérénade");
+    }
+    assert_eq!(fold("Москва").0, "москва");
+    assert_eq!(fold("がっこう").0, "がっこう");
+}
+
+#[cfg(all(feature = "stemming", feature = "translations-eng"))]
+#[test]
+fn test_english_stemmer() {
+    let stemmer = EnglishStemmer;
+    let groups: &[&[&str]] = &[
+        &["run", "runs", "running"],
+        &["make", "makes", "making"],
+        &["carry", "carries", "carried"],
+        &["watch", "watches", "watched", "watching"],
+        &["fall", "falls", "falling"],
+        &["stop", "stops", "stopped", "stopping"],
+    ];
+    for group in groups {
+        let stem = stemmer.stem(group[0]);
+        for word in group.iter() {
+            assert_eq!(stemmer.stem(word), stem, "stem of {:?}", word);
+        }
+    }
+    //short words and non-ASCII words are left alone
+    for word in &["bus", "glass", "this", "café"] {
+        assert_eq!(stemmer.stem(word), *word);
+    }
+
+    //search() uses the stemmer for English glosses (お辞儀 has the gloss "bow")
+    let hits: Vec<_> = search("bows").collect();
+    assert!(hits.iter().any(|hit| hit.text() == "bow"));
+    for hit in hits {
+        let matched = fold(&hit.text()[hit.span.clone()]).0;
+        assert!(matched.contains("bows") || stemmer.stem(&matched) == "bow");
+    }
+}
+
+#[test]
+fn test_fuzzy_katakana() {
+    use crate::search::fold_katakana;
+    let equivalent: &[&[&str]] = &[
+        &["コンピューター", "コンピュータ"],
+        &["メール", "メイル", "メエル"],
+        &["ボーリング", "ボウリング"],
+        &["ヴァイオリン", "バイオリン"],
+        &["ヴィールス", "ビールス", "ビルス"],
+    ];
+    for group in equivalent {
+        let folded = fold_katakana(group[0]).0;
+        for text in group.iter() {
+            assert_eq!(fold_katakana(text).0, folded, "fold of {:?}", text);
+        }
+    }
+    //offsets must refer to the original text
+    let (folded, offsets) = fold_katakana("ヴァー");
+    assert_eq!(folded, "バ");
+    assert_eq!(offsets, vec![(0, 6); 3]);
+
+    let opts = SearchOptions {
+        fuzzy_katakana: true,
+        ..SearchOptions::default()
+    };
+    for query in &["ヴァ", "ボール"] {
+        //without the option, matching is exact
+        for hit in search(query) {
+            assert!(hit.text()[hit.span.clone()].contains(query));
+        }
+        for hit in search_with_options(query, opts) {
+            let matched = &hit.text()[hit.span.clone()];
+            assert_eq!(fold_katakana(matched).0, fold_katakana(query).0);
+        }
+        assert!(search_with_options(query, opts).count() >= search(query).count());
+    }
+
+    //queries that are empty after folding do not match any headwords (and in particular do not
+    //panic); glosses are matched without katakana folding, so "ー" could still appear there
+    for hit in search_with_options("ー", opts) {
+        assert!(matches!(hit.element, MatchedElement::Gloss { .. }));
+    }
+    assert_eq!(search_with_options("", opts).count(), 0);
+}
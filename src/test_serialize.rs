@@ -0,0 +1,52 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use crate::*;
+
+#[test]
+fn test_serialize_entries() {
+    for entry in entries().take(100) {
+        let value = serde_json::to_value(entry).unwrap();
+        assert_eq!(value["number"], entry.number);
+
+        let kebs = value["kanji_elements"].as_array().unwrap();
+        assert_eq!(kebs.len(), entry.kanji_elements().len());
+        for (k, v) in entry.kanji_elements().zip(kebs) {
+            assert_eq!(v["text"], k.text);
+            assert_eq!(v["infos"].as_array().unwrap().len(), k.infos().len());
+        }
+
+        let rebs = value["reading_elements"].as_array().unwrap();
+        assert_eq!(rebs.len(), entry.reading_elements().len());
+        for (r, v) in entry.reading_elements().zip(rebs) {
+            assert_eq!(v["text"], r.text);
+            assert_eq!(v["no_kanji"], r.no_kanji);
+        }
+
+        let senses = value["senses"].as_array().unwrap();
+        assert_eq!(senses.len(), entry.senses().len());
+        for (s, v) in entry.senses().zip(senses) {
+            let glosses = v["glosses"].as_array().unwrap();
+            assert_eq!(glosses.len(), s.glosses().len());
+            for (g, v) in s.glosses().zip(glosses) {
+                assert_eq!(v["text"], g.text);
+                assert_eq!(v["language"], g.language.code());
+            }
+        }
+    }
+}
+
+#[test]
+fn test_enum_round_trip() {
+    for &pos in PartOfSpeech::all_variants() {
+        let json = serde_json::to_string(&pos).unwrap();
+        assert_eq!(json, format!("{:?}", pos.code()));
+        assert_eq!(serde_json::from_str::<PartOfSpeech>(&json).unwrap(), pos);
+    }
+    let info: SenseInfo = serde_json::from_str("\"yoji\"").unwrap();
+    assert_eq!(info, SenseInfo::Yojijukugo);
+    assert!(serde_json::from_str::<SenseInfo>("\"Yojijukugo\"").is_err());
+}
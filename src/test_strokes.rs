@@ -0,0 +1,72 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use jmdict_traverse::KanjiVgFile;
+
+#[test]
+fn test_kanjivg_parsing() {
+    let contents = r#"<?xml version="1.0" encoding="UTF-8"?>
+<kanjivg xmlns:kvg='http://kanjivg.tagaini.net'>
+<kanji id="kvg:kanji_04e8c">
+<g id="kvg:04e8c" kvg:element="二">
+	<path id="kvg:04e8c-s2" kvg:type="㇐" d="M12,80c2,0 50,-2 85,-1"/>
+	<path id="kvg:04e8c-s1" kvg:type="㇐" d="M25,30c1,0 40,-2 60,-1"/>
+</g>
+</kanji>
+<kanji id="kvg:kanji_04e00">
+<g id="kvg:04e00" kvg:element="一">
+	<path id="kvg:04e00-s1" kvg:type="㇐" d="M11,54c20,0 50,-5 86,-4"/>
+</g>
+</kanji>
+<kanji id="kvg:kanji_04e00-Kaisho">
+<g id="kvg:04e00-Kaisho" kvg:element="一">
+	<path id="kvg:04e00-Kaisho-s1" kvg:type="㇐" d="M10,50c20,0 50,-5 86,-4"/>
+</g>
+</kanji>
+</kanjivg>
+"#;
+    let file = KanjiVgFile::parse(contents.as_bytes()).unwrap();
+    let actual: Vec<_> = file
+        .characters
+        .iter()
+        .map(|c| (c.text, c.strokes.clone()))
+        .collect();
+    let expected = vec![
+        ('一', vec!["M11,54c20,0 50,-5 86,-4".to_string()]),
+        (
+            '二',
+            vec![
+                "M25,30c1,0 40,-2 60,-1".to_string(),
+                "M12,80c2,0 50,-2 85,-1".to_string(),
+            ],
+        ),
+    ];
+    assert_eq!(actual, expected);
+
+    //gaps in the stroke numbers are reported
+    let contents = r#"<g><path id="kvg:04e00-s2" d="M11,54"/></g>"#;
+    assert!(KanjiVgFile::parse(contents.as_bytes()).is_err());
+    let contents = r#"<g><path id="kvg:04e00-s1"/></g>"#;
+    assert!(KanjiVgFile::parse(contents.as_bytes()).is_err());
+}
+
+#[cfg(feature = "stroke-order")]
+#[test]
+fn test_stroke_order() {
+    use crate::*;
+
+    //the embedded strokes must be the same as in the KanjiVG file given at build time
+    let file = KanjiVgFile::from_env();
+    let characters = file.as_ref().map_or(&[][..], |f| &f.characters[..]);
+    for c in characters {
+        let strokes: Vec<_> = stroke_order(c.text).unwrap().collect();
+        assert_eq!(strokes, c.strokes);
+        assert_eq!(stroke_order(c.text).unwrap().len(), c.strokes.len());
+    }
+    if characters.iter().all(|c| c.text != '\u{10FFFF}') {
+        assert!(stroke_order('\u{10FFFF}').is_none());
+    }
+}
@@ -0,0 +1,40 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use crate::variants::*;
+use crate::*;
+
+#[test]
+fn test_variants_table() {
+    //the table must be sorted for binary search, and normalize_variants() relies on all
+    //replacements having the same length in UTF-8
+    for pair in VARIANTS.windows(2) {
+        assert!(pair[0].0 < pair[1].0, "{:?} is not sorted", pair);
+    }
+    for &(kyujitai, shinjitai) in VARIANTS {
+        assert!(kyujitai as u32 >= 0x4E00);
+        assert_eq!(kyujitai.len_utf8(), shinjitai.len_utf8());
+        //mapping must be idempotent
+        assert!(VARIANTS.iter().all(|&(k, _)| k != shinjitai));
+    }
+
+    assert_eq!(normalize_variants("舊國"), "旧国");
+    assert_eq!(normalize_variants("旧国"), "旧国");
+    assert_eq!(normalize_variants("きゅうこく"), "きゅうこく");
+}
+
+#[test]
+fn test_variants_in_lookup() {
+    //"お参り" should be found when written with kyūjitai
+    let text = "お參り";
+    let spans: Vec<_> = segment(text).map(|(span, _)| span).collect();
+    assert!(spans.contains(&(0..text.len())));
+    assert!(segment(text).any(|(_, e)| e.kanji_elements().any(|k| k.text == "お参り")));
+
+    assert!(search(text).any(|hit| hit.text() == "お参り" && hit.span == (0..text.len())));
+
+    assert!(complete("お參", 10).contains(&"お参り"));
+}
@@ -0,0 +1,44 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use crate::*;
+
+fn transitivity(entry: &Entry) -> Option<PartOfSpeech> {
+    use PartOfSpeech::*;
+    let mut pos = entry.senses().flat_map(|s| s.parts_of_speech());
+    pos.find(|p| matches!(p, TransitiveVerb | IntransitiveVerb))
+}
+
+#[test]
+fn test_transitivity_pair() {
+    for entry in entries() {
+        let pair = match entry.transitivity_pair() {
+            Some(pair) => pair,
+            None => continue,
+        };
+        assert_ne!(pair.number, entry.number);
+        assert!(transitivity(&pair).is_some());
+        assert_ne!(transitivity(&pair), transitivity(&entry));
+    }
+}
+
+//NOTE: This needs the full database, but is most relevant with the `scope-uncommon` and
+//`scope-archaic` features, which add obscure homographs that must not be chosen as partners
+//(e.g. 上がり込む for 上げる).
+#[cfg(not(feature = "db-minimal"))]
+#[test]
+fn test_transitivity_pair_prefers_common_partner() {
+    let find = |text: &str| {
+        entries()
+            .find(|e| e.kanji_elements().any(|k| k.text == text))
+            .unwrap()
+    };
+    for &(a, b) in &[("上げる", "上がる"), ("上がる", "上げる")] {
+        let pair = find(a).transitivity_pair().unwrap();
+        let texts: Vec<_> = pair.kanji_elements().map(|k| k.text).collect();
+        assert!(texts.contains(&b), "pair of {} was {:?}", a, texts);
+    }
+}
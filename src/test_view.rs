@@ -0,0 +1,67 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use crate::*;
+
+#[test]
+fn test_with_languages() {
+    //with all languages, only entries and senses without any glosses are skipped
+    let all_languages: Vec<GlossLanguage> = EnumSet::<GlossLanguage>::all().iter().collect();
+    let view = with_languages(&all_languages);
+    let expected: Vec<u32> = entries()
+        .filter(|e| e.senses().any(|s| s.glosses().next().is_some()))
+        .map(|e| e.number)
+        .collect();
+    let actual: Vec<u32> = view.entries().map(|e| e.entry.number).collect();
+    assert_eq!(actual, expected);
+    for entry in view.entries().take(100) {
+        let all_glosses: Vec<_> = entry.entry.senses().flat_map(|s| s.glosses()).collect();
+        assert_eq!(entry.glosses().count(), all_glosses.len());
+    }
+
+    //with no languages, nothing remains
+    assert_eq!(with_languages(&[]).entries().count(), 0);
+
+    //with a single language, only glosses in that language remain
+    for &language in &all_languages {
+        let view = with_languages(&[language]);
+        assert_eq!(view.languages().iter().collect::<Vec<_>>(), vec![language]);
+        for entry in view.entries() {
+            for sense in entry.senses() {
+                assert!(sense.glosses().next().is_some());
+                assert!(sense.glosses().all(|g| g.language == language));
+                assert_eq!(
+                    sense.glosses().count(),
+                    sense.sense.glosses_in(language).count()
+                );
+            }
+        }
+        let expected = entries()
+            .filter(|e| e.glosses_in(language).next().is_some())
+            .count();
+        assert_eq!(view.entries().count(), expected);
+    }
+}
+
+#[test]
+fn test_view_entry() {
+    let view = with_languages(&[]);
+    for entry in entries().take(100) {
+        assert!(view.entry(entry).is_none());
+    }
+    let all_languages: Vec<GlossLanguage> = EnumSet::<GlossLanguage>::all().iter().collect();
+    let view = with_languages(&all_languages);
+    for entry in entries_containing_kanji('日') {
+        let senses = view.entry(entry).map_or(0, |e| e.senses().count());
+        assert_eq!(
+            senses,
+            entry
+                .senses()
+                .filter(|s| s.glosses().next().is_some())
+                .count()
+        );
+    }
+}
@@ -0,0 +1,47 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+use crate::*;
+
+#[test]
+fn test_parse_cross_reference() {
+    let cases = [
+        ("丸・まる・1", "丸", Some("まる"), Some(1)),
+        ("二重丸・にじゅうまる", "二重丸", Some("にじゅうまる"), None),
+        ("どこ・1", "どこ", None, Some(1)),
+        ("ワン・ツー", "ワン・ツー", None, None),
+        ("日曜", "日曜", None, None),
+    ];
+    for &(text, headword, reading, sense_number) in cases.iter() {
+        let expected = CrossReference {
+            text,
+            headword,
+            reading,
+            sense_number,
+        };
+        assert_eq!(CrossReference::parse(text), expected);
+    }
+}
+
+#[test]
+fn test_resolve_cross_references() {
+    let xrefs = entries()
+        .flat_map(|e| e.senses())
+        .flat_map(|s| s.cross_references().chain(s.antonyms()));
+    for xref in xrefs {
+        if let Some(entry) = xref.resolve() {
+            let kebs = entry.kanji_elements().map(|k| k.text);
+            let rebs = entry.reading_elements().map(|r| r.text);
+            assert!(kebs.chain(rebs).any(|text| text == xref.headword));
+            if let Some(reading) = xref.reading {
+                assert!(entry.reading_elements().any(|r| r.text == reading));
+            }
+        }
+        if let Some((_, sense)) = xref.resolve_sense() {
+            assert_eq!(Some(sense.number), xref.sense_number);
+        }
+    }
+}
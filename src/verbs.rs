@@ -0,0 +1,104 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains helper functions for verbs.
+
+use crate::*;
+
+pub(crate) fn transitivity_pair(entry: &Entry) -> Option<Entry> {
+    use PartOfSpeech::*;
+    let wanted = match transitivity(entry)? {
+        TransitiveVerb => IntransitiveVerb,
+        _ => TransitiveVerb,
+    };
+    let is_candidate =
+        |other: &Entry| other.number != entry.number && transitivity(other) == Some(wanted);
+
+    //prefer pairs that are recorded in the JMdict
+    let xrefs = entry.senses().flat_map(|s| s.cross_references());
+    let candidates = xrefs.filter_map(|x| x.resolve()).filter(is_candidate);
+    if let Some(other) = best_candidate(candidates.map(|e| (e, 0))) {
+        return Some(other);
+    }
+
+    //otherwise look for a verb that shares the kanji and their reading, but not the okurigana
+    //(e.g. 上がる/あがる and 上げる/あげる both have the stem 上/あ)
+    let stems: Vec<_> = entry
+        .forms()
+        .filter_map(|(k, r)| stem_of(k.text, r.text))
+        .collect();
+    let first_kanji = stems.first()?.0.chars().next()?;
+    let candidates = entries_containing_kanji(first_kanji)
+        .filter(is_candidate)
+        .filter_map(|other| {
+            //among verbs with the same stem, prefer those whose okurigana has a similar length
+            //(e.g. 上がる instead of 上がり込む for 上げる)
+            let distance = other
+                .forms()
+                .filter_map(|(k, r)| stem_of(k.text, r.text))
+                .filter_map(|(k, r, okurigana)| {
+                    let (_, _, own_okurigana) = stems.iter().find(|s| s.0 == k && s.1 == r)?;
+                    let (a, b) = (okurigana.chars().count(), own_okurigana.chars().count());
+                    Some(a.max(b) - a.min(b))
+                })
+                .min()?;
+            Some((other, distance))
+        });
+    best_candidate(candidates)
+}
+
+///Picks the most suitable partner from the given candidates (each with a distance from the
+///original verb). Common entries are preferred over uncommon and archaic ones, since the latter
+///are usually obscure homographs of the actual partner. Among equally suitable candidates, the
+///one with the smallest distance and then the one that comes first wins.
+fn best_candidate(candidates: impl Iterator<Item = (Entry, usize)>) -> Option<Entry> {
+    let is_archaic = |e: &Entry| {
+        e.senses()
+            .all(|s| s.infos().any(|i| i == SenseInfo::Archaism))
+    };
+    candidates
+        .enumerate()
+        .min_by_key(|(idx, (e, distance))| {
+            let is_common = e.kanji_elements().any(|k| k.priority.is_common())
+                || e.reading_elements().any(|r| r.priority.is_common());
+            let has_priority = e
+                .kanji_elements()
+                .any(|k| k.priority != Priority::default())
+                || e.reading_elements()
+                    .any(|r| r.priority != Priority::default());
+            (!is_common, !has_priority, is_archaic(e), *distance, *idx)
+        })
+        .map(|(_, (e, _))| e)
+}
+
+///Returns the part of speech (either `TransitiveVerb` or `IntransitiveVerb`) that describes the
+///transitivity of the first sense of this entry that has either.
+fn transitivity(entry: &Entry) -> Option<PartOfSpeech> {
+    use PartOfSpeech::*;
+    let mut pos = entry.senses().flat_map(|s| s.parts_of_speech());
+    pos.find(|p| matches!(p, TransitiveVerb | IntransitiveVerb))
+}
+
+///Splits off the okurigana from a kanji element and the corresponding reading element, e.g.
+///("上がる", "あがる") becomes ("上", "あ", "がる").
+fn stem_of(
+    kanji_text: &'static str,
+    reading: &'static str,
+) -> Option<(&'static str, &'static str, &'static str)> {
+    let is_hiragana = |c: char| ('\u{3041}'..='\u{309F}').contains(&c);
+    let okurigana_len: usize = kanji_text
+        .chars()
+        .rev()
+        .take_while(|&c| is_hiragana(c))
+        .map(|c| c.len_utf8())
+        .sum();
+    if okurigana_len == 0 || okurigana_len == kanji_text.len() {
+        return None;
+    }
+    let split = kanji_text.len() - okurigana_len;
+    let reading_stem = reading.strip_suffix(&kanji_text[split..])?;
+    Some((&kanji_text[..split], reading_stem, &kanji_text[split..]))
+}
@@ -0,0 +1,117 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains [DictionaryView], which restricts the database to glosses in certain
+//! languages at runtime.
+
+use crate::*;
+
+///Returns a view of the database that only contains glosses in the given languages.
+///
+///When the crate is built with multiple `translations-*` features, each sense usually contains
+///glosses in several languages. Applications that only want to show some of these languages (e.g.
+///the one selected by the user) can go through this view instead of filtering the glosses manually:
+///
+///```
+///# #[cfg(feature = "translations-eng")] {
+///use jmdict::GlossLanguage;
+///let view = jmdict::with_languages(&[GlossLanguage::English]);
+///for entry in view.entries().take(10) {
+///    for sense in entry.senses() {
+///        assert!(sense.glosses().all(|g| g.language == GlossLanguage::English));
+///    }
+///}
+///# }
+///```
+pub fn with_languages(languages: &[GlossLanguage]) -> DictionaryView {
+    DictionaryView {
+        languages: languages.iter().collect(),
+    }
+}
+
+///A view of the database that only contains glosses in certain languages. See [with_languages()].
+///
+///Senses without any glosses in these languages are skipped, and so are entries without any such
+///senses.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DictionaryView {
+    languages: EnumSet<GlossLanguage>,
+}
+
+impl DictionaryView {
+    ///Returns the languages whose glosses are included in this view.
+    pub fn languages(&self) -> EnumSet<GlossLanguage> {
+        self.languages
+    }
+
+    ///Like [jmdict::entries()](crate::entries), but only yields those entries that are included in
+    ///this view.
+    pub fn entries(&self) -> impl Iterator<Item = EntryView> {
+        let view = *self;
+        crate::entries().filter_map(move |e| view.entry(e))
+    }
+
+    ///Returns the view of the given entry, or None if the entry does not have any glosses in the
+    ///languages of this view. This can be used on the results of lookups and searches, e.g.
+    ///`entries_containing_kanji('日').filter_map(|e| view.entry(e))`.
+    pub fn entry(&self, entry: Entry) -> Option<EntryView> {
+        let view = EntryView {
+            entry,
+            languages: self.languages,
+        };
+        if view.senses().next().is_some() {
+            Some(view)
+        } else {
+            None
+        }
+    }
+}
+
+///An [Entry] as seen through a [DictionaryView].
+#[derive(Clone, Copy, Debug)]
+pub struct EntryView {
+    ///The underlying entry. Its own methods, e.g. [Entry::senses()], yield glosses in all
+    ///languages.
+    pub entry: Entry,
+    languages: EnumSet<GlossLanguage>,
+}
+
+impl EntryView {
+    ///Like [Entry::senses()], but only yields senses that have glosses in the languages of this
+    ///view.
+    pub fn senses(&self) -> impl Iterator<Item = SenseView> {
+        let languages = self.languages;
+        self.entry
+            .senses()
+            .map(move |sense| SenseView { sense, languages })
+            .filter(|s| s.glosses().next().is_some())
+    }
+
+    ///Returns all glosses in the languages of this view across all senses of this entry, in the
+    ///order in which they appear in the JMdict.
+    pub fn glosses(&self) -> impl Iterator<Item = Gloss> {
+        self.senses().flat_map(|s| s.glosses())
+    }
+}
+
+///A [Sense] as seen through a [DictionaryView].
+#[derive(Clone, Copy, Debug)]
+pub struct SenseView {
+    ///The underlying sense. Its own methods, e.g. [Sense::glosses()], yield glosses in all
+    ///languages.
+    pub sense: Sense,
+    languages: EnumSet<GlossLanguage>,
+}
+
+impl SenseView {
+    ///Like [Sense::glosses()], but only yields glosses in the languages of this view.
+    pub fn glosses(&self) -> impl Iterator<Item = Gloss> {
+        let languages = self.languages;
+        self.sense
+            .glosses()
+            .filter(move |g| languages.contains(g.language))
+    }
+}
@@ -0,0 +1,117 @@
+/*******************************************************************************
+* Copyright 2021 Stefan Majewsky <majewsky@gmx.net>
+* SPDX-License-Identifier: Apache-2.0
+* Refer to the file "LICENSE" for details.
+*******************************************************************************/
+
+//! This file contains the structured representation of references between entries.
+
+use crate::*;
+
+///A reference from a [Sense] to another [Entry], or to a specific [Sense] within it. This is used
+///both for [cross-references](Sense::cross_references) and for [antonyms](Sense::antonyms).
+///
+///In the JMdict, these references consist of the text of a [KanjiElement] or [ReadingElement],
+///optionally followed by the text of a [ReadingElement] and/or a sense number to provide a precise
+///target. Where this happens, a katakana middle dot (`・`, U+30FB) is placed between the
+///components, e.g. "丸・まる・1".
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CrossReference {
+    ///The full text of the reference, as it appears in the JMdict.
+    pub text: &'static str,
+    ///The text of a [KanjiElement] or [ReadingElement] of the referenced entry.
+    pub headword: &'static str,
+    ///If given, the text of a [ReadingElement] of the referenced entry. This only appears when
+    ///`headword` is the text of a [KanjiElement].
+    pub reading: Option<&'static str>,
+    ///If given, the referenced sense within the referenced entry. Sense numbers start at 1 and
+    ///count all senses of the entry in the JMdict, including those that are not included in this
+    ///build because of feature flags.
+    pub sense_number: Option<u32>,
+}
+
+impl CrossReference {
+    pub(crate) fn parse(text: &'static str) -> Self {
+        let mut rest = text;
+        let mut sense_number = None;
+        if let Some((head, tail)) = rsplit_dot(rest) {
+            if let Ok(num) = tail.parse() {
+                rest = head;
+                sense_number = Some(num);
+            }
+        }
+
+        //NOTE: Some headwords contain the middle dot themselves (e.g. "ワン・ツー"), so we only
+        //split off a reading if it follows something that cannot be a reading.
+        let mut reading = None;
+        if let Some((head, tail)) = rsplit_dot(rest) {
+            if !is_kana(head) && is_kana(tail) {
+                rest = head;
+                reading = Some(tail);
+            }
+        }
+
+        Self {
+            text,
+            headword: rest,
+            reading,
+            sense_number,
+        }
+    }
+
+    ///Finds the referenced entry in the database. When the reference is ambiguous, the entry with
+    ///the lowest sequence number is returned.
+    ///
+    ///Returns None if no such entry exists in the database. This can happen when the referenced
+    ///entry was excluded from this build by feature flags (e.g. because it is not common).
+    pub fn resolve(&self) -> Option<Entry> {
+        let candidates = IndexedEntries::from(payload::entries_with_headword(self.headword));
+        //if the referenced sense was excluded from this build, the sense number cannot be used for
+        //disambiguation, so we fall back to only looking at the reading
+        //(candidates are not ordered by sequence number with the `order-by-frequency` feature)
+        let (iter1, iter2) = (candidates, candidates);
+        iter1
+            .filter(|entry| self.matches(entry, true))
+            .min_by_key(|entry| entry.number)
+            .or_else(|| {
+                iter2
+                    .filter(|entry| self.matches(entry, false))
+                    .min_by_key(|entry| entry.number)
+            })
+    }
+
+    ///Like [resolve()](CrossReference::resolve), but also finds the referenced sense within the
+    ///referenced entry. Returns None if the reference does not contain a sense number, or if the
+    ///referenced sense was excluded from this build by feature flags.
+    pub fn resolve_sense(&self) -> Option<(Entry, Sense)> {
+        let number = self.sense_number?;
+        let entry = self.resolve()?;
+        let sense = entry.senses().find(|s| s.number == number)?;
+        Some((entry, sense))
+    }
+
+    fn matches(&self, entry: &Entry, check_sense: bool) -> bool {
+        let matches_reading = match self.reading {
+            Some(reading) => entry.reading_elements().any(|r| r.text == reading),
+            None => true,
+        };
+        let matches_sense = match self.sense_number {
+            Some(number) if check_sense => entry.senses().any(|s| s.number == number),
+            _ => true,
+        };
+        matches_reading && matches_sense
+    }
+}
+
+fn rsplit_dot(text: &str) -> Option<(&str, &str)> {
+    let idx = text.rfind('・')?;
+    Some((&text[0..idx], &text[(idx + '・'.len_utf8())..]))
+}
+
+fn is_kana(text: &str) -> bool {
+    !text.is_empty()
+        && text
+            .chars()
+            .all(|c| matches!(c, '\u{3040}'..='\u{30FF}' | '\u{31F0}'..='\u{31FF}'))
+}